@@ -1,6 +1,8 @@
 use cairo_lang_runner::StarknetState;
 use cairo_native::{context::NativeContext, executor::JitNativeExecutor};
+use cairo_native_runtime::FeltDict;
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::{alloc::Layout, collections::HashMap, ffi::c_void, ptr::NonNull};
 use util::{create_vm_runner, prepare_programs};
 
 mod util;
@@ -94,5 +96,65 @@ pub fn bench_libfuncs(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, bench_libfuncs);
+/// Compare the arena-backed dictionary runtime against the previous design (a `HashMap` with the
+/// default hasher and a `malloc` per value).
+pub fn bench_dict_runtime(c: &mut Criterion) {
+    let mut c = c.benchmark_group("Felt252Dict Runtime");
+
+    for num_keys in [100u64, 10_000] {
+        let keys = (0..num_keys)
+            .map(|i| {
+                let mut key = [0u8; 32];
+                key[..8].copy_from_slice(&i.wrapping_mul(0x9E37_79B9_7F4A_7C15).to_le_bytes());
+                key
+            })
+            .collect::<Vec<_>>();
+
+        c.bench_with_input(
+            BenchmarkId::new("HashMap+malloc", num_keys),
+            &keys,
+            |b, keys| {
+                b.iter(|| unsafe {
+                    let mut map = HashMap::<[u8; 32], NonNull<c_void>>::new();
+                    for key in keys {
+                        let value = match map.get(key) {
+                            Some(value) => *value,
+                            None => {
+                                let value = NonNull::new(libc::malloc(32)).unwrap();
+                                map.insert(*key, value);
+                                value
+                            }
+                        };
+                        value.cast::<u64>().as_ptr().write(1);
+                    }
+                    for key in keys {
+                        black_box(map.get(key).unwrap().cast::<u64>().as_ptr().read());
+                    }
+                    for (_, value) in map.drain() {
+                        libc::free(value.as_ptr());
+                    }
+                })
+            },
+        );
+
+        c.bench_with_input(BenchmarkId::new("FeltDict", num_keys), &keys, |b, keys| {
+            b.iter(|| unsafe {
+                let mut dict = FeltDict::new(Layout::new::<[u64; 4]>());
+                for key in keys {
+                    let value = match dict.get(key) {
+                        Some(value) => value,
+                        None => dict.insert(key),
+                    };
+                    value.cast::<u64>().as_ptr().write(1);
+                }
+                for key in keys {
+                    black_box(dict.get(key).unwrap().cast::<u64>().as_ptr().read());
+                }
+                drop(dict);
+            })
+        });
+    }
+}
+
+criterion_group!(benches, bench_libfuncs, bench_dict_runtime);
 criterion_main!(benches);
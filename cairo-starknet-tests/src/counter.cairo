@@ -0,0 +1,59 @@
+use starknet::ContractAddress;
+
+#[starknet::interface]
+trait ICounter<TContractState> {
+    fn get(self: @TContractState) -> u128;
+    fn owner(self: @TContractState) -> ContractAddress;
+    fn increase(ref self: TContractState, amount: u128) -> u128;
+}
+
+#[starknet::contract]
+mod Counter {
+    use starknet::{ContractAddress, get_caller_address};
+
+    #[storage]
+    struct Storage {
+        value: u128,
+        owner: ContractAddress,
+    }
+
+    #[event]
+    #[derive(Drop, starknet::Event)]
+    enum Event {
+        Increased: Increased,
+    }
+
+    #[derive(Drop, starknet::Event)]
+    struct Increased {
+        #[key]
+        caller: ContractAddress,
+        value: u128,
+    }
+
+    #[constructor]
+    fn constructor(ref self: ContractState, owner: ContractAddress, initial_value: u128) {
+        self.owner.write(owner);
+        self.value.write(initial_value);
+    }
+
+    #[abi(embed_v0)]
+    impl Counter of super::ICounter<ContractState> {
+        fn get(self: @ContractState) -> u128 {
+            self.value.read()
+        }
+
+        fn owner(self: @ContractState) -> ContractAddress {
+            self.owner.read()
+        }
+
+        fn increase(ref self: ContractState, amount: u128) -> u128 {
+            let caller = get_caller_address();
+            assert(caller == self.owner.read(), 'not the owner');
+
+            let value = self.value.read() + amount;
+            self.value.write(value);
+            self.emit(Increased { caller, value });
+            value
+        }
+    }
+}
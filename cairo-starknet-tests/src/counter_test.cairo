@@ -0,0 +1,61 @@
+use native_starknet_tests::counter::{Counter, ICounterDispatcher, ICounterDispatcherTrait};
+use starknet::{
+    ContractAddress, contract_address_const, get_caller_address, syscalls::deploy_syscall,
+    testing::{pop_log_raw, set_caller_address, set_contract_address},
+};
+
+fn deploy_counter(owner: ContractAddress, initial_value: u128) -> ICounterDispatcher {
+    let (address, _) = deploy_syscall(
+        Counter::TEST_CLASS_HASH.try_into().unwrap(),
+        0,
+        array![owner.into(), initial_value.into()].span(),
+        false
+    )
+        .unwrap();
+    ICounterDispatcher { contract_address: address }
+}
+
+#[test]
+fn test_deploy() {
+    let owner = contract_address_const::<0x1234>();
+    let counter = deploy_counter(owner, 5);
+
+    assert_eq!(counter.get(), 5);
+    assert_eq!(counter.owner(), owner);
+}
+
+#[test]
+fn test_storage_and_events() {
+    let owner = contract_address_const::<0x1234>();
+    let counter = deploy_counter(owner, 5);
+
+    // Calls from the test are made from its contract address.
+    set_contract_address(owner);
+    assert_eq!(counter.increase(3), 8);
+    assert_eq!(counter.increase(2), 10);
+    assert_eq!(counter.get(), 10);
+
+    let (keys, data) = pop_log_raw(counter.contract_address).unwrap();
+    assert_eq!(keys, array![selector!("Increased"), owner.into()].span());
+    assert_eq!(data, array![8].span());
+    let (_, data) = pop_log_raw(counter.contract_address).unwrap();
+    assert_eq!(data, array![10].span());
+    assert!(pop_log_raw(counter.contract_address).is_none());
+}
+
+#[test]
+#[should_panic(expected: ('not the owner', 'ENTRYPOINT_FAILED'))]
+fn test_not_the_owner() {
+    let counter = deploy_counter(contract_address_const::<0x1234>(), 5);
+
+    set_contract_address(contract_address_const::<0x5678>());
+    counter.increase(1);
+}
+
+#[test]
+fn test_set_caller_address() {
+    assert_eq!(get_caller_address(), contract_address_const::<0>());
+
+    set_caller_address(contract_address_const::<0x1234>());
+    assert_eq!(get_caller_address(), contract_address_const::<0x1234>());
+}
@@ -0,0 +1,2 @@
+mod counter;
+mod counter_test;
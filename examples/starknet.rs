@@ -305,7 +305,12 @@ fn main() {
     let native_executor = JitNativeExecutor::from_native_module(native_program, Default::default());
 
     let result = native_executor
-        .invoke_contract_dynamic(fn_id, &[Felt::from(1)], Some(u128::MAX), SyscallHandler)
+        .invoke_contract_dynamic(
+            fn_id,
+            &[Felt::from(1)],
+            Some(u128::MAX),
+            &mut SyscallHandler,
+        )
         .expect("failed to execute the given contract");
 
     println!();
@@ -0,0 +1,210 @@
+//! # Felt dictionary runtime
+//!
+//! Dictionaries map felts (as their little endian byte representation) to values of a single type,
+//! whose layout is known when the dictionary is created. Values are stored inline in an arena,
+//! which is freed in bulk when the dictionary is dropped.
+//!
+//! Every access made by the compiled code is recorded, together with the value of the key before
+//! and after it, so that squashing the dictionary can charge the same gas as the Cairo VM, which
+//! processes the whole access log of each key, and so that the log can be read back.
+
+use bumpalo::Bump;
+use rustc_hash::FxHashMap;
+use std::{alloc::Layout, ffi::c_void, ptr::NonNull};
+
+/// A `Felt252Dict<T>` as seen by the compiled code.
+#[derive(Debug)]
+pub struct FeltDict {
+    mappings: FxHashMap<[u8; 32], FeltDictEntry>,
+    layout: Layout,
+    arena: Bump,
+
+    total_accesses: u64,
+    /// The key of every recorded access, in order.
+    access_keys: Vec<[u8; 32]>,
+    /// The value before and after every recorded access, `2 * layout.size()` bytes each.
+    access_values: Vec<u8>,
+    /// The value's slot of the last recorded access, until the compiled code finalizes it.
+    pending_access: Option<NonNull<c_void>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct FeltDictEntry {
+    value: NonNull<c_void>,
+    accesses: u64,
+}
+
+impl FeltDict {
+    /// Create an empty dictionary for values with the given layout.
+    pub fn new(layout: Layout) -> Self {
+        Self {
+            mappings: FxHashMap::default(),
+            layout: layout.pad_to_align(),
+            arena: Bump::new(),
+            total_accesses: 0,
+            access_keys: Vec::new(),
+            access_values: Vec::new(),
+            pending_access: None,
+        }
+    }
+
+    /// Return the layout of the values.
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Return the number of entries in the dictionary.
+    pub fn len(&self) -> usize {
+        self.mappings.len()
+    }
+
+    /// Return whether the dictionary is empty.
+    pub fn is_empty(&self) -> bool {
+        self.mappings.is_empty()
+    }
+
+    /// Return a pointer to the value of `key`, if present.
+    pub fn get(&self, key: &[u8; 32]) -> Option<NonNull<c_void>> {
+        self.mappings.get(key).map(|entry| entry.value)
+    }
+
+    /// Return a pointer to the value of `key`, reserving an uninitialized slot in the arena if it
+    /// wasn't present.
+    ///
+    /// The access is not recorded. Use [`Self::insert_and_record`] for accesses made by the program.
+    pub fn insert(&mut self, key: &[u8; 32]) -> NonNull<c_void> {
+        self.entry_mut(key).value
+    }
+
+    /// Return a pointer to the value of `key` and record the access, if present.
+    ///
+    /// The access's new value is read when calling [`Self::finalize_access`].
+    pub fn get_and_record(&mut self, key: &[u8; 32]) -> Option<NonNull<c_void>> {
+        let value = self.mappings.get(key)?.value;
+        self.record_pending_access(key, value, false);
+
+        Some(value)
+    }
+
+    /// Same as [`Self::insert`], but recording the access.
+    ///
+    /// The value of new keys before the access is all zeroes, which is the default value of every
+    /// type a dictionary can hold. The access's new value is read when calling
+    /// [`Self::finalize_access`].
+    pub fn insert_and_record(&mut self, key: &[u8; 32]) -> NonNull<c_void> {
+        let is_new = !self.mappings.contains_key(key);
+        let value = self.entry_mut(key).value;
+        self.record_pending_access(key, value, is_new);
+
+        value
+    }
+
+    /// Read the new value of the last recorded access from its slot, once the compiled code has
+    /// written it.
+    pub fn finalize_access(&mut self) {
+        let Some(value) = self.pending_access.take() else {
+            return;
+        };
+
+        let size = self.layout.size();
+        let offset = self.access_values.len() - size;
+        // SAFETY: The compiled code has written the new value before finalizing the access.
+        let new_value = unsafe { std::slice::from_raw_parts(value.cast::<u8>().as_ptr(), size) };
+        self.access_values[offset..].copy_from_slice(new_value);
+    }
+
+    /// Record an access to `key` with the given values before and after it, which must be
+    /// `layout.size()` bytes long. Used to rebuild squashed dictionaries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values' length doesn't match the layout's size.
+    pub fn record_access(&mut self, key: &[u8; 32], prev_value: &[u8], new_value: &[u8]) {
+        assert_eq!(prev_value.len(), self.layout.size());
+        assert_eq!(new_value.len(), self.layout.size());
+
+        self.entry_mut(key).accesses += 1;
+        self.total_accesses += 1;
+
+        self.access_keys.push(*key);
+        self.access_values.extend_from_slice(prev_value);
+        self.access_values.extend_from_slice(new_value);
+    }
+
+    /// Return the number of recorded accesses to `key`.
+    pub fn accesses(&self, key: &[u8; 32]) -> u64 {
+        self.mappings.get(key).map_or(0, |entry| entry.accesses)
+    }
+
+    /// Return the number of recorded accesses to all keys.
+    pub fn total_accesses(&self) -> u64 {
+        self.total_accesses
+    }
+
+    /// Return the number of recorded accesses which weren't the first one to their key.
+    ///
+    /// This is the amount of accesses for which squashing refunds the difference between the unique
+    /// key and the repeated access costs.
+    pub fn repeated_accesses(&self) -> u64 {
+        let unique_keys = self
+            .mappings
+            .values()
+            .filter(|entry| entry.accesses != 0)
+            .count() as u64;
+
+        self.total_accesses - unique_keys
+    }
+
+    /// Iterate over the keys and pointers to the values of the dictionary.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8; 32], NonNull<c_void>)> {
+        self.mappings.iter().map(|(k, v)| (k, v.value))
+    }
+
+    /// Iterate over the recorded accesses in order, as their key and the bytes of the value before
+    /// and after each of them.
+    pub fn access_log(&self) -> impl Iterator<Item = (&[u8; 32], &[u8], &[u8])> {
+        let size = self.layout.size();
+
+        self.access_keys.iter().enumerate().map(move |(i, key)| {
+            let offset = 2 * i * size;
+            (
+                key,
+                &self.access_values[offset..offset + size],
+                &self.access_values[offset + size..offset + 2 * size],
+            )
+        })
+    }
+
+    /// Record an access to `key`, whose value is at `value`, until it's finalized. Both the value
+    /// before and after it are the current one meanwhile.
+    fn record_pending_access(&mut self, key: &[u8; 32], value: NonNull<c_void>, is_new: bool) {
+        let size = self.layout.size();
+
+        self.entry_mut(key).accesses += 1;
+        self.total_accesses += 1;
+
+        self.access_keys.push(*key);
+        if is_new {
+            self.access_values
+                .resize(self.access_values.len() + 2 * size, 0);
+        } else {
+            // SAFETY: The values of existing keys are always initialized.
+            let prev_value =
+                unsafe { std::slice::from_raw_parts(value.cast::<u8>().as_ptr(), size) };
+            self.access_values.extend_from_slice(prev_value);
+            self.access_values.extend_from_slice(prev_value);
+        }
+
+        self.pending_access = Some(value);
+    }
+
+    fn entry_mut(&mut self, key: &[u8; 32]) -> &mut FeltDictEntry {
+        let arena = &self.arena;
+        let layout = self.layout;
+
+        self.mappings.entry(*key).or_insert_with(|| FeltDictEntry {
+            value: arena.alloc_layout(layout).cast(),
+            accesses: 0,
+        })
+    }
+}
@@ -4,7 +4,12 @@ use lazy_static::lazy_static;
 use starknet_crypto::FieldElement;
 use starknet_curve::AffinePoint;
 use starknet_types_core::felt::Felt;
-use std::{collections::HashMap, fs::File, io::Write, os::fd::FromRawFd, ptr::NonNull, slice};
+use std::{alloc::Layout, fs::File, io::Write, os::fd::FromRawFd, ptr::NonNull, slice};
+
+pub use self::dict::FeltDict;
+
+pub mod dict;
+pub mod syscalls;
 
 lazy_static! {
     pub static ref HALF_PRIME: FieldElement = FieldElement::from_dec_str(
@@ -144,36 +149,46 @@ pub unsafe extern "C" fn cairo_native__libfunc__hades_permutation(
     op2.copy_from_slice(&state[2].to_bytes_be());
 }
 
-/// Allocates a new dictionary. Internally a rust hashmap: `HashMap<[u8; 32], NonNull<()>`
+/// Allocates a new dictionary for values of the given size and alignment.
+///
+/// The size and alignment are the layout of the dictionary's value type as computed by the
+/// compiler. An invalid layout aborts the process, since unwinding out of this function would be
+/// undefined behavior.
 ///
 /// # Safety
 ///
 /// This function is intended to be called from MLIR, deals with pointers, and is therefore
 /// definitely unsafe to use manually.
 #[no_mangle]
-pub unsafe extern "C" fn cairo_native__alloc_dict() -> *mut std::ffi::c_void {
-    Box::into_raw(Box::<HashMap<[u8; 32], NonNull<std::ffi::c_void>>>::default()) as _
+pub unsafe extern "C" fn cairo_native__alloc_dict(
+    value_size: u64,
+    value_align: u64,
+) -> *mut FeltDict {
+    let layout = match (usize::try_from(value_size), usize::try_from(value_align)) {
+        (Ok(size), Ok(align)) => Layout::from_size_align(size, align).ok(),
+        _ => None,
+    };
+    let Some(layout) = layout else {
+        eprintln!("invalid dict value layout (size {value_size}, align {value_align})");
+        std::process::abort();
+    };
+
+    Box::into_raw(Box::new(FeltDict::new(layout)))
 }
 
-/// Frees the dictionary.
+/// Frees the dictionary, including all its values at once.
 ///
 /// # Safety
 ///
 /// This function is intended to be called from MLIR, deals with pointers, and is therefore
 /// definitely unsafe to use manually.
 #[no_mangle]
-pub unsafe extern "C" fn cairo_native__dict_free(
-    ptr: *mut HashMap<[u8; 32], NonNull<std::ffi::c_void>>,
-) {
-    let mut map = Box::from_raw(ptr);
-
-    // Free the entries manually.
-    for (_, entry) in map.drain() {
-        libc::free(entry.as_ptr().cast());
-    }
+pub unsafe extern "C" fn cairo_native__dict_free(ptr: *mut FeltDict) {
+    drop(Box::from_raw(ptr));
 }
 
-/// Gets the value for a given key, the returned pointer is null if not found.
+/// Gets the value for a given key, the returned pointer is null if not found. Hits are recorded as
+/// accesses.
 ///
 /// # Safety
 ///
@@ -181,19 +196,17 @@ pub unsafe extern "C" fn cairo_native__dict_free(
 /// definitely unsafe to use manually.
 #[no_mangle]
 pub unsafe extern "C" fn cairo_native__dict_get(
-    ptr: *const HashMap<[u8; 32], NonNull<std::ffi::c_void>>,
+    ptr: *mut FeltDict,
     key: &[u8; 32],
-) -> *const std::ffi::c_void {
-    let map: &HashMap<[u8; 32], NonNull<std::ffi::c_void>> = &*ptr;
-
-    if let Some(v) = map.get(key) {
-        v.as_ptr()
-    } else {
-        std::ptr::null_mut()
+) -> *mut std::ffi::c_void {
+    match (*ptr).get_and_record(key) {
+        Some(value) => value.as_ptr(),
+        None => std::ptr::null_mut(),
     }
 }
 
-/// Inserts the provided key value. Returning the old one or nullptr if there was none.
+/// Inserts the provided key, returning a pointer to its (uninitialized if new) value. The insertion
+/// is recorded as an access.
 ///
 /// # Safety
 ///
@@ -201,18 +214,34 @@ pub unsafe extern "C" fn cairo_native__dict_get(
 /// definitely unsafe to use manually.
 #[no_mangle]
 pub unsafe extern "C" fn cairo_native__dict_insert(
-    ptr: *mut HashMap<[u8; 32], NonNull<std::ffi::c_void>>,
+    ptr: *mut FeltDict,
     key: &[u8; 32],
-    value: NonNull<std::ffi::c_void>,
 ) -> *mut std::ffi::c_void {
-    let ptr = ptr.cast::<HashMap<[u8; 32], NonNull<std::ffi::c_void>>>();
-    let old_ptr = (*ptr).insert(*key, value);
+    (*ptr).insert_and_record(key).as_ptr()
+}
 
-    if let Some(v) = old_ptr {
-        v.as_ptr()
-    } else {
-        std::ptr::null_mut()
-    }
+/// Reads the new value of the last access, recorded by either `cairo_native__dict_get` or
+/// `cairo_native__dict_insert`, once the compiled code has written it to the value's slot.
+///
+/// # Safety
+///
+/// This function is intended to be called from MLIR, deals with pointers, and is therefore
+/// definitely unsafe to use manually.
+#[no_mangle]
+pub unsafe extern "C" fn cairo_native__dict_finalize_access(ptr: *mut FeltDict) {
+    (*ptr).finalize_access();
+}
+
+/// Returns the number of accesses which weren't the first one to their key, for which squashing the
+/// dictionary refunds part of the gas charged by each access.
+///
+/// # Safety
+///
+/// This function is intended to be called from MLIR, deals with pointers, and is therefore
+/// definitely unsafe to use manually.
+#[no_mangle]
+pub unsafe extern "C" fn cairo_native__dict_repeated_accesses(ptr: *const FeltDict) -> u64 {
+    (*ptr).repeated_accesses()
 }
 
 /// Compute `ec_point_from_x_nz(x)` and store it.
@@ -0,0 +1,260 @@
+//! # Default syscall implementations
+//!
+//! Syscalls whose result only depends on their inputs, like the hashing builtins, can be
+//! implemented once here and reused by every syscall handler. Only the dynamic part of the gas
+//! costs is charged here, like the keccak rounds: the base cost of every syscall is deducted by
+//! the syscall callbacks before the handler is called.
+
+use ark_ec::{
+    short_weierstrass::{Affine, SWCurveConfig},
+    AffineRepr, CurveGroup,
+};
+use ark_ff::{BigInt, BigInteger, PrimeField};
+use sha2::digest::generic_array::GenericArray;
+use starknet_types_core::felt::Felt;
+
+/// Same as the `SyscallResult` of `cairo_native::starknet`: the error variant contains the panic
+/// data.
+pub type SyscallResult<T> = Result<T, Vec<Felt>>;
+
+/// A `u256` split into its low and high halves, as represented in Sierra.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct U256 {
+    pub lo: u128,
+    pub hi: u128,
+}
+
+/// A point of either the secp256k1 or the secp256r1 curves, in affine coordinates.
+///
+/// The point at infinity is represented as `(0, 0)`, like the VM does.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Secp256Point {
+    pub x: U256,
+    pub y: U256,
+}
+
+/// Dynamic gas costs of the syscalls implemented here.
+///
+/// The base costs of every syscall are charged by `cairo_native::starknet::SyscallGasCosts`.
+pub mod gas_costs {
+    pub const KECCAK_ROUND_COST: u128 = 180000;
+}
+
+/// Deduct `amount` from `remaining_gas`, failing the syscall like the VM does if there is not
+/// enough gas.
+pub fn deduct_gas(remaining_gas: &mut u128, amount: u128) -> SyscallResult<()> {
+    match remaining_gas.checked_sub(amount) {
+        Some(gas) => {
+            *remaining_gas = gas;
+            Ok(())
+        }
+        None => Err(vec![Felt::from_bytes_be_slice(b"Syscall out of gas")]),
+    }
+}
+
+/// Compute the keccak hash of an input already padded to a multiple of 17 words (1088 bits).
+///
+/// Based on `cairo-lang-runner`'s implementation.
+pub fn keccak(input: &[u64], remaining_gas: &mut u128) -> SyscallResult<U256> {
+    if input.len() % 17 != 0 {
+        return Err(vec![Felt::from_bytes_be_slice(
+            b"Invalid keccak input size",
+        )]);
+    }
+
+    let mut state = [0u64; 25];
+    for chunk in input.chunks(17) {
+        deduct_gas(remaining_gas, gas_costs::KECCAK_ROUND_COST)?;
+        for (i, val) in chunk.iter().enumerate() {
+            state[i] ^= val;
+        }
+        keccak::f1600(&mut state);
+    }
+
+    Ok(U256 {
+        lo: ((state[1] as u128) << 64) | state[0] as u128,
+        hi: ((state[3] as u128) << 64) | state[2] as u128,
+    })
+}
+
+/// Apply the sha256 compression function of a single 512-bit block to `state`.
+///
+/// The block's words are interpreted as big endian, as done by the Cairo corelib.
+pub fn sha256_process_block(state: &mut [u32; 8], block: &[u32; 16]) -> SyscallResult<()> {
+    let mut data = [0u8; 64];
+    for (chunk, word) in data.chunks_exact_mut(4).zip(block) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    sha2::compress256(state, &[*GenericArray::from_slice(&data)]);
+
+    Ok(())
+}
+
+/// Executes the `secp256k1_new` syscall.
+pub fn secp256k1_new(x: U256, y: U256) -> SyscallResult<Option<Secp256Point>> {
+    secp256_new::<ark_secp256k1::Config>(x, y)
+}
+
+/// Executes the `secp256k1_add` syscall.
+pub fn secp256k1_add(p0: Secp256Point, p1: Secp256Point) -> SyscallResult<Secp256Point> {
+    secp256_add::<ark_secp256k1::Config>(p0, p1)
+}
+
+/// Executes the `secp256k1_mul` syscall.
+pub fn secp256k1_mul(p: Secp256Point, m: U256) -> SyscallResult<Secp256Point> {
+    secp256_mul::<ark_secp256k1::Config>(p, m)
+}
+
+/// Executes the `secp256k1_get_point_from_x` syscall.
+pub fn secp256k1_get_point_from_x(x: U256, y_parity: bool) -> SyscallResult<Option<Secp256Point>> {
+    secp256_get_point_from_x::<ark_secp256k1::Config>(x, y_parity)
+}
+
+/// Executes the `secp256k1_get_xy` syscall.
+pub fn secp256k1_get_xy(p: Secp256Point) -> SyscallResult<(U256, U256)> {
+    Ok((p.x, p.y))
+}
+
+/// Executes the `secp256r1_new` syscall.
+pub fn secp256r1_new(x: U256, y: U256) -> SyscallResult<Option<Secp256Point>> {
+    secp256_new::<ark_secp256r1::Config>(x, y)
+}
+
+/// Executes the `secp256r1_add` syscall.
+pub fn secp256r1_add(p0: Secp256Point, p1: Secp256Point) -> SyscallResult<Secp256Point> {
+    secp256_add::<ark_secp256r1::Config>(p0, p1)
+}
+
+/// Executes the `secp256r1_mul` syscall.
+pub fn secp256r1_mul(p: Secp256Point, m: U256) -> SyscallResult<Secp256Point> {
+    secp256_mul::<ark_secp256r1::Config>(p, m)
+}
+
+/// Executes the `secp256r1_get_point_from_x` syscall.
+pub fn secp256r1_get_point_from_x(x: U256, y_parity: bool) -> SyscallResult<Option<Secp256Point>> {
+    secp256_get_point_from_x::<ark_secp256r1::Config>(x, y_parity)
+}
+
+/// Executes the `secp256r1_get_xy` syscall.
+pub fn secp256r1_get_xy(p: Secp256Point) -> SyscallResult<(U256, U256)> {
+    Ok((p.x, p.y))
+}
+
+fn coordinates_out_of_range() -> Vec<Felt> {
+    vec![Felt::from_bytes_be_slice(b"Coordinates out of range")]
+}
+
+fn u256_to_bigint(value: U256) -> BigInt<4> {
+    BigInt::new([
+        value.lo as u64,
+        (value.lo >> 64) as u64,
+        value.hi as u64,
+        (value.hi >> 64) as u64,
+    ])
+}
+
+fn bigint_to_u256(value: BigInt<4>) -> U256 {
+    U256 {
+        lo: ((value.0[1] as u128) << 64) | value.0[0] as u128,
+        hi: ((value.0[3] as u128) << 64) | value.0[2] as u128,
+    }
+}
+
+fn u256_to_field<F: PrimeField<BigInt = BigInt<4>>>(value: U256) -> Option<F> {
+    F::from_bigint(u256_to_bigint(value))
+}
+
+/// Convert a point into its affine representation without checking whether it's on the curve.
+///
+/// Points can only be created through `secp256_new` and `secp256_get_point_from_x`, which already
+/// validate them.
+fn point_to_affine<C>(p: Secp256Point) -> SyscallResult<Affine<C>>
+where
+    C: SWCurveConfig,
+    C::BaseField: PrimeField<BigInt = BigInt<4>>,
+{
+    if p.x == U256::default() && p.y == U256::default() {
+        return Ok(Affine::identity());
+    }
+
+    match (u256_to_field(p.x), u256_to_field(p.y)) {
+        (Some(x), Some(y)) => Ok(Affine::new_unchecked(x, y)),
+        _ => Err(coordinates_out_of_range()),
+    }
+}
+
+fn affine_to_point<C>(p: Affine<C>) -> Secp256Point
+where
+    C: SWCurveConfig,
+    C::BaseField: PrimeField<BigInt = BigInt<4>>,
+{
+    match p.xy() {
+        Some((x, y)) => Secp256Point {
+            x: bigint_to_u256(x.into_bigint()),
+            y: bigint_to_u256(y.into_bigint()),
+        },
+        None => Secp256Point::default(),
+    }
+}
+
+fn secp256_new<C>(x: U256, y: U256) -> SyscallResult<Option<Secp256Point>>
+where
+    C: SWCurveConfig,
+    C::BaseField: PrimeField<BigInt = BigInt<4>>,
+{
+    // Like the VM, `(0, 0)` is accepted as the point at infinity (which is on the curve). Any other
+    // point which isn't on the curve is rejected with `None`.
+    let p = point_to_affine::<C>(Secp256Point { x, y })?;
+
+    Ok(
+        (p.is_on_curve() && p.is_in_correct_subgroup_assuming_on_curve())
+            .then_some(Secp256Point { x, y }),
+    )
+}
+
+fn secp256_add<C>(p0: Secp256Point, p1: Secp256Point) -> SyscallResult<Secp256Point>
+where
+    C: SWCurveConfig,
+    C::BaseField: PrimeField<BigInt = BigInt<4>>,
+{
+    let p0 = point_to_affine::<C>(p0)?;
+    let p1 = point_to_affine::<C>(p1)?;
+
+    Ok(affine_to_point((p0 + p1).into_affine()))
+}
+
+fn secp256_mul<C>(p: Secp256Point, m: U256) -> SyscallResult<Secp256Point>
+where
+    C: SWCurveConfig,
+    C::BaseField: PrimeField<BigInt = BigInt<4>>,
+{
+    let p = point_to_affine::<C>(p)?;
+
+    let mut m_bytes = [0u8; 32];
+    m_bytes[..16].copy_from_slice(&m.lo.to_le_bytes());
+    m_bytes[16..].copy_from_slice(&m.hi.to_le_bytes());
+    let m = C::ScalarField::from_le_bytes_mod_order(&m_bytes);
+
+    Ok(affine_to_point((p * m).into_affine()))
+}
+
+fn secp256_get_point_from_x<C>(x: U256, y_parity: bool) -> SyscallResult<Option<Secp256Point>>
+where
+    C: SWCurveConfig,
+    C::BaseField: PrimeField<BigInt = BigInt<4>>,
+{
+    let x = u256_to_field::<C::BaseField>(x).ok_or_else(coordinates_out_of_range)?;
+
+    Ok(Affine::<C>::get_ys_from_x_unchecked(x)
+        .map(|(smaller, greater)| {
+            // Return the correct y coordinate based on the parity.
+            if smaller.into_bigint().is_odd() == y_parity {
+                smaller
+            } else {
+                greater
+            }
+        })
+        .map(|y| Affine::<C>::new_unchecked(x, y))
+        .filter(|p| p.is_in_correct_subgroup_assuming_on_curve())
+        .map(affine_to_point))
+}
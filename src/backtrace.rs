@@ -0,0 +1,209 @@
+//! # Panic backtraces
+//!
+//! Programs compiled with panic backtraces enabled (check out
+//! [`NativeContext::with_panic_backtraces`](crate::context::NativeContext::with_panic_backtraces))
+//! report the Sierra statements which create and propagate their panics (see
+//! [the instrumentation](crate::metadata::panic_trace)). This module collects them into a
+//! [`PanicBacktrace`], which can then be mapped to Cairo source locations using the program's
+//! [`DebugInfo`].
+//!
+//! The trace is kept per thread and cleared every time a function is invoked.
+
+use crate::{
+    debug_info::{DebugInfo, SourceLocation},
+    utils::find_statement_function,
+};
+use cairo_lang_compiler::db::RootDatabase;
+use cairo_lang_sierra::{
+    ids::FunctionId,
+    program::{Program, StatementIdx},
+};
+use std::{
+    cell::RefCell,
+    fmt::{self, Display},
+};
+
+thread_local! {
+    static PANIC_TRACE: RefCell<Vec<StatementIdx>> = RefCell::new(Vec::new());
+}
+
+extern "C" fn record_panic_trace(statement_idx: u64) {
+    PANIC_TRACE.with(|trace| {
+        trace
+            .borrow_mut()
+            .push(StatementIdx(statement_idx as usize))
+    });
+}
+
+/// Point the panic trace hook global of a compiled program to this module's trace.
+///
+/// # Safety
+///
+/// The pointer must point to the hook global of a program compiled with panic backtraces enabled.
+pub(crate) unsafe fn install_panic_trace_hook(hook_ptr: *mut u64) {
+    *hook_ptr = record_panic_trace as extern "C" fn(u64) as usize as u64;
+}
+
+/// Discard the trace of the current thread.
+pub(crate) fn clear_panic_trace() {
+    PANIC_TRACE.with(|trace| trace.borrow_mut().clear());
+}
+
+/// The Sierra statements which created a panic. The first one is the origin of the panic, followed
+/// by the statements propagating it through every frame of the call chain.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PanicBacktrace {
+    pub statements: Vec<StatementIdx>,
+}
+
+impl PanicBacktrace {
+    /// Take the backtrace recorded by the last invocation in the current thread, if it panicked.
+    pub fn take() -> Option<Self> {
+        let statements = PANIC_TRACE.with(|trace| std::mem::take(&mut *trace.borrow_mut()));
+        (!statements.is_empty()).then_some(Self { statements })
+    }
+
+    /// Map every statement to its function and Cairo source location (see
+    /// [`BacktraceFrame::resolve`]).
+    pub fn resolve(
+        &self,
+        db: &RootDatabase,
+        program: &Program,
+        debug_info: &DebugInfo,
+    ) -> Vec<BacktraceFrame> {
+        self.statements
+            .iter()
+            .map(|&statement_idx| BacktraceFrame::resolve(db, program, debug_info, statement_idx))
+            .collect()
+    }
+}
+
+/// A Sierra statement resolved to its function and Cairo source location, such as a frame of a
+/// [`PanicBacktrace`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BacktraceFrame {
+    pub statement_idx: StatementIdx,
+    pub function: Option<FunctionId>,
+    pub location: Option<SourceLocation>,
+}
+
+impl BacktraceFrame {
+    /// Map a statement to its function and Cairo source location.
+    ///
+    /// Statements without a location of their own (mostly compiler-generated ones) fall back to the
+    /// location of their function.
+    pub fn resolve(
+        db: &RootDatabase,
+        program: &Program,
+        debug_info: &DebugInfo,
+        statement_idx: StatementIdx,
+    ) -> Self {
+        let function = find_statement_function(program, statement_idx).map(|x| x.id.clone());
+
+        let location = debug_info
+            .statements
+            .get(&statement_idx)
+            .and_then(|location_id| {
+                SourceLocation::from_stable_location(db, location_id.get(db).stable_location)
+            })
+            .or_else(|| {
+                function
+                    .as_ref()
+                    .and_then(|function| debug_info.funcs.get(function))
+                    .and_then(|stable_loc| SourceLocation::from_stable_location(db, *stable_loc))
+            });
+
+        Self {
+            statement_idx,
+            function,
+            location,
+        }
+    }
+}
+
+impl Display for BacktraceFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.function {
+            Some(function) => write!(f, "{function}")?,
+            None => write!(f, "<unknown>")?,
+        }
+        write!(f, " (statement {})", self.statement_idx)?;
+        if let Some(location) = &self.location {
+            write!(f, " at {location}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::NativeContext, executor::JitNativeExecutor, utils::test::load_cairo, OptLevel,
+    };
+
+    #[test]
+    fn take_trace() {
+        clear_panic_trace();
+        assert_eq!(PanicBacktrace::take(), None);
+
+        record_panic_trace(3);
+        record_panic_trace(7);
+        assert_eq!(
+            PanicBacktrace::take(),
+            Some(PanicBacktrace {
+                statements: vec![StatementIdx(3), StatementIdx(7)],
+            })
+        );
+        assert_eq!(PanicBacktrace::take(), None);
+    }
+
+    #[test]
+    fn trace_panic() {
+        let (module_name, program) = load_cairo! {
+            #[inline(never)]
+            fn inner(x: felt252) -> felt252 {
+                assert(x != 0, 42);
+                x
+            }
+
+            fn run_test() -> felt252 {
+                inner(0)
+            }
+        };
+
+        let native_context = NativeContext::new().with_panic_backtraces(true);
+        let native_module = native_context.compile(&program, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(native_module, OptLevel::None);
+
+        let entry_point = program
+            .funcs
+            .iter()
+            .find(|x| {
+                x.id.debug_name.as_deref()
+                    == Some(format!("{0}::{0}::run_test", module_name).as_str())
+            })
+            .unwrap();
+        executor.invoke_dynamic(&entry_point.id, &[], None).unwrap();
+
+        let backtrace = PanicBacktrace::take().unwrap();
+        let function_names = backtrace
+            .statements
+            .iter()
+            .map(|&statement_idx| {
+                find_statement_function(&program, statement_idx)
+                    .and_then(|x| x.id.debug_name.as_deref())
+                    .unwrap()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+
+        // The panic may originate in a corelib function, but it must go through `inner`.
+        assert!(function_names.contains(&format!("{0}::{0}::inner", module_name)));
+        assert_eq!(
+            function_names.last().unwrap(),
+            &format!("{0}::{0}::run_test", module_name)
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use anyhow::{bail, Context};
+use cairo_lang_compiler::{
+    db::RootDatabase, diagnostics::DiagnosticsReporter, project::setup_project,
+};
+use cairo_lang_diagnostics::ToOption;
+use cairo_lang_runner::SierraCasmRunner;
+use cairo_lang_sierra::{
+    extensions::core::{CoreLibfunc, CoreType},
+    program::{Function, Program},
+    program_registry::ProgramRegistry,
+};
+use cairo_lang_sierra_generator::{
+    db::SierraGenGroup,
+    replace_ids::{DebugReplacer, SierraIdReplacer},
+};
+use cairo_lang_starknet::contract::get_contracts_info;
+use cairo_native::{
+    context::NativeContext,
+    executor::{AotNativeExecutor, JitNativeExecutor, NativeExecutor},
+    fuzz::{fuzz_function, FuzzConfig},
+    metadata::gas::{GasMetadata, MetadataComputationConfig},
+    OptLevel,
+};
+use clap::{Parser, ValueEnum};
+use std::path::{Path, PathBuf};
+use tracing_subscriber::{EnvFilter, FmtSubscriber};
+
+#[derive(Clone, Debug, ValueEnum)]
+enum RunMode {
+    Aot,
+    Jit,
+}
+
+/// Runs a function of a Cairo program with random arguments generated from its signature, reporting
+/// the smallest failing input found.
+/// Exits with 1 if the compilation fails or a failing input is found, otherwise 0.
+#[derive(Parser, Debug)]
+#[clap(version, verbatim_doc_comment)]
+struct Args {
+    /// The Cairo project path to compile.
+    path: PathBuf,
+    /// The suffix of the name of the function to fuzz (ex. `::my_function`).
+    function: String,
+    /// Whether path is a single file.
+    #[arg(short, long)]
+    single_file: bool,
+    /// Allows the compilation to succeed with warnings.
+    #[arg(long)]
+    allow_warnings: bool,
+    /// In cases where gas is available, the amount of provided gas.
+    #[arg(long)]
+    available_gas: Option<usize>,
+    /// Run with JIT or AOT (compiled).
+    #[arg(long, value_enum, default_value_t = RunMode::Jit)]
+    run_mode: RunMode,
+    /// Optimization level, Valid: 0, 1, 2, 3. Values higher than 3 are considered as 3.
+    #[arg(short = 'O', long, default_value_t = 0)]
+    opt_level: u8,
+    /// Number of successful cases required to pass.
+    #[arg(long, default_value_t = 256)]
+    cases: u32,
+    /// Maximum length of the generated arrays.
+    #[arg(long, default_value_t = 16)]
+    max_array_len: usize,
+    /// Seed of the random generator, to reproduce a previous run.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Run every case on the Cairo VM too and fail if the results differ.
+    #[arg(long)]
+    compare_vm: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    // Configure logging and error handling.
+    tracing::subscriber::set_global_default(
+        FmtSubscriber::builder()
+            .with_env_filter(EnvFilter::from_default_env())
+            .finish(),
+    )?;
+
+    let args = Args::parse();
+
+    check_compiler_path(args.single_file, &args.path)?;
+
+    let db = &mut RootDatabase::builder().detect_corelib().build()?;
+    let main_crate_ids = setup_project(db, Path::new(&args.path))?;
+
+    let mut reporter = DiagnosticsReporter::stderr();
+    if args.allow_warnings {
+        reporter = reporter.allow_warnings();
+    }
+    if reporter.check(db) {
+        bail!("failed to compile: {}", args.path.display());
+    }
+
+    let (sierra_program, _statements_locations) = db
+        .get_sierra_program(main_crate_ids.clone())
+        .to_option()
+        .with_context(|| "Compilation failed without any diagnostics.")?;
+    let replacer = DebugReplacer { db };
+    if args.available_gas.is_none() && sierra_program.requires_gas_counter() {
+        bail!("Program requires gas counter, please provide `--available-gas` argument.");
+    }
+
+    let contracts_info = get_contracts_info(db, main_crate_ids, &replacer)?;
+    let sierra_program = replacer.apply(&sierra_program);
+
+    let native_module = NativeContext::new().compile(&sierra_program, None).unwrap();
+
+    let opt_level = match args.opt_level {
+        0 => OptLevel::None,
+        1 => OptLevel::Less,
+        2 => OptLevel::Default,
+        _ => OptLevel::Aggressive,
+    };
+
+    let native_executor: NativeExecutor = match args.run_mode {
+        RunMode::Aot => AotNativeExecutor::from_native_module(native_module, opt_level).into(),
+        RunMode::Jit => JitNativeExecutor::from_native_module(native_module, opt_level).into(),
+    };
+
+    let gas_metadata =
+        GasMetadata::new(&sierra_program, Some(MetadataComputationConfig::default())).unwrap();
+
+    let func = find_function(&sierra_program, &args.function)?;
+    let initial_gas = gas_metadata
+        .get_initial_available_gas(&func.id, args.available_gas.map(|x| x.try_into().unwrap()))
+        .with_context(|| "not enough gas to run")?;
+
+    let runner = if args.compare_vm {
+        Some(SierraCasmRunner::new(
+            sierra_program.clone(),
+            Some(Default::default()),
+            contracts_info,
+            false,
+        )?)
+    } else {
+        None
+    };
+
+    let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&sierra_program)?;
+    let config = FuzzConfig {
+        cases: args.cases,
+        max_array_len: args.max_array_len,
+        seed: args.seed,
+    };
+
+    let failure = fuzz_function(
+        &native_executor,
+        &registry,
+        &func.id,
+        Some(initial_gas),
+        runner.as_ref().map(|runner| (runner, args.available_gas)),
+        &config,
+    )?;
+
+    match failure {
+        None => {
+            println!("{} cases passed.", config.cases);
+            Ok(())
+        }
+        Some(failure) => {
+            println!("Found a failing input (after shrinking):");
+            for (i, arg) in failure.args.iter().enumerate() {
+                println!("  {i:>4}: {arg:?}");
+            }
+            println!("Reason: {}", failure.reason);
+            bail!("the function failed for some inputs");
+        }
+    }
+}
+
+pub fn check_compiler_path(single_file: bool, path: &Path) -> anyhow::Result<()> {
+    if path.is_file() {
+        if !single_file {
+            anyhow::bail!("The given path is a file, but --single-file was not supplied.");
+        }
+    } else if path.is_dir() {
+        if single_file {
+            anyhow::bail!("The given path is a directory, but --single-file was supplied.");
+        }
+    } else {
+        anyhow::bail!("The given path does not exist.");
+    }
+    Ok(())
+}
+
+pub fn find_function<'a>(
+    sierra_program: &'a Program,
+    name_suffix: &str,
+) -> anyhow::Result<&'a Function> {
+    sierra_program
+        .funcs
+        .iter()
+        .find(|f| {
+            f.id.debug_name
+                .as_deref()
+                .is_some_and(|name| name.ends_with(name_suffix))
+        })
+        .with_context(|| format!("function {name_suffix} not found"))
+}
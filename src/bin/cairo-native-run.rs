@@ -3,26 +3,38 @@ use cairo_lang_compiler::{
     db::RootDatabase, diagnostics::DiagnosticsReporter, project::setup_project,
 };
 use cairo_lang_diagnostics::ToOption;
-use cairo_lang_runner::{short_string::as_cairo_short_string, RunResultValue};
-use cairo_lang_sierra::program::{Function, Program};
+use cairo_lang_runner::{
+    short_string::as_cairo_short_string, RunResultValue, SierraCasmRunner, StarknetState,
+};
+use cairo_lang_sierra::{
+    extensions::core::{CoreLibfunc, CoreType},
+    program::{Function, Program},
+    program_registry::ProgramRegistry,
+};
 use cairo_lang_sierra_generator::{
     db::SierraGenGroup,
     replace_ids::{DebugReplacer, SierraIdReplacer},
 };
 use cairo_lang_starknet::contract::get_contracts_info;
 use cairo_native::{
+    backtrace::PanicBacktrace,
     context::NativeContext,
     debug_info::{DebugInfo, DebugLocations},
     execution_result::ExecutionResult,
     executor::{AotNativeExecutor, JitNativeExecutor, NativeExecutor},
     metadata::gas::{GasMetadata, MetadataComputationConfig},
+    profiler::ProfileData,
     values::JitValue,
+    vm::compare_with_vm,
     OptLevel,
 };
 use clap::{Parser, ValueEnum};
 use itertools::Itertools;
 use starknet_types_core::felt::Felt;
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
 #[derive(Clone, Debug, ValueEnum)]
@@ -53,6 +65,17 @@ struct Args {
     /// Optimization level, Valid: 0, 1, 2, 3. Values higher than 3 are considered as 3.
     #[arg(short = 'O', long, default_value_t = 0)]
     opt_level: u8,
+    /// Print the Cairo backtrace of the panic, if the program panics.
+    #[arg(long)]
+    backtrace: bool,
+    /// Profile the run, print a summary and write the samples to the given file as folded stacks
+    /// (which can be turned into a flamegraph by tools like `inferno-flamegraph`).
+    #[arg(long)]
+    profile: Option<PathBuf>,
+    /// Run the function on the Cairo VM too and fail if the return value, the panic data or the
+    /// remaining gas differ from the native run.
+    #[arg(long)]
+    compare_vm: bool,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -89,22 +112,21 @@ fn main() -> anyhow::Result<()> {
         anyhow::bail!("Program requires gas counter, please provide `--available-gas` argument.");
     }
 
-    let _contracts_info = get_contracts_info(db, main_crate_ids, &replacer)?;
+    let contracts_info = get_contracts_info(db, main_crate_ids, &replacer)?;
     let sierra_program = replacer.apply(&sierra_program);
 
-    let native_context = NativeContext::new();
-
-    let debug_locations = {
-        let debug_info = DebugInfo::extract(db, &sierra_program)
-            .map_err(|_| {
-                let mut buffer = String::new();
-                assert!(DiagnosticsReporter::write_to_string(&mut buffer).check(db));
-                buffer
-            })
-            .unwrap();
+    let native_context = NativeContext::new()
+        .with_panic_backtraces(args.backtrace)
+        .with_profiler(args.profile.is_some());
 
-        DebugLocations::extract(native_context.context(), db, &debug_info)
-    };
+    let debug_info = DebugInfo::extract(db, &sierra_program)
+        .map_err(|_| {
+            let mut buffer = String::new();
+            assert!(DiagnosticsReporter::write_to_string(&mut buffer).check(db));
+            buffer
+        })
+        .unwrap();
+    let debug_locations = DebugLocations::extract(native_context.context(), db, &debug_info);
 
     // Compile the sierra program into a MLIR module.
     let native_module = native_context
@@ -150,13 +172,77 @@ fn main() -> anyhow::Result<()> {
                     None => print!("{value}, "),
                 }
             }
-            println!("].")
+            println!("].");
+
+            if args.backtrace {
+                if let Some(backtrace) = PanicBacktrace::take() {
+                    println!("Panic backtrace:");
+                    for (i, frame) in backtrace
+                        .resolve(db, &sierra_program, &debug_info)
+                        .iter()
+                        .enumerate()
+                    {
+                        println!("  {i:>4}: {frame}");
+                    }
+                }
+            }
         }
     }
     if let Some(gas) = result.remaining_gas {
         println!("Remaining gas: {gas}");
     }
 
+    if let Some(profile_path) = &args.profile {
+        let profile = ProfileData::take();
+
+        println!("Profile (top functions by time):");
+        let function_gas = profile
+            .function_gas(&sierra_program, &gas_metadata)
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        for (function_id, counters) in profile.function_counters(&sierra_program).iter().take(10) {
+            println!(
+                "  {:>12?} {:>8} calls {:>10} gas  {function_id}",
+                counters.total_time,
+                counters.calls,
+                function_gas.get(function_id).copied().unwrap_or_default(),
+            );
+        }
+
+        std::fs::write(profile_path, profile.to_folded_stacks(&sierra_program)).with_context(
+            || format!("Failed to write the profile to {}.", profile_path.display()),
+        )?;
+        println!("Profile written to {}.", profile_path.display());
+    }
+
+    if args.compare_vm {
+        let runner = SierraCasmRunner::new(
+            sierra_program.clone(),
+            Some(Default::default()),
+            contracts_info,
+            false,
+        )?;
+        let vm_result = runner
+            .run_function_with_starknet_context(
+                func,
+                &[],
+                args.available_gas,
+                StarknetState::default(),
+            )
+            .with_context(|| "Failed to run the function on the VM.")?;
+
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&sierra_program)?;
+        let mismatches = compare_with_vm(&registry, &func.id, &result, &vm_result)?;
+        if !mismatches.is_empty() {
+            println!("The native run differs from the VM run:");
+            for mismatch in &mismatches {
+                println!("  {mismatch}");
+            }
+            bail!("the native and VM runs differ");
+        }
+        println!("The native run matches the VM run.");
+    }
+
     Ok(())
 }
 
@@ -280,6 +366,15 @@ fn jitvalue_to_felt(value: &JitValue) -> Vec<Felt> {
             }
         }
         JitValue::Felt252Dict { .. } => todo!(),
+        JitValue::SquashedFelt252Dict { value, .. } => {
+            for entry in value {
+                felts.push(entry.key.to_bigint().into());
+                let felt = jitvalue_to_felt(&entry.value);
+                felts.extend(felt);
+            }
+
+            felts
+        }
         JitValue::Uint8(x) => vec![(*x).into()],
         JitValue::Uint16(x) => vec![(*x).into()],
         JitValue::Uint32(x) => vec![(*x).into()],
@@ -4,13 +4,23 @@ use cairo_lang_compiler::{
     db::RootDatabase, diagnostics::DiagnosticsReporter, project::setup_project,
 };
 use cairo_lang_filesystem::cfg::{Cfg, CfgSet};
-use cairo_lang_runner::{casm_run::format_next_item, RunResultValue};
+use cairo_lang_runner::{
+    casm_run::format_next_item, RunResultValue, SierraCasmRunner, StarknetState,
+};
 use cairo_lang_sierra::{
-    extensions::gas::CostTokenType,
+    extensions::{
+        core::{CoreLibfunc, CoreType},
+        gas::CostTokenType,
+    },
     ids::FunctionId,
     program::{Function, Program},
+    program_registry::ProgramRegistry,
+};
+use cairo_lang_sierra_to_casm::metadata::MetadataComputationConfig as VmMetadataComputationConfig;
+use cairo_lang_starknet::{
+    contract::{starknet_keccak, ContractInfo},
+    starknet_plugin_suite,
 };
-use cairo_lang_starknet::{contract::ContractInfo, starknet_plugin_suite};
 use cairo_lang_test_plugin::{
     compile_test_prepared_db,
     test_config::{PanicExpectation, TestExpectation},
@@ -18,21 +28,38 @@ use cairo_lang_test_plugin::{
 };
 use cairo_lang_utils::{casts::IntoOrPanic, ordered_hash_map::OrderedHashMap};
 use cairo_native::{
+    backtrace::{BacktraceFrame, PanicBacktrace},
+    cache::{AotProgramCache, JitProgramCache, ProgramCache},
     context::NativeContext,
+    coverage::{CoverageData, CoverageReport},
+    debug_info::DebugInfo,
     execution_result::ExecutionResult,
     executor::{AotNativeExecutor, JitNativeExecutor, NativeExecutor},
     metadata::gas::{GasMetadata, MetadataComputationConfig},
+    starknet::{EntryPoint, EntryPoints, InMemoryStarknetState},
     values::JitValue,
+    vm::{compare_with_vm, VmMismatch},
     OptLevel,
 };
 use clap::{Parser, ValueEnum};
 use colored::Colorize;
 use itertools::Itertools;
 use num_traits::ToPrimitive;
+use serde_json::json;
 use starknet_types_core::felt::Felt;
 use std::{
+    collections::HashMap,
+    fmt::Write,
+    fs,
+    num::NonZeroUsize,
+    panic::{self, AssertUnwindSafe},
     path::{Path, PathBuf},
-    sync::Mutex,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
     vec::IntoIter,
 };
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
@@ -43,6 +70,16 @@ enum RunMode {
     Jit,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable progress and summary.
+    Pretty,
+    /// A JSON document with the result of every test.
+    Json,
+    /// A JUnit XML report.
+    Junit,
+}
+
 /// Compiles a Cairo project and runs all the functions marked as `#[test]`.
 /// Exits with 1 if the compilation or run fails, otherwise 0.
 #[derive(Parser, Debug)]
@@ -74,6 +111,27 @@ struct Args {
     /// Optimization level, Valid: 0, 1, 2, 3. Values higher than 3 are considered as 3.
     #[arg(short = 'O', long, default_value_t = 0)]
     opt_level: u8,
+    /// Print the Cairo backtrace of the failed tests which panicked.
+    #[arg(long, default_value_t = false)]
+    backtrace: bool,
+    /// Write the line and function coverage of the project's sources to this file, in the lcov
+    /// format.
+    #[arg(long)]
+    coverage: Option<PathBuf>,
+    /// Number of threads running the tests in parallel, only supported in AOT mode. Defaults to
+    /// the number of available CPUs.
+    #[arg(short, long)]
+    jobs: Option<usize>,
+    /// Fail the tests which run for longer than this many seconds, only supported in AOT mode.
+    #[arg(long)]
+    timeout: Option<u64>,
+    /// The format of the results printed to stdout.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Pretty)]
+    format: OutputFormat,
+    /// Run every test on the Cairo VM too and fail the tests whose return value, panic data or
+    /// remaining gas differ from the native run.
+    #[arg(long)]
+    compare_vm: bool,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -88,6 +146,14 @@ fn main() -> anyhow::Result<()> {
     )?;
 
     check_compiler_path(args.single_file, &args.path)?;
+    if matches!(args.run_mode, RunMode::Jit) {
+        if args.jobs.is_some() {
+            bail!("`--jobs` requires `--run-mode aot`, JIT tests run sequentially.");
+        }
+        if args.timeout.is_some() {
+            bail!("`--timeout` requires `--run-mode aot`, JIT tests can't be interrupted.");
+        }
+    }
 
     let db = &mut {
         let mut b = RootDatabase::builder();
@@ -127,12 +193,8 @@ fn main() -> anyhow::Result<()> {
         args.filter.clone(),
     );
 
-    let TestsSummary {
-        passed,
-        failed,
-        ignored,
-        failed_run_results,
-    } = run_tests(
+    let summary = run_tests(
+        &db,
         compiled.named_tests,
         compiled.sierra_program,
         compiled.function_set_costs,
@@ -140,34 +202,55 @@ fn main() -> anyhow::Result<()> {
         &args,
     )?;
 
-    if failed.is_empty() {
-        println!(
-            "test result: {}. {} passed; {} failed; {} ignored; {filtered_out} filtered out;",
-            "ok".bright_green(),
-            passed.len(),
-            failed.len(),
-            ignored.len()
-        );
-    } else {
-        println!("failures:");
-        for (failure, run_result) in failed.iter().zip_eq(failed_run_results) {
-            print!("   {failure} - ");
-            match run_result {
-                RunResultValue::Success(_) => {
-                    println!("expected panic but finished successfully.");
-                }
-                RunResultValue::Panic(values) => {
-                    println!("{}", format_for_panic(values.into_iter()));
+    let passed = summary.count("passed");
+    let failed = summary.count("failed") + summary.count("timed_out");
+    let ignored = summary.count("ignored");
+
+    match args.format {
+        OutputFormat::Pretty if failed == 0 => {
+            println!(
+                "test result: {}. {} passed; {} failed; {} ignored; {filtered_out} filtered out;",
+                "ok".bright_green(),
+                passed,
+                failed,
+                ignored
+            );
+        }
+        OutputFormat::Pretty => {
+            println!("failures:");
+            for (name, result) in &summary.results {
+                let Some(result) = result else {
+                    continue;
+                };
+                let Some(message) = result.status.failure_message() else {
+                    continue;
+                };
+
+                println!("   {name} - {message}");
+                match &result.status {
+                    TestStatus::Fail(_, backtrace) => {
+                        for (i, frame) in backtrace.iter().enumerate() {
+                            println!("      {i:>4}: {frame}");
+                        }
+                    }
+                    TestStatus::VmMismatch(mismatches) => {
+                        for mismatch in mismatches {
+                            println!("      {mismatch}");
+                        }
+                    }
+                    _ => {}
                 }
             }
+            println!();
         }
-        println!();
+        OutputFormat::Json => println!("{:#}", summary.to_json(filtered_out)),
+        OutputFormat::Junit => print!("{}", summary.to_junit()),
+    }
+
+    if failed != 0 {
         bail!(
-            "test result: {}. {} passed; {} failed; {} ignored",
+            "test result: {}. {passed} passed; {failed} failed; {ignored} ignored",
             "FAILED".bright_red(),
-            passed.len(),
-            failed.len(),
-            ignored.len()
         );
     }
 
@@ -261,7 +344,33 @@ pub fn find_function<'a>(
 /// The status of a ran test.
 enum TestStatus {
     Success,
-    Fail(RunResultValue),
+    Fail(RunResultValue, Vec<BacktraceFrame>),
+    TimedOut,
+    /// The native run differs from the VM run.
+    VmMismatch(Vec<VmMismatch>),
+    /// The test couldn't be executed.
+    Error(String),
+}
+
+impl TestStatus {
+    /// Describe why the test failed, if it did.
+    fn failure_message(&self) -> Option<String> {
+        match self {
+            TestStatus::Success => None,
+            TestStatus::Fail(RunResultValue::Success(_), _) => {
+                Some("expected panic but finished successfully.".to_string())
+            }
+            TestStatus::Fail(RunResultValue::Panic(values), _) => {
+                Some(format_for_panic(values.clone().into_iter()))
+            }
+            TestStatus::TimedOut => Some("timed out.".to_string()),
+            TestStatus::VmMismatch(mismatches) => Some(format!(
+                "differs from the VM run: {}.",
+                mismatches.iter().join("; ")
+            )),
+            TestStatus::Error(message) => Some(message.clone()),
+        }
+    }
 }
 
 /// The result of a ran test.
@@ -270,14 +379,229 @@ struct TestResult {
     status: TestStatus,
     /// The gas usage of the run if relevant.
     gas_usage: Option<i64>,
+    /// How long the run took.
+    duration: Duration,
 }
 
 /// Summary data of the ran tests.
 pub struct TestsSummary {
-    passed: Vec<String>,
-    failed: Vec<String>,
-    ignored: Vec<String>,
-    failed_run_results: Vec<RunResultValue>,
+    /// The name and result of every test, in the order they were collected. Ignored tests have no
+    /// result.
+    results: Vec<(String, Option<TestResult>)>,
+}
+
+impl TestsSummary {
+    /// Count the tests with the given status (see [`status_name`]).
+    fn count(&self, status: &str) -> usize {
+        self.results
+            .iter()
+            .filter(|(_, result)| status_name(result.as_ref()) == status)
+            .count()
+    }
+
+    /// Build the JSON report of the tests.
+    fn to_json(&self, filtered_out: usize) -> serde_json::Value {
+        let tests = self
+            .results
+            .iter()
+            .map(|(name, result)| {
+                let panic_data = match result.as_ref().map(|result| &result.status) {
+                    Some(TestStatus::Fail(RunResultValue::Panic(values), _)) => Some(
+                        values
+                            .iter()
+                            .map(|value| format!("{:#x}", value.to_biguint()))
+                            .collect_vec(),
+                    ),
+                    _ => None,
+                };
+                let backtrace = match result.as_ref().map(|result| &result.status) {
+                    Some(TestStatus::Fail(_, backtrace)) => {
+                        backtrace.iter().map(ToString::to_string).collect_vec()
+                    }
+                    _ => Vec::new(),
+                };
+
+                let duration = result
+                    .as_ref()
+                    .map_or(0.0, |result| result.duration.as_secs_f64());
+
+                json!({
+                    "name": name,
+                    "status": status_name(result.as_ref()),
+                    "duration_secs": duration,
+                    "gas_usage": result.as_ref().and_then(|result| result.gas_usage),
+                    "message": result.as_ref().and_then(|result| result.status.failure_message()),
+                    "panic_data": panic_data,
+                    "backtrace": backtrace,
+                })
+            })
+            .collect_vec();
+
+        json!({
+            "summary": {
+                "passed": self.count("passed"),
+                "failed": self.count("failed"),
+                "timed_out": self.count("timed_out"),
+                "ignored": self.count("ignored"),
+                "filtered_out": filtered_out,
+            },
+            "tests": tests,
+        })
+    }
+
+    /// Build the JUnit XML report of the tests.
+    fn to_junit(&self) -> String {
+        let total_time = self
+            .results
+            .iter()
+            .filter_map(|(_, result)| result.as_ref())
+            .map(|result| result.duration.as_secs_f64())
+            .sum::<f64>();
+        let attributes = format!(
+            r#"name="cairo-native-test" tests="{}" failures="{}" skipped="{}" time="{:.3}""#,
+            self.results.len(),
+            self.count("failed") + self.count("timed_out"),
+            self.count("ignored"),
+            total_time,
+        );
+
+        let mut output = String::new();
+        writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+        writeln!(output, "<testsuites {attributes}>").unwrap();
+        writeln!(output, "  <testsuite {attributes}>").unwrap();
+
+        for (name, result) in &self.results {
+            let (classname, test_name) = name.rsplit_once("::").unwrap_or(("", name));
+            writeln!(
+                output,
+                r#"    <testcase name="{}" classname="{}" time="{:.3}">"#,
+                escape_xml(test_name),
+                escape_xml(classname),
+                result
+                    .as_ref()
+                    .map_or(0.0, |result| result.duration.as_secs_f64()),
+            )
+            .unwrap();
+
+            match result {
+                None => writeln!(output, "      <skipped/>").unwrap(),
+                Some(result) => {
+                    if let Some(gas_usage) = result.gas_usage {
+                        writeln!(output, "      <properties>").unwrap();
+                        writeln!(
+                            output,
+                            r#"        <property name="gas_usage" value="{gas_usage}"/>"#
+                        )
+                        .unwrap();
+                        writeln!(output, "      </properties>").unwrap();
+                    }
+
+                    if let Some(message) = result.status.failure_message() {
+                        let (kind, backtrace) = match &result.status {
+                            TestStatus::Fail(_, backtrace) => ("panic", backtrace.as_slice()),
+                            TestStatus::VmMismatch(_) => ("vm_mismatch", [].as_slice()),
+                            TestStatus::Error(_) => ("error", [].as_slice()),
+                            _ => ("timeout", [].as_slice()),
+                        };
+                        writeln!(
+                            output,
+                            r#"      <failure type="{kind}" message="{}">{}</failure>"#,
+                            escape_xml(&message),
+                            escape_xml(&backtrace.iter().join("\n")),
+                        )
+                        .unwrap();
+                    }
+                }
+            }
+
+            writeln!(output, "    </testcase>").unwrap();
+        }
+
+        writeln!(output, "  </testsuite>").unwrap();
+        writeln!(output, "</testsuites>").unwrap();
+
+        output
+    }
+}
+
+/// The name of a test's status in the summary and the reports.
+fn status_name(result: Option<&TestResult>) -> &'static str {
+    match result.map(|result| &result.status) {
+        Some(TestStatus::Success) => "passed",
+        Some(TestStatus::Fail(..) | TestStatus::VmMismatch(_) | TestStatus::Error(_)) => "failed",
+        Some(TestStatus::TimedOut) => "timed_out",
+        None => "ignored",
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// A test ready to be executed.
+struct TestJob {
+    /// The position of the test within the collected tests.
+    index: usize,
+    name: String,
+    function_id: FunctionId,
+    initial_gas: u128,
+    /// The gas available to the test, as configured by its attributes.
+    available_gas: Option<usize>,
+}
+
+/// The outcome of executing a test, before checking it against its expectation.
+struct TestRun {
+    result: ExecutionResult,
+    backtrace: Option<PanicBacktrace>,
+    coverage: CoverageData,
+    duration: Duration,
+    /// The differences with the VM run, when comparing against the VM.
+    vm_mismatches: Vec<VmMismatch>,
+}
+
+/// The Cairo VM runner used to check the native runs against.
+struct VmComparison {
+    runner: SierraCasmRunner,
+    registry: ProgramRegistry<CoreType, CoreLibfunc>,
+}
+
+impl VmComparison {
+    /// Run the test on the VM and compare the outcome with its native run.
+    fn compare(
+        &self,
+        job: &TestJob,
+        native_result: &ExecutionResult,
+    ) -> anyhow::Result<Vec<VmMismatch>> {
+        let func = self.runner.find_function(&job.name)?;
+        let vm_result = self
+            .runner
+            .run_function_with_starknet_context(
+                func,
+                &[],
+                job.available_gas,
+                StarknetState::default(),
+            )
+            .with_context(|| format!("Failed to run the function `{}` on the VM.", job.name))?;
+
+        Ok(compare_with_vm(
+            &self.registry,
+            &job.function_id,
+            native_result,
+            &vm_result,
+        )?)
+    }
+}
+
+/// Events sent by the workers running the tests in parallel.
+enum WorkerEvent<T> {
+    /// The worker started executing the job at the given position.
+    Started(usize),
+    /// The worker finished executing the job at the given position.
+    Finished(usize, anyhow::Result<T>),
 }
 
 fn result_to_runresult(result: &ExecutionResult) -> anyhow::Result<RunResultValue> {
@@ -350,6 +674,15 @@ fn jitvalue_to_felt(value: &JitValue) -> Vec<Felt> {
 
             felts
         }
+        JitValue::SquashedFelt252Dict { value, .. } => {
+            for entry in value {
+                felts.push(entry.key);
+                let felt = jitvalue_to_felt(&entry.value);
+                felts.extend(felt);
+            }
+
+            felts
+        }
         JitValue::Uint8(x) => vec![(*x).into()],
         JitValue::Uint16(x) => vec![(*x).into()],
         JitValue::Uint32(x) => vec![(*x).into()],
@@ -370,13 +703,26 @@ fn jitvalue_to_felt(value: &JitValue) -> Vec<Felt> {
 
 /// Runs the tests and process the results for a summary.
 fn run_tests(
+    db: &RootDatabase,
     named_tests: Vec<(String, TestConfig)>,
     sierra_program: Program,
     function_set_costs: OrderedHashMap<FunctionId, OrderedHashMap<CostTokenType, i32>>,
-    _contracts_info: OrderedHashMap<Felt252, ContractInfo>,
+    contracts_info: OrderedHashMap<Felt252, ContractInfo>,
     args: &Args,
 ) -> anyhow::Result<TestsSummary> {
-    let native_context = NativeContext::new();
+    // The context is shared with the AOT workers, which create the state of every test with it.
+    let native_context = Arc::new(
+        NativeContext::new()
+            .with_panic_backtraces(args.backtrace)
+            .with_coverage(args.coverage.is_some()),
+    );
+    let debug_info = if args.backtrace || args.coverage.is_some() {
+        Some(DebugInfo::extract(db, &sierra_program).map_err(|_| {
+            anyhow::anyhow!("failed to extract the debug info required for backtraces and coverage")
+        })?)
+    } else {
+        None
+    };
 
     // Compile the sierra program into a MLIR module.
     let native_module = native_context
@@ -397,9 +743,22 @@ fn run_tests(
         _ => OptLevel::Aggressive,
     };
 
-    let native_executor: NativeExecutor = match args.run_mode {
-        RunMode::Aot => AotNativeExecutor::from_native_module(native_module, opt_level).into(),
-        RunMode::Jit => JitNativeExecutor::from_native_module(native_module, opt_level).into(),
+    let vm_comparison = if args.compare_vm {
+        Some(VmComparison {
+            runner: SierraCasmRunner::new(
+                sierra_program.clone(),
+                Some(VmMetadataComputationConfig {
+                    function_set_costs: function_set_costs.clone(),
+                    linear_ap_change_solver: true,
+                    linear_gas_solver: true,
+                }),
+                contracts_info.clone(),
+                false,
+            )?,
+            registry: ProgramRegistry::new(&sierra_program)?,
+        })
+    } else {
+        None
     };
 
     let gas_metadata = GasMetadata::new(
@@ -412,104 +771,559 @@ fn run_tests(
     )
     .unwrap();
 
-    println!("running {} tests", named_tests.len());
-    let wrapped_summary = Mutex::new(Ok(TestsSummary {
-        passed: vec![],
-        failed: vec![],
-        ignored: vec![],
-        failed_run_results: vec![],
-    }));
-    named_tests
-        .into_iter()
-        .map(
-            |(name, test)| -> anyhow::Result<(String, Option<TestResult>)> {
-                if test.ignored {
-                    return Ok((name, None));
-                }
-                tracing::trace!("running test {name:?}");
+    let classes = test_classes(&contracts_info);
+
+    if args.format == OutputFormat::Pretty {
+        println!("running {} tests", named_tests.len());
+    }
+
+    let mut results = Vec::with_capacity(named_tests.len());
+    let mut jobs = Vec::new();
+    for (index, (name, test)) in named_tests.iter().enumerate() {
+        results.push((name.clone(), None));
+        if test.ignored {
+            if args.format == OutputFormat::Pretty {
+                print_result(name, None);
+            }
+            continue;
+        }
+
+        let func = find_function(&sierra_program, name.as_str())?;
+        let initial_gas = gas_metadata
+            .get_initial_available_gas(&func.id, test.available_gas.map(|x| x.try_into().unwrap()))
+            .with_context(|| "not enough gas to run")?;
 
-                let func = find_function(&sierra_program, name.as_str())?;
+        jobs.push(TestJob {
+            index,
+            name: name.clone(),
+            function_id: func.id.clone(),
+            initial_gas,
+            available_gas: test.available_gas,
+        });
+    }
 
-                let initial_gas = gas_metadata
-                    .get_initial_available_gas(
-                        &func.id,
-                        test.available_gas.map(|x| x.try_into().unwrap()),
+    let timeout = args.timeout.map(Duration::from_secs);
+    let mut coverage = CoverageData::default();
+    let mut finish = |job: &TestJob, run: Option<anyhow::Result<TestRun>>| {
+        let result = match run {
+            Some(run) => run
+                .and_then(|run| {
+                    coverage.merge(&run.coverage);
+                    check_test_run(
+                        db,
+                        &sierra_program,
+                        debug_info.as_ref(),
+                        &gas_metadata,
+                        job,
+                        &named_tests[job.index].1,
+                        run,
                     )
-                    .with_context(|| "not enough gas to run")?;
-
-                let result = native_executor
-                    .invoke_dynamic(&func.id, &[], Some(initial_gas))
-                    .with_context(|| format!("Failed to run the function `{}`.", name.as_str()))?;
-
-                let run_result = result_to_runresult(&result)?;
-                Ok((
-                    name,
-                    Some(TestResult {
-                        status: match &run_result {
-                            RunResultValue::Success(_) => match test.expectation {
-                                TestExpectation::Success => TestStatus::Success,
-                                TestExpectation::Panics(_) => TestStatus::Fail(run_result),
-                            },
-                            RunResultValue::Panic(value) => match test.expectation {
-                                TestExpectation::Success => TestStatus::Fail(run_result),
-                                TestExpectation::Panics(panic_expectation) => {
-                                    match panic_expectation {
-                                        PanicExpectation::Exact(expected) if value != &expected => {
-                                            TestStatus::Fail(run_result)
-                                        }
-                                        _ => TestStatus::Success,
-                                    }
-                                }
-                            },
-                        },
-                        gas_usage: test
-                            .available_gas
-                            .zip(result.remaining_gas)
-                            .map(|(before, after)| {
-                                before.into_or_panic::<i64>() - after.to_i64().unwrap()
-                            })
-                            .or_else(|| {
-                                gas_metadata
-                                    .initial_required_gas(&func.id)
-                                    .map(|gas| gas.try_into().unwrap())
-                            }),
-                    }),
-                ))
+                })
+                .unwrap_or_else(|e| TestResult {
+                    status: TestStatus::Error(format!("{e:#}")),
+                    gas_usage: None,
+                    duration: Duration::ZERO,
+                }),
+            None => TestResult {
+                status: TestStatus::TimedOut,
+                gas_usage: None,
+                duration: timeout.unwrap_or_default(),
             },
-        )
-        .for_each(|r| {
-            let mut wrapped_summary = wrapped_summary.lock().unwrap();
-            if wrapped_summary.is_err() {
-                return;
+        };
+
+        if args.format == OutputFormat::Pretty {
+            print_result(&job.name, Some(&result));
+        }
+        results[job.index].1 = Some(result);
+    };
+
+    match args.run_mode {
+        RunMode::Aot => {
+            let native_executor = Arc::new(AotNativeExecutor::from_native_module(
+                native_module,
+                opt_level,
+            ));
+            let native_context = native_context.clone();
+            let vm_comparison = vm_comparison.map(Arc::new);
+            let threads = args
+                .jobs
+                .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get));
+
+            run_in_parallel(
+                jobs,
+                threads.max(1),
+                timeout,
+                move |job| {
+                    // Executors aren't `Send`, only the AOT ones they wrap are.
+                    let native_executor = NativeExecutor::Aot(native_executor.clone());
+                    execute_test(
+                        job,
+                        &native_executor,
+                        AotProgramCache::new(&native_context).into(),
+                        &classes,
+                        vm_comparison.as_deref(),
+                    )
+                },
+                &mut finish,
+            )?;
+        }
+        RunMode::Jit => {
+            let native_executor = NativeExecutor::from(JitNativeExecutor::from_native_module(
+                native_module,
+                opt_level,
+            ));
+            for job in &jobs {
+                let run = catch_test_panic(job, || {
+                    execute_test(
+                        job,
+                        &native_executor,
+                        JitProgramCache::new(&native_context).into(),
+                        &classes,
+                        vm_comparison.as_ref(),
+                    )
+                });
+                finish(job, Some(run));
             }
-            let (name, status) = match r {
-                Ok((name, status)) => (name, status),
-                Err(err) => {
-                    *wrapped_summary = Err(err);
-                    return;
-                }
-            };
-            let summary = wrapped_summary.as_mut().unwrap();
-            let (res_type, status_str, gas_usage) = match status {
-                Some(TestResult {
-                    status: TestStatus::Success,
-                    gas_usage,
-                }) => (&mut summary.passed, "ok".bright_green(), gas_usage),
-                Some(TestResult {
-                    status: TestStatus::Fail(run_result),
-                    gas_usage,
-                }) => {
-                    summary.failed_run_results.push(run_result);
-                    (&mut summary.failed, "fail".bright_red(), gas_usage)
-                }
-                None => (&mut summary.ignored, "ignored".bright_yellow(), None),
+        }
+    }
+
+    if let Some((output, debug_info)) = args.coverage.as_ref().zip(debug_info.as_ref()) {
+        write_coverage_report(
+            db,
+            &sierra_program,
+            debug_info,
+            &coverage,
+            &args.path,
+            output,
+        )?;
+        if args.format == OutputFormat::Pretty {
+            println!("coverage report written to {}", output.display());
+        }
+    }
+
+    Ok(TestsSummary { results })
+}
+
+/// Run the tests on `threads` worker threads sharing `execute`, calling `finish` as they complete
+/// (or with `None` when they time out). Tests which fail to execute or panic are reported to
+/// `finish` too, without stopping the other ones.
+///
+/// Native code can't be interrupted, therefore the workers running tests which time out are
+/// abandoned (and replaced) and keep running until the process exits.
+fn run_in_parallel<T: Send + 'static>(
+    jobs: Vec<TestJob>,
+    threads: usize,
+    timeout: Option<Duration>,
+    execute: impl Fn(&TestJob) -> anyhow::Result<T> + Send + Sync + 'static,
+    mut finish: impl FnMut(&TestJob, Option<anyhow::Result<T>>),
+) -> anyhow::Result<()> {
+    let jobs = Arc::<[TestJob]>::from(jobs);
+    let execute = Arc::new(execute);
+
+    let (job_tx, job_rx) = mpsc::channel();
+    for position in 0..jobs.len() {
+        job_tx.send(position).unwrap();
+    }
+    drop(job_tx);
+
+    let job_rx = Arc::new(Mutex::new(job_rx));
+    let (event_tx, event_rx) = mpsc::channel();
+    let spawn_worker = || {
+        let execute = execute.clone();
+        let jobs = jobs.clone();
+        let job_rx = job_rx.clone();
+        let event_tx = event_tx.clone();
+
+        thread::spawn(move || loop {
+            let Ok(position) = job_rx.lock().unwrap().recv() else {
+                break;
             };
-            if let Some(gas_usage) = gas_usage {
-                println!("test {name} ... {status_str} (gas usage est.: {gas_usage})");
-            } else {
-                println!("test {name} ... {status_str}");
+            if event_tx.send(WorkerEvent::Started(position)).is_err() {
+                break;
+            }
+
+            let job = &jobs[position];
+            let run = catch_test_panic(job, || execute(job));
+            if event_tx.send(WorkerEvent::Finished(position, run)).is_err() {
+                break;
             }
-            res_type.push(name);
         });
-    wrapped_summary.into_inner().unwrap()
+    };
+
+    for _ in 0..threads.min(jobs.len()) {
+        spawn_worker();
+    }
+
+    let mut running = HashMap::<usize, Instant>::new();
+    let mut remaining = jobs.len();
+    while remaining != 0 {
+        let deadline = timeout.and_then(|timeout| {
+            running
+                .values()
+                .min()
+                .map(|started_at| *started_at + timeout)
+        });
+        let event = match deadline {
+            Some(deadline) => {
+                event_rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => event_rx.recv().map_err(RecvTimeoutError::from),
+        };
+
+        match event {
+            Ok(WorkerEvent::Started(position)) => {
+                running.insert(position, Instant::now());
+            }
+            Ok(WorkerEvent::Finished(position, run)) => {
+                // The results of the tests which already timed out are discarded.
+                if running.remove(&position).is_some() {
+                    remaining -= 1;
+                    finish(&jobs[position], Some(run));
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                let timeout = timeout.unwrap();
+                let timed_out = running
+                    .iter()
+                    .filter(|(_, started_at)| started_at.elapsed() >= timeout)
+                    .map(|(position, _)| *position)
+                    .collect_vec();
+
+                for position in timed_out {
+                    running.remove(&position);
+                    remaining -= 1;
+                    finish(&jobs[position], None);
+
+                    // The worker is stuck running the test, so another one takes its place.
+                    spawn_worker();
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => bail!("the test workers stopped unexpectedly"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Run `execute`, turning its panics into errors so that a single test can't bring down the whole
+/// run.
+fn catch_test_panic<T>(
+    job: &TestJob,
+    execute: impl FnOnce() -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    panic::catch_unwind(AssertUnwindSafe(execute))
+        .unwrap_or_else(|_| Err(anyhow::anyhow!("The function `{}` panicked.", job.name)))
+}
+
+/// Execute a test on a fresh Starknet state, collecting the instrumentation data it generated in
+/// the current thread, and compare it against the VM if requested.
+///
+/// The contracts of the tested crates are declared in the state, so the test can deploy them.
+fn execute_test<'m>(
+    job: &TestJob,
+    native_executor: &NativeExecutor<'m>,
+    cache: ProgramCache<'m, Felt>,
+    classes: &TestClasses,
+    vm_comparison: Option<&VmComparison>,
+) -> anyhow::Result<TestRun> {
+    let mut state = InMemoryStarknetState::new(cache);
+    for (class_hash, entry_points) in classes {
+        state.declare_compiled(*class_hash, native_executor.clone(), entry_points.clone());
+    }
+
+    let started_at = Instant::now();
+    let result = native_executor
+        .invoke_dynamic_with_syscall_handler(
+            &job.function_id,
+            &[],
+            Some(job.initial_gas),
+            &mut state,
+        )
+        .with_context(|| format!("Failed to run the function `{}`.", job.name))?;
+    let duration = started_at.elapsed();
+
+    let vm_mismatches = match vm_comparison {
+        Some(vm_comparison) => vm_comparison.compare(job, &result)?,
+        None => Vec::new(),
+    };
+
+    Ok(TestRun {
+        result,
+        backtrace: PanicBacktrace::take(),
+        coverage: CoverageData::take(),
+        duration,
+        vm_mismatches,
+    })
+}
+
+/// The contracts compiled along with the tests, as class hashes and the entry points of their
+/// classes.
+type TestClasses = Vec<(Felt, EntryPoints)>;
+
+/// Resolve the entry points of the contracts compiled along with the tests, which are functions
+/// of the test program itself.
+fn test_classes(contracts_info: &OrderedHashMap<Felt252, ContractInfo>) -> TestClasses {
+    let felt = |value: &Felt252| Felt::from_bytes_be_slice(&value.to_bytes_be());
+    let entry_points = |functions: &OrderedHashMap<Felt252, FunctionId>| {
+        functions
+            .iter()
+            .map(|(selector, function_id)| EntryPoint {
+                selector: felt(selector),
+                function_id: function_id.clone(),
+            })
+            .collect()
+    };
+    let constructor_selector =
+        Felt::from_bytes_be_slice(&starknet_keccak(b"constructor").to_bytes_be());
+
+    contracts_info
+        .iter()
+        .map(|(class_hash, contract_info)| {
+            let entry_points = EntryPoints {
+                constructor: contract_info
+                    .constructor
+                    .clone()
+                    .map(|function_id| EntryPoint {
+                        selector: constructor_selector,
+                        function_id,
+                    }),
+                external: entry_points(&contract_info.externals),
+                l1_handler: entry_points(&contract_info.l1_handlers),
+            };
+
+            (felt(class_hash), entry_points)
+        })
+        .collect()
+}
+
+/// Check the outcome of a test against its expectation.
+fn check_test_run(
+    db: &RootDatabase,
+    sierra_program: &Program,
+    debug_info: Option<&DebugInfo>,
+    gas_metadata: &GasMetadata,
+    job: &TestJob,
+    test: &TestConfig,
+    run: TestRun,
+) -> anyhow::Result<TestResult> {
+    let run_result = result_to_runresult(&run.result)?;
+    let backtrace = run
+        .backtrace
+        .zip(debug_info)
+        .map(|(backtrace, debug_info)| backtrace.resolve(db, sierra_program, debug_info))
+        .unwrap_or_default();
+
+    Ok(TestResult {
+        status: match &run_result {
+            _ if !run.vm_mismatches.is_empty() => TestStatus::VmMismatch(run.vm_mismatches),
+            RunResultValue::Success(_) => match test.expectation {
+                TestExpectation::Success => TestStatus::Success,
+                TestExpectation::Panics(_) => TestStatus::Fail(run_result, backtrace),
+            },
+            RunResultValue::Panic(value) => match &test.expectation {
+                TestExpectation::Success => TestStatus::Fail(run_result, backtrace),
+                TestExpectation::Panics(panic_expectation) => match panic_expectation {
+                    PanicExpectation::Exact(expected) if value != expected => {
+                        TestStatus::Fail(run_result, backtrace)
+                    }
+                    _ => TestStatus::Success,
+                },
+            },
+        },
+        gas_usage: test
+            .available_gas
+            .zip(run.result.remaining_gas)
+            .map(|(before, after)| before.into_or_panic::<i64>() - after.to_i64().unwrap())
+            .or_else(|| {
+                gas_metadata
+                    .initial_required_gas(&job.function_id)
+                    .map(|gas| gas.try_into().unwrap())
+            }),
+        duration: run.duration,
+    })
+}
+
+/// Print the result of a test as it completes.
+fn print_result(name: &str, result: Option<&TestResult>) {
+    let (status_str, gas_usage) = match result {
+        Some(TestResult {
+            status: TestStatus::Success,
+            gas_usage,
+            ..
+        }) => ("ok".bright_green(), *gas_usage),
+        Some(TestResult {
+            status: TestStatus::Fail(..) | TestStatus::VmMismatch(_) | TestStatus::Error(_),
+            gas_usage,
+            ..
+        }) => ("fail".bright_red(), *gas_usage),
+        Some(TestResult {
+            status: TestStatus::TimedOut,
+            ..
+        }) => ("timeout".bright_red(), None),
+        None => ("ignored".bright_yellow(), None),
+    };
+
+    if let Some(gas_usage) = gas_usage {
+        println!("test {name} ... {status_str} (gas usage est.: {gas_usage})");
+    } else {
+        println!("test {name} ... {status_str}");
+    }
+}
+
+/// Map the coverage counters to the sources of the project (the corelib and other dependencies are
+/// left out) and write them to `output` in the lcov format.
+fn write_coverage_report(
+    db: &RootDatabase,
+    sierra_program: &Program,
+    debug_info: &DebugInfo,
+    data: &CoverageData,
+    project_path: &Path,
+    output: &Path,
+) -> anyhow::Result<()> {
+    let project_path = project_path.canonicalize()?;
+    let project_root = if project_path.is_file() {
+        project_path.parent().unwrap_or(&project_path)
+    } else {
+        project_path.as_path()
+    };
+
+    let mut report = CoverageReport::new(db, sierra_program, debug_info, data);
+    report.files.retain(|path, _| {
+        path.canonicalize()
+            .is_ok_and(|path| path.starts_with(project_root))
+    });
+
+    fs::write(output, report.to_lcov()).with_context(|| {
+        format!(
+            "failed to write the coverage report to {}",
+            output.display()
+        )
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn job(index: usize, name: &str) -> TestJob {
+        TestJob {
+            index,
+            name: name.to_string(),
+            function_id: FunctionId::new(index as u64),
+            initial_gas: 0,
+            available_gas: None,
+        }
+    }
+
+    fn result(status: TestStatus) -> Option<TestResult> {
+        Some(TestResult {
+            status,
+            gas_usage: Some(42),
+            duration: Duration::from_millis(1500),
+        })
+    }
+
+    #[test]
+    fn json_report() {
+        let summary = TestsSummary {
+            results: vec![
+                ("tests::passes".to_string(), result(TestStatus::Success)),
+                (
+                    "tests::panics".to_string(),
+                    result(TestStatus::Fail(
+                        RunResultValue::Panic(vec![Felt252::from(0x2a)]),
+                        Vec::new(),
+                    )),
+                ),
+                ("tests::hangs".to_string(), result(TestStatus::TimedOut)),
+                ("tests::ignored".to_string(), None),
+            ],
+        };
+
+        let report = summary.to_json(3);
+        assert_eq!(
+            report["summary"],
+            json!({
+                "passed": 1,
+                "failed": 1,
+                "timed_out": 1,
+                "ignored": 1,
+                "filtered_out": 3,
+            })
+        );
+
+        let tests = report["tests"].as_array().unwrap();
+        assert_eq!(tests.len(), 4);
+        assert_eq!(tests[0]["name"], "tests::passes");
+        assert_eq!(tests[0]["status"], "passed");
+        assert_eq!(tests[0]["duration_secs"], 1.5);
+        assert_eq!(tests[0]["gas_usage"], 42);
+        assert_eq!(tests[0]["message"], serde_json::Value::Null);
+        assert_eq!(tests[1]["status"], "failed");
+        assert_eq!(tests[1]["panic_data"], json!(["0x2a"]));
+        assert_eq!(tests[1]["backtrace"], json!([]));
+        assert_eq!(tests[2]["status"], "timed_out");
+        assert_eq!(tests[2]["message"], "timed out.");
+        assert_eq!(tests[3]["status"], "ignored");
+        assert_eq!(tests[3]["gas_usage"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn junit_report() {
+        let summary = TestsSummary {
+            results: vec![
+                (
+                    "tests::<odd>&\"name\"".to_string(),
+                    result(TestStatus::Error("expected <a> & 'b'".to_string())),
+                ),
+                ("tests::ignored".to_string(), None),
+            ],
+        };
+
+        let report = summary.to_junit();
+        assert!(report.contains(r#"tests="2" failures="1" skipped="1""#));
+        assert!(report
+            .contains(r#"<testcase name="&lt;odd&gt;&amp;&quot;name&quot;" classname="tests""#));
+        assert!(report.contains(
+            r#"<failure type="error" message="expected &lt;a&gt; &amp; &apos;b&apos;">"#
+        ));
+        assert!(report.contains(r#"<property name="gas_usage" value="42"/>"#));
+        assert!(report.contains("<skipped/>"));
+        assert!(!report.contains("<odd>"));
+    }
+
+    #[test]
+    fn parallel_run_with_timeout() {
+        let jobs = vec![job(0, "fast"), job(1, "hangs"), job(2, "panics")];
+
+        let mut runs = HashMap::new();
+        run_in_parallel(
+            jobs,
+            2,
+            Some(Duration::from_millis(200)),
+            |job| match job.name.as_str() {
+                "hangs" => {
+                    // Abandoned once it times out.
+                    thread::sleep(Duration::from_secs(30));
+                    Ok(job.index)
+                }
+                "panics" => panic!("test panic"),
+                _ => Ok(job.index),
+            },
+            |job, run| {
+                runs.insert(job.index, run.map(|run| run.map_err(|e| e.to_string())));
+            },
+        )
+        .unwrap();
+
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[&0], Some(Ok(0)));
+        assert_eq!(runs[&1], None);
+        assert_eq!(
+            runs[&2],
+            Some(Err("The function `panics` panicked.".to_string()))
+        );
+    }
 }
@@ -0,0 +1,568 @@
+use anyhow::{bail, Context};
+use cairo_lang_casm::{
+    cell_expression::{CellExpression, CellOperator},
+    operand::{CellRef, DerefOrImmediate, Register},
+};
+use cairo_lang_compiler::{
+    db::RootDatabase, diagnostics::DiagnosticsReporter, project::setup_project,
+};
+use cairo_lang_diagnostics::ToOption;
+use cairo_lang_runner::{build_hints_dict, CairoHintProcessor, SierraCasmRunner, StarknetState};
+use cairo_lang_sierra::{
+    extensions::core::{CoreLibfunc, CoreType, CoreTypeConcrete},
+    program::{Program, Statement, StatementIdx},
+    program_registry::ProgramRegistry,
+};
+use cairo_lang_sierra_generator::{
+    db::SierraGenGroup,
+    replace_ids::{DebugReplacer, SierraIdReplacer},
+};
+use cairo_lang_sierra_to_casm::{
+    annotations::{ProgramAnnotations, StatementAnnotations},
+    invocations::{compile_invocation, ProgramInfo},
+    metadata::calc_metadata,
+};
+use cairo_lang_sierra_type_size::get_type_size_map;
+use cairo_lang_starknet::contract::get_contracts_info;
+use cairo_native::{
+    backtrace::BacktraceFrame,
+    context::NativeContext,
+    debug_info::{DebugInfo, DebugLocations},
+    executor::JitNativeExecutor,
+    metadata::{
+        gas::{GasMetadata, MetadataComputationConfig},
+        statement_trace::StatementTraceMode,
+    },
+    statement_trace::{Divergence, StatementTrace},
+    OptLevel,
+};
+use cairo_vm::vm::{
+    runners::cairo_runner::RunResources, trace::trace_entry::TraceEntry, vm_core::VirtualMachine,
+};
+use clap::Parser;
+use itertools::chain;
+use num_traits::ToPrimitive;
+use starknet_types_core::felt::{Felt, NonZeroFelt};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing_subscriber::{EnvFilter, FmtSubscriber};
+
+/// Runs the main function of a Cairo program both natively and on the Cairo VM, tracing every Sierra
+/// statement, and reports the first statement where both executions diverge.
+/// Exits with 1 if the compilation or any run fails, or if the traces diverge, otherwise 0.
+#[derive(Parser, Debug)]
+#[clap(version, verbatim_doc_comment)]
+struct Args {
+    /// The Cairo project path to compile and run.
+    path: PathBuf,
+    /// Whether path is a single file.
+    #[arg(short, long)]
+    single_file: bool,
+    /// Allows the compilation to succeed with warnings.
+    #[arg(long)]
+    allow_warnings: bool,
+    /// In cases where gas is available, the amount of provided gas.
+    #[arg(long)]
+    available_gas: Option<usize>,
+    /// Trace the branches taken by the statements and the values of their integer and felt
+    /// outputs, in both runs.
+    #[arg(long)]
+    values: bool,
+    /// Number of statements to print before the divergence.
+    #[arg(long, default_value_t = 10)]
+    context: usize,
+    /// Write both traces (`native.trace` and `vm.trace`) to this directory.
+    #[arg(long)]
+    output_dir: Option<PathBuf>,
+}
+
+fn main() -> anyhow::Result<()> {
+    // Configure logging and error handling.
+    tracing::subscriber::set_global_default(
+        FmtSubscriber::builder()
+            .with_env_filter(EnvFilter::from_default_env())
+            .finish(),
+    )?;
+
+    let args = Args::parse();
+
+    check_compiler_path(args.single_file, &args.path)?;
+
+    let db = &mut RootDatabase::builder().detect_corelib().build()?;
+    let main_crate_ids = setup_project(db, Path::new(&args.path))?;
+
+    let mut reporter = DiagnosticsReporter::stderr();
+    if args.allow_warnings {
+        reporter = reporter.allow_warnings();
+    }
+    if reporter.check(db) {
+        bail!("failed to compile: {}", args.path.display());
+    }
+
+    let (sierra_program, _statements_locations) = db
+        .get_sierra_program(main_crate_ids.clone())
+        .to_option()
+        .with_context(|| "Compilation failed without any diagnostics.")?;
+    let replacer = DebugReplacer { db };
+    if args.available_gas.is_none() && sierra_program.requires_gas_counter() {
+        bail!("Program requires gas counter, please provide `--available-gas` argument.");
+    }
+
+    let contracts_info = get_contracts_info(db, main_crate_ids, &replacer)?;
+    let sierra_program = replacer.apply(&sierra_program);
+
+    let debug_info = DebugInfo::extract(db, &sierra_program)
+        .map_err(|_| {
+            let mut buffer = String::new();
+            assert!(DiagnosticsReporter::write_to_string(&mut buffer).check(db));
+            buffer
+        })
+        .unwrap();
+
+    let native_trace = run_native(db, &sierra_program, &debug_info, &args)?;
+
+    let runner = SierraCasmRunner::new(
+        sierra_program.clone(),
+        Some(Default::default()),
+        contracts_info,
+        false,
+    )?;
+    let (vm_trace, code_offsets) = run_vm(&runner, &sierra_program, &args)?;
+
+    if let Some(output_dir) = &args.output_dir {
+        fs::create_dir_all(output_dir)?;
+        fs::write(output_dir.join("native.trace"), native_trace.to_string())?;
+        fs::write(output_dir.join("vm.trace"), vm_trace.to_string())?;
+    }
+
+    match native_trace.find_divergence(&vm_trace, &code_offsets) {
+        None => {
+            println!(
+                "The traces match ({} native statements, {} VM statements).",
+                native_trace.steps.len(),
+                vm_trace.steps.len()
+            );
+            Ok(())
+        }
+        Some(divergence) => {
+            print_divergence(
+                db,
+                &sierra_program,
+                &debug_info,
+                &native_trace,
+                &vm_trace,
+                divergence,
+                args.context,
+            );
+            bail!("the native and VM traces diverge");
+        }
+    }
+}
+
+fn run_native(
+    db: &RootDatabase,
+    sierra_program: &Program,
+    debug_info: &DebugInfo,
+    args: &Args,
+) -> anyhow::Result<StatementTrace> {
+    let native_context = NativeContext::new().with_statement_trace(Some(if args.values {
+        StatementTraceMode::StatementsAndValues
+    } else {
+        StatementTraceMode::Statements
+    }));
+
+    let debug_locations = DebugLocations::extract(native_context.context(), db, debug_info);
+    let native_module = native_context
+        .compile(sierra_program, Some(debug_locations))
+        .unwrap();
+    let native_executor = JitNativeExecutor::from_native_module(native_module, OptLevel::None);
+
+    let gas_metadata =
+        GasMetadata::new(sierra_program, Some(MetadataComputationConfig::default())).unwrap();
+    let func = find_main(sierra_program)?;
+    let initial_gas = gas_metadata
+        .get_initial_available_gas(&func, args.available_gas.map(|x| x.try_into().unwrap()))
+        .with_context(|| "not enough gas to run")?;
+
+    native_executor
+        .invoke_dynamic(&func, &[], Some(initial_gas))
+        .with_context(|| "Failed to run the function natively.")?;
+
+    Ok(StatementTrace::take())
+}
+
+/// Run the main function on the Cairo VM, returning its statement trace and the CASM code offsets
+/// of every statement.
+fn run_vm(
+    runner: &SierraCasmRunner,
+    sierra_program: &Program,
+    args: &Args,
+) -> anyhow::Result<(StatementTrace, Vec<usize>)> {
+    let func = runner.find_function("::main")?;
+
+    let initial_gas = runner.get_initial_available_gas(func, args.available_gas)?;
+    let (entry_code, builtins) = runner.create_entry_code(func, &[], initial_gas)?;
+    let footer = SierraCasmRunner::create_code_footer();
+    let instructions = chain!(
+        entry_code.iter(),
+        runner.get_casm_program().instructions.iter(),
+        footer.iter()
+    );
+    let (hints_dict, string_to_hint) = build_hints_dict(instructions.clone());
+
+    let mut hint_processor = CairoHintProcessor {
+        runner: Some(runner),
+        starknet_state: StarknetState::default(),
+        string_to_hint,
+        run_resources: RunResources::default(),
+    };
+    let mut vm = VirtualMachine::new(true);
+    let result = runner
+        .run_function_with_vm(
+            func,
+            &mut vm,
+            &mut hint_processor,
+            hints_dict,
+            instructions,
+            builtins,
+        )
+        .with_context(|| "Failed to run the function on the VM.")?;
+
+    let trace = vm
+        .get_relocated_trace()
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+
+    // The entry code is laid out right before the program at the start of the code segment, where
+    // the run begins.
+    let real_pc_0 = trace.first().map_or(0, |entry| entry.pc)
+        + entry_code
+            .iter()
+            .map(|instruction| instruction.body.op_size())
+            .sum::<usize>();
+
+    let code_offsets = runner
+        .get_casm_program()
+        .debug_info
+        .sierra_statement_info
+        .iter()
+        .map(|info| info.code_offset)
+        .collect::<Vec<_>>();
+
+    let mut vm_trace = StatementTrace::from_casm_pcs(
+        &code_offsets,
+        trace
+            .iter()
+            .filter_map(|entry| entry.pc.checked_sub(real_pc_0)),
+    );
+
+    if args.values {
+        let memory = result
+            .memory
+            .iter()
+            .map(|cell| cell.as_ref().map(|value| Felt::from(&value.to_bigint())))
+            .collect::<Vec<_>>();
+        let outputs = statement_outputs(sierra_program)?;
+
+        let steps = trace
+            .iter()
+            .enumerate()
+            .filter_map(|(position, entry)| {
+                let statement_idx = StatementTrace::statement_at_pc(
+                    &code_offsets,
+                    entry.pc.checked_sub(real_pc_0)?,
+                )?;
+                Some((position, statement_idx))
+            })
+            .collect::<Vec<_>>();
+        for ((position, statement_idx), step) in steps.iter().zip(&mut vm_trace.steps) {
+            let Some((branch, registers)) = find_branch(
+                &trace,
+                &code_offsets,
+                real_pc_0,
+                *position,
+                *statement_idx,
+                sierra_program,
+            ) else {
+                continue;
+            };
+
+            step.branch = Some(branch);
+            if let Some(branches) = &outputs[statement_idx.0] {
+                step.outputs = branches[branch]
+                    .iter()
+                    .filter_map(|(output_idx, cell)| {
+                        Some((*output_idx, eval_cell(&memory, registers, cell)?))
+                    })
+                    .collect();
+            }
+        }
+    }
+
+    Ok((vm_trace, code_offsets))
+}
+
+/// Find the branch taken by the statement executed at the trace entry, along with the registers
+/// right after it. The statement is done once its frame continues outside of its code.
+fn find_branch<'a>(
+    trace: &'a [TraceEntry],
+    code_offsets: &[usize],
+    real_pc_0: usize,
+    position: usize,
+    statement_idx: StatementIdx,
+    sierra_program: &Program,
+) -> Option<(usize, &'a TraceEntry)> {
+    let Statement::Invocation(invocation) = &sierra_program.statements[statement_idx.0] else {
+        return None;
+    };
+
+    let start = real_pc_0 + code_offsets[statement_idx.0];
+    let end = code_offsets
+        .get(statement_idx.0 + 1)
+        .map_or(usize::MAX, |offset| real_pc_0 + offset);
+    let fp = trace[position].fp;
+    let next = trace[position + 1..]
+        .iter()
+        .find(|entry| entry.fp == fp && !(start..end).contains(&entry.pc))?;
+
+    invocation
+        .branches
+        .iter()
+        .position(|branch| {
+            real_pc_0 + code_offsets[statement_idx.next(&branch.target).0] == next.pc
+        })
+        .map(|branch| (branch, next))
+}
+
+/// The CASM cells of the integer and felt outputs of every branch of every invocation, as computed
+/// by the Sierra to CASM compiler. Their registers are the ones right after the statement.
+#[allow(clippy::type_complexity)]
+fn statement_outputs(
+    program: &Program,
+) -> anyhow::Result<Vec<Option<Vec<Vec<(usize, CellExpression)>>>>> {
+    if program
+        .libfunc_declarations
+        .iter()
+        .any(|declaration| declaration.long_id.generic_id.0.starts_with("const_as_"))
+    {
+        bail!("programs with constant segments are not supported when tracing values");
+    }
+
+    // Replicates the compilation loop of `cairo_lang_sierra_to_casm::compiler::compile`, with the
+    // same metadata as the runner.
+    let metadata = calc_metadata(program, Default::default())
+        .map_err(|e| anyhow::anyhow!("failed to compute the program's metadata: {e}"))?;
+    let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new_with_ap_change(
+        program,
+        metadata.ap_change_info.function_ap_change.clone(),
+    )?;
+    let type_sizes = get_type_size_map(program, &registry)
+        .with_context(|| "failed to compute the type sizes")?;
+    let mut annotations = ProgramAnnotations::create(
+        program.statements.len(),
+        &program.funcs,
+        &metadata,
+        true,
+        &type_sizes,
+    )
+    .map_err(|e| anyhow::anyhow!("{e}"))?;
+
+    let mut outputs = Vec::with_capacity(program.statements.len());
+    for (statement_idx, statement) in program.statements.iter().enumerate() {
+        let statement_idx = StatementIdx(statement_idx);
+        let Statement::Invocation(invocation) = statement else {
+            outputs.push(None);
+            continue;
+        };
+
+        let (statement_annotations, invoke_refs) = annotations
+            .get_annotations_after_take_args(statement_idx, invocation.args.iter())
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        let libfunc = registry.get_libfunc(&invocation.libfunc_id)?;
+        let compiled = compile_invocation(
+            ProgramInfo {
+                metadata: &metadata,
+                type_sizes: &type_sizes,
+                const_data_values: &|_| unreachable!("constants were rejected above"),
+            },
+            invocation,
+            libfunc,
+            statement_idx,
+            &invoke_refs,
+            statement_annotations.environment.clone(),
+        )
+        .map_err(|e| anyhow::anyhow!("failed to compile statement {statement_idx}: {e}"))?;
+
+        outputs.push(Some(
+            compiled
+                .results
+                .iter()
+                .map(|branch| {
+                    branch
+                        .refs
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, output)| {
+                            matches!(
+                                registry.get_type(&output.ty),
+                                Ok(CoreTypeConcrete::Felt252(_)
+                                    | CoreTypeConcrete::Uint8(_)
+                                    | CoreTypeConcrete::Uint16(_)
+                                    | CoreTypeConcrete::Uint32(_)
+                                    | CoreTypeConcrete::Uint64(_)
+                                    | CoreTypeConcrete::Uint128(_)
+                                    | CoreTypeConcrete::Sint8(_)
+                                    | CoreTypeConcrete::Sint16(_)
+                                    | CoreTypeConcrete::Sint32(_)
+                                    | CoreTypeConcrete::Sint64(_)
+                                    | CoreTypeConcrete::Sint128(_))
+                            )
+                        })
+                        .filter_map(|(output_idx, output)| {
+                            match output.expression.cells.as_slice() {
+                                [cell] => Some((output_idx, cell.clone())),
+                                _ => None,
+                            }
+                        })
+                        .collect()
+                })
+                .collect(),
+        ));
+
+        let updated_annotations = StatementAnnotations {
+            environment: compiled.environment,
+            ..statement_annotations
+        };
+        let branching_libfunc = compiled.results.len() > 1;
+        for (branch_info, branch_changes) in invocation.branches.iter().zip(compiled.results) {
+            annotations
+                .propagate_annotations(
+                    statement_idx,
+                    statement_idx.next(&branch_info.target),
+                    &updated_annotations,
+                    branch_info,
+                    branch_changes,
+                    branching_libfunc,
+                )
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        }
+    }
+
+    Ok(outputs)
+}
+
+/// Evaluate a single CASM cell using the relocated memory of a run and the registers at the time.
+fn eval_cell(
+    memory: &[Option<Felt>],
+    registers: &TraceEntry,
+    cell: &CellExpression,
+) -> Option<Felt> {
+    let deref = |cell: &CellRef| {
+        let base = match cell.register {
+            Register::AP => registers.ap,
+            Register::FP => registers.fp,
+        };
+        let address = base.checked_add_signed(cell.offset.into())?;
+        *memory.get(address)?.as_ref()
+    };
+
+    Some(match cell {
+        CellExpression::Deref(cell) => deref(cell)?,
+        CellExpression::DoubleDeref(cell, offset) => {
+            let address = deref(cell)?.to_biguint().to_usize()?;
+            (*memory.get(address.checked_add_signed((*offset).into())?)?)?
+        }
+        CellExpression::Immediate(value) => Felt::from(value),
+        CellExpression::BinOp { op, a, b } => {
+            let a = deref(a)?;
+            let b = match b {
+                DerefOrImmediate::Deref(cell) => deref(cell)?,
+                DerefOrImmediate::Immediate(value) => Felt::from(&value.value),
+            };
+            match op {
+                CellOperator::Add => a + b,
+                CellOperator::Sub => a - b,
+                CellOperator::Mul => a * b,
+                CellOperator::Div => a.field_div(&NonZeroFelt::try_from(b).ok()?),
+            }
+        }
+    })
+}
+
+fn print_divergence(
+    db: &RootDatabase,
+    sierra_program: &Program,
+    debug_info: &DebugInfo,
+    native_trace: &StatementTrace,
+    vm_trace: &StatementTrace,
+    divergence: Divergence,
+    context: usize,
+) {
+    let describe = |statement_idx: StatementIdx| {
+        format!(
+            "{}\n        {}",
+            BacktraceFrame::resolve(db, sierra_program, debug_info, statement_idx),
+            sierra_program.statements[statement_idx.0],
+        )
+    };
+
+    let native_position = divergence.native.unwrap_or(native_trace.steps.len());
+    println!("The traces diverge at native step {native_position}.");
+
+    println!("Last native steps:");
+    for step in &native_trace.steps[native_position.saturating_sub(context)..native_position] {
+        println!("  {}", describe(step.statement_idx));
+        if step.branch.is_some() {
+            println!("        outputs: {step}");
+        }
+    }
+
+    if let Some((native, vm)) = divergence.last_common {
+        println!("Last common statement (native step {native}, VM step {vm}):");
+        println!("  {}", describe(native_trace.steps[native].statement_idx));
+    }
+
+    match divergence.native {
+        Some(position) => println!(
+            "Native executes:\n  {}",
+            describe(native_trace.steps[position].statement_idx)
+        ),
+        None => println!("Native execution ends."),
+    }
+    match divergence.vm {
+        Some(position) => println!(
+            "The VM executes:\n  {}",
+            describe(vm_trace.steps[position].statement_idx)
+        ),
+        None => println!("The VM execution ends."),
+    }
+}
+
+fn find_main(sierra_program: &Program) -> anyhow::Result<cairo_lang_sierra::ids::FunctionId> {
+    sierra_program
+        .funcs
+        .iter()
+        .find(|f| {
+            f.id.debug_name
+                .as_deref()
+                .is_some_and(|name| name.ends_with("::main"))
+        })
+        .map(|f| f.id.clone())
+        .with_context(|| "function ::main not found")
+}
+
+pub fn check_compiler_path(single_file: bool, path: &Path) -> anyhow::Result<()> {
+    if path.is_file() {
+        if !single_file {
+            anyhow::bail!("The given path is a file, but --single-file was not supplied.");
+        }
+    } else if path.is_dir() {
+        if single_file {
+            anyhow::bail!("The given path is a directory, but --single-file was supplied.");
+        }
+    } else {
+        anyhow::bail!("The given path does not exist.");
+    }
+    Ok(())
+}
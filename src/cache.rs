@@ -1,4 +1,6 @@
 pub use self::{aot::AotProgramCache, jit::JitProgramCache};
+use crate::{executor::NativeExecutor, OptLevel};
+use cairo_lang_sierra::program::Program;
 use std::hash::Hash;
 
 pub mod aot;
@@ -13,6 +15,36 @@ where
     Jit(JitProgramCache<'a, K>),
 }
 
+impl<'a, K> ProgramCache<'a, K>
+where
+    K: PartialEq + Eq + Hash,
+{
+    /// Return the executor of a previously compiled program, if any.
+    pub fn get(&self, key: &K) -> Option<NativeExecutor<'a>> {
+        match self {
+            ProgramCache::Aot(cache) => cache.get(key).map(NativeExecutor::Aot),
+            ProgramCache::Jit(cache) => cache.get(key).map(NativeExecutor::Jit),
+        }
+    }
+
+    /// Compile a program and store its executor under the given key.
+    pub fn compile_and_insert(
+        &mut self,
+        key: K,
+        program: &Program,
+        opt_level: OptLevel,
+    ) -> NativeExecutor<'a> {
+        match self {
+            ProgramCache::Aot(cache) => {
+                NativeExecutor::Aot(cache.compile_and_insert(key, program, opt_level))
+            }
+            ProgramCache::Jit(cache) => {
+                NativeExecutor::Jit(cache.compile_and_insert(key, program, opt_level))
+            }
+        }
+    }
+}
+
 impl<'a, K> From<AotProgramCache<'a, K>> for ProgramCache<'a, K>
 where
     K: PartialEq + Eq + Hash,
@@ -49,7 +49,11 @@ use crate::{
     error::Error,
     libfuncs::{BranchArg, LibfuncBuilder, LibfuncHelper},
     metadata::{
+        coverage::CoverageMeta,
         gas::{GasCost, GasMetadata},
+        panic_trace::PanicTraceMeta,
+        profiler::{ProfilerEvent, ProfilerMeta},
+        statement_trace::StatementTraceMeta,
         tail_recursion::TailRecursionMeta,
         MetadataStorage,
     },
@@ -60,7 +64,7 @@ use bumpalo::Bump;
 use cairo_lang_sierra::{
     edit_state,
     extensions::{
-        core::{CoreLibfunc, CoreType},
+        core::{CoreConcreteLibfunc, CoreLibfunc, CoreType},
         gas::CostTokenType,
         ConcreteLibfunc,
     },
@@ -268,6 +272,16 @@ fn compile_func(
     })?;
 
     tracing::trace!("Implementing the entry block.");
+    if let Some(profiler) = metadata.get_mut::<ProfilerMeta>() {
+        profiler.build_event(
+            context,
+            module,
+            &entry_block,
+            Location::name(context, "profiler(entry)", Location::unknown(context)),
+            ProfilerEvent::FunctionEnter,
+            function.entry_point,
+        )?;
+    }
     entry_block.append_operation(cf::br(
         &blocks[&function.entry_point].1,
         &match &statements[function.entry_point.0] {
@@ -339,19 +353,43 @@ fn compile_func(
 
                     let (state, _) = edit_state::take_args(state, invocation.args.iter())?;
 
+                    let concrete_libfunc = registry.get_libfunc(&invocation.libfunc_id)?;
+                    let mut branches = generate_branching_targets(
+                        &blocks,
+                        statements,
+                        statement_idx,
+                        invocation,
+                        &state,
+                    );
+                    if metadata
+                        .get::<StatementTraceMeta>()
+                        .is_some_and(StatementTraceMeta::traces_values)
+                    {
+                        build_statement_trace_branches(
+                            context,
+                            module,
+                            registry,
+                            &region,
+                            &blocks_arena,
+                            metadata,
+                            concrete_libfunc,
+                            statement_idx,
+                            Location::name(
+                                context,
+                                &format!("statement_trace({})", libfunc_name),
+                                Location::unknown(context),
+                            ),
+                            &mut branches,
+                        )?;
+                    }
+
                     let helper = LibfuncHelper {
                         module,
                         init_block: &pre_entry_block,
                         region: &region,
                         blocks_arena: &blocks_arena,
                         last_block: Cell::new(block),
-                        branches: generate_branching_targets(
-                            &blocks,
-                            statements,
-                            statement_idx,
-                            invocation,
-                            &state,
-                        ),
+                        branches,
                         results: invocation
                             .branches
                             .iter()
@@ -359,7 +397,6 @@ fn compile_func(
                             .collect::<Vec<_>>(),
                     };
 
-                    let concrete_libfunc = registry.get_libfunc(&invocation.libfunc_id)?;
                     if let Some(target) = concrete_libfunc.is_function_call() {
                         if target == &function.id && state.is_empty() {
                             // TODO: Defer insertions until after the recursion has been confirmed
@@ -405,6 +442,65 @@ fn compile_func(
                         }
                     }
 
+                    if let Some(panic_trace) = metadata.get_mut::<PanicTraceMeta>() {
+                        if PanicTraceMeta::is_panic(registry, concrete_libfunc)? {
+                            panic_trace.build_record(
+                                context,
+                                module,
+                                block,
+                                Location::name(
+                                    context,
+                                    &format!("panic_trace({})", libfunc_name),
+                                    Location::unknown(context),
+                                ),
+                                statement_idx,
+                            )?;
+                        }
+                    }
+
+                    if let Some(profiler) = metadata.get_mut::<ProfilerMeta>() {
+                        profiler.build_event(
+                            context,
+                            module,
+                            block,
+                            Location::name(
+                                context,
+                                &format!("profiler({})", libfunc_name),
+                                Location::unknown(context),
+                            ),
+                            ProfilerEvent::Invocation,
+                            statement_idx,
+                        )?;
+                    }
+
+                    if let Some(coverage) = metadata.get_mut::<CoverageMeta>() {
+                        coverage.build_hit(
+                            context,
+                            module,
+                            block,
+                            Location::name(
+                                context,
+                                &format!("coverage({})", libfunc_name),
+                                Location::unknown(context),
+                            ),
+                            statement_idx,
+                        )?;
+                    }
+
+                    if let Some(statement_trace) = metadata.get_mut::<StatementTraceMeta>() {
+                        statement_trace.build_statement(
+                            context,
+                            module,
+                            block,
+                            Location::name(
+                                context,
+                                &format!("statement_trace({})", libfunc_name),
+                                Location::unknown(context),
+                            ),
+                            statement_idx,
+                        )?;
+                    }
+
                     concrete_libfunc.build(
                         context,
                         registry,
@@ -465,6 +561,29 @@ fn compile_func(
 
                     let (_, mut values) = edit_state::take_args(state, var_ids.iter())?;
 
+                    if let Some(profiler) = metadata.get_mut::<ProfilerMeta>() {
+                        profiler.build_event(
+                            context,
+                            module,
+                            block,
+                            location,
+                            ProfilerEvent::FunctionReturn,
+                            statement_idx,
+                        )?;
+                    }
+                    if let Some(coverage) = metadata.get_mut::<CoverageMeta>() {
+                        coverage.build_hit(context, module, block, location, statement_idx)?;
+                    }
+                    if let Some(statement_trace) = metadata.get_mut::<StatementTraceMeta>() {
+                        statement_trace.build_return(
+                            context,
+                            module,
+                            block,
+                            location,
+                            statement_idx,
+                        )?;
+                    }
+
                     let mut block = *block;
                     if !tailrec_state.is_empty() {
                         let location = Location::name(
@@ -694,7 +813,13 @@ fn compile_func(
                 Attribute::unit(context),
             ),
         ],
-        Location::unknown(context),
+        Location::name(
+            context,
+            &function_name,
+            debug_info
+                .and_then(|debug_info| debug_info.funcs.get(&function.id).copied())
+                .unwrap_or_else(|| Location::unknown(context)),
+        ),
     ));
 
     tracing::debug!("Done generating function {}.", function.id);
@@ -1061,3 +1186,80 @@ where
         })
         .collect()
 }
+
+/// Route every branch of an invocation through a trampoline block which reports the branch taken
+/// and its outputs to the statement trace before jumping into the original target.
+#[allow(clippy::too_many_arguments)]
+fn build_statement_trace_branches<'ctx, 'this>(
+    context: &'ctx Context,
+    module: &'this Module<'ctx>,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    region: &'this Region<'ctx>,
+    blocks_arena: &'this Bump,
+    metadata: &mut MetadataStorage,
+    libfunc: &CoreConcreteLibfunc,
+    statement_idx: StatementIdx,
+    location: Location<'ctx>,
+    branches: &mut [(&'this Block<'ctx>, Vec<BranchArg<'ctx, 'this>>)],
+) -> Result<(), Error>
+where
+    'this: 'ctx,
+{
+    for (branch_idx, ((target, target_args), signature)) in branches
+        .iter_mut()
+        .zip(libfunc.branch_signatures())
+        .enumerate()
+    {
+        let output_types = signature
+            .vars
+            .iter()
+            .map(|var| {
+                Ok((
+                    registry
+                        .get_type(&var.ty)?
+                        .build(context, module, registry, metadata, &var.ty)?,
+                    location,
+                ))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let trampoline: &'this Block<'ctx> =
+            blocks_arena.alloc(region.append_block(Block::new(&output_types)));
+
+        let outputs = signature
+            .vars
+            .iter()
+            .enumerate()
+            .map(|(idx, var)| Ok((trampoline.argument(idx)?.into(), &var.ty)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        metadata
+            .get_mut::<StatementTraceMeta>()
+            .expect("statement trace metadata should be present")
+            .build_branch(
+                context,
+                module,
+                registry,
+                trampoline,
+                location,
+                statement_idx,
+                branch_idx,
+                &outputs,
+            )?;
+
+        let forwarded_args = target_args
+            .iter()
+            .map(|arg| {
+                Ok(match arg {
+                    BranchArg::External(value) => *value,
+                    BranchArg::Returned(idx) => trampoline.argument(*idx)?.into(),
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        trampoline.append_operation(cf::br(target, &forwarded_args, location));
+
+        *target = trampoline;
+        *target_args = (0..signature.vars.len()).map(BranchArg::Returned).collect();
+    }
+
+    Ok(())
+}
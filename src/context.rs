@@ -5,18 +5,25 @@ use crate::{
     error::Error,
     ffi::{get_data_layout_rep, get_target_triple},
     metadata::{
+        coverage::CoverageMeta,
         gas::{GasMetadata, MetadataComputationConfig},
+        panic_trace::PanicTraceMeta,
+        profiler::ProfilerMeta,
         runtime_bindings::RuntimeBindingsMeta,
+        statement_trace::{StatementTraceMeta, StatementTraceMode},
         MetadataStorage,
     },
     module::NativeModule,
-    utils::run_pass_manager,
+    sierra_version::SierraVersionCheck,
+    starknet::{ContractClassProgram, NativeContractClass},
+    utils::{run_debug_info_pass, run_pass_manager},
 };
 use cairo_lang_sierra::{
     extensions::core::{CoreLibfunc, CoreType},
     program::Program,
     program_registry::ProgramRegistry,
 };
+use cairo_lang_starknet::contract_class::ContractClass;
 use llvm_sys::target::{
     LLVM_InitializeAllAsmPrinters, LLVM_InitializeAllTargetInfos, LLVM_InitializeAllTargetMCs,
     LLVM_InitializeAllTargets,
@@ -36,6 +43,10 @@ use melior::{
 #[derive(Debug, Eq, PartialEq)]
 pub struct NativeContext {
     context: Context,
+    coverage: bool,
+    panic_backtraces: bool,
+    profiler: bool,
+    statement_trace: Option<StatementTraceMode>,
 }
 
 unsafe impl Send for NativeContext {}
@@ -50,7 +61,44 @@ impl Default for NativeContext {
 impl NativeContext {
     pub fn new() -> Self {
         let context = initialize_mlir();
-        Self { context }
+        Self {
+            context,
+            coverage: false,
+            panic_backtraces: false,
+            profiler: false,
+            statement_trace: None,
+        }
+    }
+
+    /// Instrument the compiled programs to count how many times every statement is executed, which
+    /// can be retrieved after running them using
+    /// [`CoverageData::take`](crate::coverage::CoverageData::take).
+    pub fn with_coverage(mut self, enabled: bool) -> Self {
+        self.coverage = enabled;
+        self
+    }
+
+    /// Instrument the compiled programs to record where their panics originate and the frames they
+    /// go through, which can be retrieved after running them using
+    /// [`PanicBacktrace::take`](crate::backtrace::PanicBacktrace::take).
+    pub fn with_panic_backtraces(mut self, enabled: bool) -> Self {
+        self.panic_backtraces = enabled;
+        self
+    }
+
+    /// Instrument the compiled programs to count and time their function calls and libfunc
+    /// invocations, which can be retrieved after running them using
+    /// [`ProfileData::take`](crate::profiler::ProfileData::take).
+    pub fn with_profiler(mut self, enabled: bool) -> Self {
+        self.profiler = enabled;
+        self
+    }
+
+    /// Instrument the compiled programs to trace every executed statement, which can be retrieved
+    /// after running them using [`StatementTrace::take`](crate::statement_trace::StatementTrace::take).
+    pub fn with_statement_trace(mut self, mode: Option<StatementTraceMode>) -> Self {
+        self.statement_trace = mode;
+        self
     }
 
     pub fn context(&self) -> &Context {
@@ -116,6 +164,18 @@ impl NativeContext {
         // Unwrapping here is not necessary since the insertion will only fail if there was
         // already some metadata of the same type.
         metadata.insert(gas_metadata);
+        if self.coverage {
+            metadata.insert(CoverageMeta::default());
+        }
+        if self.panic_backtraces {
+            metadata.insert(PanicTraceMeta::default());
+        }
+        if self.profiler {
+            metadata.insert(ProfilerMeta::default());
+        }
+        if let Some(mode) = self.statement_trace {
+            metadata.insert(StatementTraceMeta::new(mode));
+        }
 
         // Create the Sierra program registry
         let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(program)?;
@@ -145,6 +205,12 @@ impl NativeContext {
 
         run_pass_manager(&self.context, &mut module)?;
 
+        // Lower the Cairo source locations into DWARF so that debuggers and profilers can map the
+        // generated code back to the `.cairo` files.
+        if debug_locations.is_some() {
+            run_debug_info_pass(&self.context, &mut module)?;
+        }
+
         if let Ok(x) = std::env::var("NATIVE_DEBUG_DUMP") {
             if x == "1" || x == "true" {
                 std::fs::write("dump.mlir", module.as_operation().to_string())
@@ -187,6 +253,18 @@ impl NativeContext {
 
         let gas_metadata = GasMetadata::new(program, Some(metadata_config))?;
         metadata.insert(gas_metadata);
+        if self.coverage {
+            metadata.insert(CoverageMeta::default());
+        }
+        if self.panic_backtraces {
+            metadata.insert(PanicTraceMeta::default());
+        }
+        if self.profiler {
+            metadata.insert(ProfilerMeta::default());
+        }
+        if let Some(mode) = self.statement_trace {
+            metadata.insert(StatementTraceMeta::new(mode));
+        }
 
         // Create the Sierra program registry
         let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(program)?;
@@ -204,6 +282,28 @@ impl NativeContext {
 
         Ok(NativeModule::new(module, registry, metadata))
     }
+
+    /// Compiles the sierra program of a contract class into MLIR and then lowers to LLVM.
+    /// The Sierra version and libfuncs of the contract class are validated before code generation
+    /// according to `version_check`, and the class's debug info is applied to the program.
+    /// Returns the corresponding NativeModule along with the class's entry points.
+    pub fn compile_contract_class(
+        &self,
+        contract_class: &ContractClass,
+        version_check: &SierraVersionCheck,
+    ) -> Result<NativeContractClass, Error> {
+        let ContractClassProgram {
+            sierra_version,
+            program,
+            entry_points,
+        } = ContractClassProgram::extract(contract_class)?;
+        version_check.check(&sierra_version, &program)?;
+
+        Ok(NativeContractClass {
+            module: self.compile(&program, None)?,
+            entry_points,
+        })
+    }
 }
 
 /// Initialize an MLIR context.
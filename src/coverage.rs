@@ -0,0 +1,292 @@
+//! # Code coverage
+//!
+//! Programs compiled with coverage enabled (check out
+//! [`NativeContext::with_coverage`](crate::context::NativeContext::with_coverage)) report every
+//! executed Sierra statement (see [the instrumentation](crate::metadata::coverage)). This module
+//! counts them into a [`CoverageData`], which can be mapped to Cairo source lines and functions
+//! using the program's [`DebugInfo`] and exported in the lcov format.
+//!
+//! The counters are kept per thread and accumulate over invocations until they are taken.
+
+use crate::debug_info::{DebugInfo, SourceLocation};
+use cairo_lang_compiler::db::RootDatabase;
+use cairo_lang_sierra::program::{Program, StatementIdx};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    path::PathBuf,
+};
+
+thread_local! {
+    static COVERAGE: RefCell<CoverageData> = RefCell::new(CoverageData::default());
+}
+
+extern "C" fn record_coverage_hit(statement_idx: u64) {
+    COVERAGE.with(|coverage| {
+        *coverage
+            .borrow_mut()
+            .statements
+            .entry(StatementIdx(statement_idx as usize))
+            .or_default() += 1;
+    });
+}
+
+/// Point the coverage hook global of a compiled program to this module's counters.
+///
+/// # Safety
+///
+/// The pointer must point to the hook global of a program compiled with coverage enabled.
+pub(crate) unsafe fn install_coverage_hook(hook_ptr: *mut u64) {
+    *hook_ptr = record_coverage_hit as extern "C" fn(u64) as usize as u64;
+}
+
+/// The number of times every executed statement has been executed.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CoverageData {
+    pub statements: HashMap<StatementIdx, u64>,
+}
+
+impl CoverageData {
+    /// Take the counters of the current thread and reset them.
+    pub fn take() -> Self {
+        COVERAGE.with(|coverage| std::mem::take(&mut *coverage.borrow_mut()))
+    }
+
+    /// Add the counters of another run (ex. from another thread).
+    pub fn merge(&mut self, other: &Self) {
+        for (statement_idx, count) in &other.statements {
+            *self.statements.entry(*statement_idx).or_default() += count;
+        }
+    }
+}
+
+/// The coverage of a single source file.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FileCoverage {
+    /// The hit count of every line with code, by line number.
+    pub lines: BTreeMap<usize, u64>,
+    /// The line and call count of every function defined in the file, by name.
+    pub functions: BTreeMap<String, (usize, u64)>,
+}
+
+/// The coverage of a program, mapped to its Cairo sources.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CoverageReport {
+    pub files: BTreeMap<PathBuf, FileCoverage>,
+}
+
+impl CoverageReport {
+    /// Map the counters to the source lines and functions of the program.
+    ///
+    /// Every statement with a source location contributes a line, whose hit count is the highest
+    /// among its statements. Statements without a location (mostly compiler-generated ones) are
+    /// ignored. Functions are counted by the executions of their entry point.
+    pub fn new(
+        db: &RootDatabase,
+        program: &Program,
+        debug_info: &DebugInfo,
+        data: &CoverageData,
+    ) -> Self {
+        let mut files = BTreeMap::<PathBuf, FileCoverage>::new();
+
+        for (statement_idx, location_id) in &debug_info.statements {
+            let Some(location) =
+                SourceLocation::from_stable_location(db, location_id.get(db).stable_location)
+            else {
+                continue;
+            };
+
+            let count = data.statements.get(statement_idx).copied().unwrap_or(0);
+            let hits = files
+                .entry(location.path)
+                .or_default()
+                .lines
+                .entry(location.line)
+                .or_default();
+            *hits = (*hits).max(count);
+        }
+
+        for function in &program.funcs {
+            let Some(location) = debug_info
+                .funcs
+                .get(&function.id)
+                .and_then(|stable_loc| SourceLocation::from_stable_location(db, *stable_loc))
+            else {
+                continue;
+            };
+
+            let count = data
+                .statements
+                .get(&function.entry_point)
+                .copied()
+                .unwrap_or(0);
+            files
+                .entry(location.path)
+                .or_default()
+                .functions
+                .insert(function.id.to_string(), (location.line, count));
+        }
+
+        Self { files }
+    }
+
+    /// Export the report in the lcov tracefile format.
+    pub fn to_lcov(&self) -> String {
+        let mut output = String::new();
+
+        for (path, file) in &self.files {
+            writeln!(output, "TN:").unwrap();
+            writeln!(output, "SF:{}", path.display()).unwrap();
+
+            for (name, (line, _)) in &file.functions {
+                writeln!(output, "FN:{line},{name}").unwrap();
+            }
+            for (name, (_, count)) in &file.functions {
+                writeln!(output, "FNDA:{count},{name}").unwrap();
+            }
+            writeln!(output, "FNF:{}", file.functions.len()).unwrap();
+            writeln!(
+                output,
+                "FNH:{}",
+                file.functions
+                    .values()
+                    .filter(|(_, count)| *count > 0)
+                    .count()
+            )
+            .unwrap();
+
+            for (line, count) in &file.lines {
+                writeln!(output, "DA:{line},{count}").unwrap();
+            }
+            writeln!(output, "LF:{}", file.lines.len()).unwrap();
+            writeln!(
+                output,
+                "LH:{}",
+                file.lines.values().filter(|count| **count > 0).count()
+            )
+            .unwrap();
+
+            writeln!(output, "end_of_record").unwrap();
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::NativeContext, executor::JitNativeExecutor, utils::test::load_cairo, OptLevel,
+    };
+
+    #[test]
+    fn merge_data() {
+        let mut data = CoverageData {
+            statements: HashMap::from([(StatementIdx(0), 1), (StatementIdx(1), 2)]),
+        };
+        data.merge(&CoverageData {
+            statements: HashMap::from([(StatementIdx(1), 3), (StatementIdx(2), 4)]),
+        });
+
+        assert_eq!(
+            data,
+            CoverageData {
+                statements: HashMap::from([
+                    (StatementIdx(0), 1),
+                    (StatementIdx(1), 5),
+                    (StatementIdx(2), 4),
+                ]),
+            }
+        );
+    }
+
+    #[test]
+    fn lcov() {
+        let report = CoverageReport {
+            files: BTreeMap::from([(
+                PathBuf::from("src/lib.cairo"),
+                FileCoverage {
+                    lines: BTreeMap::from([(2, 1), (3, 0), (7, 4)]),
+                    functions: BTreeMap::from([
+                        ("lib::lib::covered".to_string(), (1, 1)),
+                        ("lib::lib::uncovered".to_string(), (6, 0)),
+                    ]),
+                },
+            )]),
+        };
+
+        assert_eq!(
+            report.to_lcov(),
+            [
+                "TN:",
+                "SF:src/lib.cairo",
+                "FN:1,lib::lib::covered",
+                "FN:6,lib::lib::uncovered",
+                "FNDA:1,lib::lib::covered",
+                "FNDA:0,lib::lib::uncovered",
+                "FNF:2",
+                "FNH:1",
+                "DA:2,1",
+                "DA:3,0",
+                "DA:7,4",
+                "LF:3",
+                "LH:2",
+                "end_of_record",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn cover_program() {
+        let (module_name, program) = load_cairo! {
+            fn run_test() -> u32 {
+                double(3)
+            }
+
+            #[inline(never)]
+            fn double(x: u32) -> u32 {
+                x * 2
+            }
+
+            #[inline(never)]
+            fn unused(x: u32) -> u32 {
+                x + 1
+            }
+        };
+
+        let native_context = NativeContext::new().with_coverage(true);
+        let native_module = native_context.compile(&program, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(native_module, OptLevel::None);
+
+        let find_function = |name: &str| {
+            program
+                .funcs
+                .iter()
+                .find(|x| {
+                    x.id.debug_name.as_deref()
+                        == Some(format!("{0}::{0}::{name}", module_name).as_str())
+                })
+                .unwrap()
+        };
+
+        let entry_point = find_function("run_test");
+        CoverageData::take();
+        executor.invoke_dynamic(&entry_point.id, &[], None).unwrap();
+        executor.invoke_dynamic(&entry_point.id, &[], None).unwrap();
+
+        let data = CoverageData::take();
+        assert_eq!(data.statements.get(&entry_point.entry_point), Some(&2));
+        assert_eq!(
+            data.statements.get(&find_function("double").entry_point),
+            Some(&2)
+        );
+        assert_eq!(
+            data.statements.get(&find_function("unused").entry_point),
+            None
+        );
+    }
+}
@@ -14,7 +14,11 @@ use cairo_lang_sierra::{
     program::{Program, StatementIdx},
 };
 use melior::{ir::Location, Context};
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    path::PathBuf,
+};
 
 mod funcs;
 mod libfunc_declarations;
@@ -136,23 +140,54 @@ impl<'c> DebugLocations<'c> {
     }
 }
 
+/// A position within a Cairo source file. Lines and columns are one-based.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SourceLocation {
+    pub path: PathBuf,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl SourceLocation {
+    /// Resolve a stable location into its file and position. Virtual files (generated code) don't
+    /// have a location.
+    pub fn from_stable_location(db: &RootDatabase, stable_loc: StableLocation) -> Option<Self> {
+        let diagnostic_location = stable_loc.diagnostic_location(db);
+
+        let path = match db.lookup_intern_file(diagnostic_location.file_id) {
+            FileLongId::OnDisk(path) => path,
+            FileLongId::Virtual(_) => return None,
+        };
+
+        let pos = diagnostic_location
+            .span
+            .start
+            .position_in_file(db, diagnostic_location.file_id)
+            .unwrap();
+
+        // Positions are zero-based, but DWARF (and therefore debuggers) expect one-based lines and
+        // columns.
+        Some(Self {
+            path,
+            line: pos.line + 1,
+            col: pos.col + 1,
+        })
+    }
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.path.display(), self.line, self.col)
+    }
+}
+
 fn extract_location_from_stable_loc<'c>(
     context: &'c Context,
     db: &RootDatabase,
     stable_loc: StableLocation,
 ) -> Location<'c> {
-    let diagnostic_location = stable_loc.diagnostic_location(db);
-
-    let path = match db.lookup_intern_file(diagnostic_location.file_id) {
-        FileLongId::OnDisk(path) => path,
-        FileLongId::Virtual(_) => return Location::unknown(context),
-    };
-
-    let pos = diagnostic_location
-        .span
-        .start
-        .position_in_file(db, diagnostic_location.file_id)
-        .unwrap();
-
-    Location::new(context, &path.to_string_lossy(), pos.line, pos.col)
+    match SourceLocation::from_stable_location(db, stable_loc) {
+        Some(x) => Location::new(context, &x.path.to_string_lossy(), x.line, x.col),
+        None => Location::unknown(context),
+    }
 }
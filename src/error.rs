@@ -2,6 +2,7 @@
 use cairo_lang_sierra::{
     edit_state::EditStateError, ids::ConcreteTypeId, program_registry::ProgramRegistryError,
 };
+use cairo_lang_starknet::allowed_libfuncs::AllowedLibfuncsError;
 
 use std::{alloc::LayoutError, num::TryFromIntError};
 use thiserror::Error;
@@ -59,6 +60,43 @@ pub enum Error {
 
     #[error("llvm error")]
     LLVMCompileError(String),
+
+    #[error("unsupported sierra version {0}, the latest supported version is {1}")]
+    UnsupportedSierraVersion(String, String),
+
+    #[error("libfunc '{0}' is not allowed by the libfunc list '{1}'")]
+    DisallowedLibfunc(String, String),
+
+    #[error(transparent)]
+    AllowedLibfuncsError(#[from] AllowedLibfuncsError),
+
+    #[error("no allowed libfunc list '{0}' is available for sierra version {1}")]
+    MissingAllowedLibfuncsList(String, String),
+
+    #[error("error deserializing the sierra program: {0}")]
+    SierraProgramDeserialization(String),
+
+    #[error("the contract class has no function with index {0}")]
+    MissingEntryPoint(usize),
+
+    #[error("the replay diverged from the recording: {0}")]
+    ReplayMismatch(String),
+
+    #[error("can't generate arguments of type '{0}'")]
+    UnsupportedArgumentType(String),
+
+    #[error("the fuzzing was aborted: {0}")]
+    FuzzAborted(String),
+
+    #[error("the abi has no item named '{0}'")]
+    UnknownAbiItem(String),
+
+    #[error("the values don't match the abi: {0}")]
+    AbiMismatch(String),
+
+    #[cfg(feature = "with-serde")]
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
 }
 
 pub fn make_unexpected_value_error(expected: String) -> Error {
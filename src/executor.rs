@@ -1,8 +1,25 @@
-pub use self::{aot::AotNativeExecutor, jit::JitNativeExecutor};
+pub use self::{
+    aot::AotNativeExecutor,
+    jit::{JitDebugOptions, JitNativeExecutor},
+};
+pub use crate::ffi::ExecutionEngine;
 use crate::{
+    backtrace::install_panic_trace_hook,
+    coverage::install_coverage_hook,
     error::Error,
     execution_result::{BuiltinStats, ContractExecutionResult, ExecutionResult},
-    starknet::{handler::StarknetSyscallHandlerCallbacks, StarknetSyscallHandler},
+    metadata::{
+        cheatcode::CHEATCODE_HOOK_SYMBOL, coverage::COVERAGE_HOOK_SYMBOL,
+        panic_trace::PANIC_TRACE_HOOK_SYMBOL, profiler::PROFILER_HOOK_SYMBOL,
+        statement_trace::STATEMENT_TRACE_HOOK_SYMBOL,
+    },
+    profiler::install_profiler_hook,
+    starknet::{
+        handler::StarknetSyscallHandlerCallbacks,
+        testing::{install_cheatcode_hook, set_cheatcode_target},
+        StarknetSyscallHandler,
+    },
+    statement_trace::install_statement_trace_hook,
     types::TypeBuilder,
     utils::get_integer_layout,
     values::JitValue,
@@ -24,6 +41,7 @@ use std::{
     arch::global_asm,
     ptr::{null_mut, NonNull},
     rc::Rc,
+    sync::Arc,
 };
 
 mod aot;
@@ -48,9 +66,11 @@ extern "C" {
     );
 }
 
+/// An AOT or JIT executor. AOT executors are reference counted atomically, since unlike the JIT
+/// ones they can be shared between threads.
 #[derive(Debug, Clone)]
 pub enum NativeExecutor<'m> {
-    Aot(Rc<AotNativeExecutor>),
+    Aot(Arc<AotNativeExecutor>),
     Jit(Rc<JitNativeExecutor<'m>>),
 }
 
@@ -72,7 +92,7 @@ impl<'a> NativeExecutor<'a> {
         function_id: &FunctionId,
         args: &[JitValue],
         gas: Option<u128>,
-        syscall_handler: impl StarknetSyscallHandler,
+        syscall_handler: &mut dyn StarknetSyscallHandler,
     ) -> Result<ExecutionResult, Error> {
         match self {
             NativeExecutor::Aot(executor) => executor.invoke_dynamic_with_syscall_handler(
@@ -95,7 +115,7 @@ impl<'a> NativeExecutor<'a> {
         function_id: &FunctionId,
         args: &[Felt],
         gas: Option<u128>,
-        syscall_handler: impl StarknetSyscallHandler,
+        syscall_handler: &mut dyn StarknetSyscallHandler,
     ) -> Result<ContractExecutionResult, Error> {
         match self {
             NativeExecutor::Aot(executor) => {
@@ -110,7 +130,7 @@ impl<'a> NativeExecutor<'a> {
 
 impl<'m> From<AotNativeExecutor> for NativeExecutor<'m> {
     fn from(value: AotNativeExecutor) -> Self {
-        Self::Aot(Rc::new(value))
+        Self::Aot(Arc::new(value))
     }
 }
 
@@ -120,15 +140,40 @@ impl<'m> From<JitNativeExecutor<'m>> for NativeExecutor<'m> {
     }
 }
 
+/// Point the [host hooks](crate::metadata::host_hook) of an instrumented program to their
+/// collectors.
+///
+/// # Safety
+///
+/// The lookup function must return the address of the given hook global, or `None` if the program
+/// doesn't declare it.
+unsafe fn install_host_hooks(mut lookup: impl FnMut(&str) -> Option<*mut u64>) {
+    let hooks: [(&str, unsafe fn(*mut u64)); 5] = [
+        (CHEATCODE_HOOK_SYMBOL, install_cheatcode_hook),
+        (COVERAGE_HOOK_SYMBOL, install_coverage_hook),
+        (PANIC_TRACE_HOOK_SYMBOL, install_panic_trace_hook),
+        (PROFILER_HOOK_SYMBOL, install_profiler_hook),
+        (STATEMENT_TRACE_HOOK_SYMBOL, install_statement_trace_hook),
+    ];
+
+    for (symbol, install) in hooks {
+        if let Some(hook_ptr) = lookup(symbol) {
+            install(hook_ptr);
+        }
+    }
+}
+
 fn invoke_dynamic(
     registry: &ProgramRegistry<CoreType, CoreLibfunc>,
     function_ptr: *const c_void,
     function_signature: &FunctionSignature,
     args: &[JitValue],
     gas: u128,
-    mut syscall_handler: Option<impl StarknetSyscallHandler>,
+    syscall_handler: Option<&mut dyn StarknetSyscallHandler>,
 ) -> ExecutionResult {
     tracing::info!("Invoking function with signature: {function_signature:?}.");
+    crate::backtrace::clear_panic_trace();
+    crate::statement_trace::clear_statement_trace();
 
     let is_builtin = <CoreTypeConcrete as TypeBuilder>::is_builtin;
     let is_zst = <CoreTypeConcrete as TypeBuilder>::is_zst;
@@ -136,6 +181,20 @@ fn invoke_dynamic(
     let arena = Bump::new();
     let mut invoke_data = ArgumentMapper::new(&arena, registry);
 
+    // Cheatcodes don't receive the syscall handler, therefore its callbacks are kept aside while
+    // the program runs (even if it doesn't take a syscall handler). Both the syscalls and the
+    // cheatcodes reach the handler through the same pointer to the callbacks, so they never hold
+    // separate borrows of it. The guard is dropped before the arena.
+    let syscall_handler = syscall_handler.map(|handler| {
+        let callbacks: *mut _ =
+            arena.alloc(StarknetSyscallHandlerCallbacks::new(arena.alloc(handler)));
+        callbacks
+    });
+    let _cheatcode_target = syscall_handler.map(|callbacks| unsafe {
+        // SAFETY: The callbacks outlive the guard, and cheatcodes never run during a syscall.
+        set_cheatcode_target(callbacks)
+    });
+
     // Generate return pointer (if necessary).
     //
     // Generated when either:
@@ -189,19 +248,11 @@ fn invoke_dynamic(
                 get_integer_layout(128).align(),
                 &[gas as u64, (gas >> 64) as u64],
             ),
-            CoreTypeConcrete::StarkNet(StarkNetTypeConcrete::System(_)) => {
-                match syscall_handler.as_mut() {
-                    Some(syscall_handler) => {
-                        let syscall_handler =
-                            arena.alloc(StarknetSyscallHandlerCallbacks::new(syscall_handler));
-                        invoke_data.push_aligned(
-                            get_integer_layout(64).align(),
-                            &[syscall_handler as *mut _ as u64],
-                        )
-                    }
-                    None => panic!("Syscall handler is required"),
-                }
-            }
+            CoreTypeConcrete::StarkNet(StarkNetTypeConcrete::System(_)) => match syscall_handler {
+                Some(syscall_handler) => invoke_data
+                    .push_aligned(get_integer_layout(64).align(), &[syscall_handler as u64]),
+                None => panic!("Syscall handler is required"),
+            },
             type_info => invoke_data
                 .push(
                     type_id,
@@ -477,7 +528,8 @@ impl<'a> ArgumentMapper<'a> {
                     &Felt::from_bytes_be_slice(value).to_le_digits(),
                 );
             }
-            (CoreTypeConcrete::Felt252Dict(_), JitValue::Felt252Dict { .. }) => {
+            (CoreTypeConcrete::Felt252Dict(_), JitValue::Felt252Dict { .. })
+            | (CoreTypeConcrete::SquashedFelt252Dict(_), JitValue::SquashedFelt252Dict { .. }) => {
                 #[cfg(not(feature = "with-runtime"))]
                 unimplemented!("enable the `with-runtime` feature to use felt252 dicts");
 
@@ -757,20 +809,21 @@ fn parse_result(
                 JitValue::from_jit(return_ptr.unwrap(), type_id, registry)
             }
         }
-        CoreTypeConcrete::Felt252Dict(_) => match return_ptr {
-            Some(return_ptr) => JitValue::from_jit(
-                unsafe { *return_ptr.cast::<NonNull<()>>().as_ref() },
-                type_id,
-                registry,
-            ),
-            None => JitValue::from_jit(
-                NonNull::new(ret_registers[0] as *mut ()).unwrap(),
-                type_id,
-                registry,
-            ),
-        },
+        CoreTypeConcrete::Felt252Dict(_) | CoreTypeConcrete::SquashedFelt252Dict(_) => {
+            match return_ptr {
+                Some(return_ptr) => JitValue::from_jit(
+                    unsafe { *return_ptr.cast::<NonNull<()>>().as_ref() },
+                    type_id,
+                    registry,
+                ),
+                None => JitValue::from_jit(
+                    NonNull::new(ret_registers[0] as *mut ()).unwrap(),
+                    type_id,
+                    registry,
+                ),
+            }
+        }
         CoreTypeConcrete::Felt252DictEntry(_) => todo!(),
-        CoreTypeConcrete::SquashedFelt252Dict(_) => todo!(),
         CoreTypeConcrete::Span(_) => todo!(),
         CoreTypeConcrete::Snapshot(_) => todo!(),
         CoreTypeConcrete::Bytes31(_) => todo!(),
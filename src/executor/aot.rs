@@ -3,7 +3,7 @@ use crate::{
     execution_result::{ContractExecutionResult, ExecutionResult},
     metadata::gas::GasMetadata,
     module::NativeModule,
-    starknet::{DummySyscallHandler, StarknetSyscallHandler},
+    starknet::StarknetSyscallHandler,
     utils::generate_function_name,
     values::JitValue,
     OptLevel,
@@ -37,6 +37,17 @@ impl AotNativeExecutor {
         registry: ProgramRegistry<CoreType, CoreLibfunc>,
         gas_metadata: GasMetadata,
     ) -> Self {
+        // The hook globals only exist when the program was compiled with the matching
+        // instrumentation.
+        unsafe {
+            super::install_host_hooks(|symbol| {
+                library
+                    .get::<*mut u64>(symbol.as_bytes())
+                    .ok()
+                    .map(|hook_ptr| *hook_ptr)
+            })
+        };
+
         Self {
             library,
             registry,
@@ -57,11 +68,11 @@ impl AotNativeExecutor {
         let object_data = crate::module_to_object(&module, opt_level).unwrap();
         crate::object_to_shared_lib(&object_data, &library_path).unwrap();
 
-        Self {
-            library: unsafe { Library::new(library_path).unwrap() },
+        Self::new(
+            unsafe { Library::new(library_path).unwrap() },
             registry,
-            gas_metadata: metadata.remove().unwrap(),
-        }
+            metadata.remove().unwrap(),
+        )
     }
 
     pub fn invoke_dynamic(
@@ -81,7 +92,7 @@ impl AotNativeExecutor {
             self.extract_signature(function_id),
             args,
             available_gas,
-            Option::<DummySyscallHandler>::None,
+            None,
         ))
     }
 
@@ -90,7 +101,7 @@ impl AotNativeExecutor {
         function_id: &FunctionId,
         args: &[JitValue],
         gas: Option<u128>,
-        syscall_handler: impl StarknetSyscallHandler,
+        syscall_handler: &mut dyn StarknetSyscallHandler,
     ) -> Result<ExecutionResult, Error> {
         let available_gas = self
             .gas_metadata
@@ -112,7 +123,7 @@ impl AotNativeExecutor {
         function_id: &FunctionId,
         args: &[Felt],
         gas: Option<u128>,
-        syscall_handler: impl StarknetSyscallHandler,
+        syscall_handler: &mut dyn StarknetSyscallHandler,
     ) -> Result<ContractExecutionResult, Error> {
         let available_gas = self
             .gas_metadata
@@ -1,10 +1,11 @@
 use crate::{
     error::Error,
     execution_result::{ContractExecutionResult, ExecutionResult},
+    ffi::ExecutionEngine,
     metadata::gas::GasMetadata,
     module::NativeModule,
-    starknet::{DummySyscallHandler, StarknetSyscallHandler},
-    utils::{create_engine, generate_function_name},
+    starknet::StarknetSyscallHandler,
+    utils::{create_engine_with_debug_options, generate_function_name},
     values::JitValue,
     OptLevel,
 };
@@ -15,8 +16,40 @@ use cairo_lang_sierra::{
     program_registry::ProgramRegistry,
 };
 use libc::c_void;
-use melior::{ir::Module, ExecutionEngine};
+use melior::ir::{attribute::StringAttribute, Module};
 use starknet_types_core::felt::Felt;
+use std::{
+    collections::HashSet,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Size given to the last function in the perf map, whose end isn't known.
+const PERF_MAP_LAST_FUNCTION_SIZE: usize = 0x1000;
+
+/// Debugger and profiler integrations for the JIT-compiled code.
+///
+/// Every integration is disabled by default.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct JitDebugOptions {
+    /// Register the generated code with the GDB JIT interface, so that `gdb` and `lldb` can see its
+    /// symbols and debug info.
+    pub gdb_jit_interface: bool,
+    /// Register LLVM's perf JIT event listener, which writes a jitdump file for `perf inject --jit`
+    /// when LLVM is built with perf support.
+    pub perf_jitdump: bool,
+    /// Append the generated functions to the given perf map, named after the Sierra functions'
+    /// `debug_name`. `perf report` reads it from [`Self::default_perf_map_path`].
+    pub perf_map: Option<PathBuf>,
+}
+
+impl JitDebugOptions {
+    /// Return the path where `perf` looks for the current process' perf map.
+    pub fn default_perf_map_path() -> PathBuf {
+        PathBuf::from(format!("/tmp/perf-{}.map", std::process::id()))
+    }
+}
 
 /// A MLIR JIT execution engine in the context of Cairo Native.
 pub struct JitNativeExecutor<'m> {
@@ -38,19 +71,58 @@ impl std::fmt::Debug for JitNativeExecutor<'_> {
 }
 
 impl<'m> JitNativeExecutor<'m> {
+    /// Create the executor with the default [`JitDebugOptions`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if LLVM fails to create the execution engine.
     pub fn from_native_module(native_module: NativeModule<'m>, opt_level: OptLevel) -> Self {
+        Self::from_native_module_with_debug_options(
+            native_module,
+            opt_level,
+            JitDebugOptions::default(),
+        )
+        .expect("failed to create the execution engine")
+    }
+
+    /// Same as [`Self::from_native_module`], enabling the requested debugger and profiler
+    /// integrations.
+    pub fn from_native_module_with_debug_options(
+        native_module: NativeModule<'m>,
+        opt_level: OptLevel,
+        debug_options: JitDebugOptions,
+    ) -> Result<Self, Error> {
         let NativeModule {
             module,
             registry,
             metadata,
         } = native_module;
 
-        Self {
-            engine: create_engine(&module, &metadata, opt_level),
+        let engine =
+            create_engine_with_debug_options(&module, &metadata, opt_level, &debug_options)?;
+
+        // Only look up the globals the module declares, since looking up missing symbols makes the
+        // engine report errors.
+        let globals = find_globals(&module);
+        unsafe {
+            super::install_host_hooks(|symbol| {
+                globals
+                    .contains(symbol)
+                    .then(|| engine.lookup(symbol).cast())
+            })
+        };
+        if let Some(path) = &debug_options.perf_map {
+            if let Err(e) = write_perf_map(&engine, &module, path) {
+                tracing::warn!("failed to write the perf map: {e}");
+            }
+        }
+
+        Ok(Self {
+            engine,
             module,
             registry,
             gas_metadata: metadata.get::<GasMetadata>().cloned().unwrap(),
-        }
+        })
     }
 
     pub fn program_registry(&self) -> &ProgramRegistry<CoreType, CoreLibfunc> {
@@ -81,7 +153,7 @@ impl<'m> JitNativeExecutor<'m> {
             self.extract_signature(function_id),
             args,
             available_gas,
-            Option::<DummySyscallHandler>::None,
+            None,
         ))
     }
 
@@ -93,7 +165,7 @@ impl<'m> JitNativeExecutor<'m> {
         function_id: &FunctionId,
         args: &[JitValue],
         gas: Option<u128>,
-        syscall_handler: impl StarknetSyscallHandler,
+        syscall_handler: &mut dyn StarknetSyscallHandler,
     ) -> Result<ExecutionResult, Error> {
         let available_gas = self
             .gas_metadata
@@ -115,7 +187,7 @@ impl<'m> JitNativeExecutor<'m> {
         function_id: &FunctionId,
         args: &[Felt],
         gas: Option<u128>,
-        syscall_handler: impl StarknetSyscallHandler,
+        syscall_handler: &mut dyn StarknetSyscallHandler,
     ) -> Result<ContractExecutionResult, Error> {
         let available_gas = self
             .gas_metadata
@@ -154,3 +226,125 @@ impl<'m> JitNativeExecutor<'m> {
             .signature
     }
 }
+
+/// Return the names of the globals declared in the module.
+fn find_globals(module: &Module) -> HashSet<String> {
+    let mut globals = HashSet::new();
+
+    let mut next_op = module.body().first_operation();
+    while let Some(op) = next_op {
+        next_op = op.next_in_block();
+
+        if op.name().as_string_ref().as_str() == Ok("llvm.mlir.global") {
+            if let Ok(name) = op.attribute("sym_name").and_then(StringAttribute::try_from) {
+                globals.insert(name.value().to_string());
+            }
+        }
+    }
+
+    globals
+}
+
+/// Append the address ranges of the JIT-compiled functions to the perf map at `path`.
+///
+/// The engine doesn't expose the size of the functions, therefore each one is assumed to extend
+/// until the next one.
+fn write_perf_map(engine: &ExecutionEngine, module: &Module, path: &Path) -> std::io::Result<()> {
+    let mut symbols = Vec::new();
+
+    let mut next_op = module.body().first_operation();
+    while let Some(op) = next_op {
+        next_op = op.next_in_block();
+
+        // Skip everything but function definitions (runtime functions are only declared).
+        let is_definition = op.name().as_string_ref().as_str() == Ok("llvm.func")
+            && op
+                .region(0)
+                .is_ok_and(|region| region.first_block().is_some());
+        if !is_definition {
+            continue;
+        }
+
+        if let Ok(name) = op.attribute("sym_name").and_then(StringAttribute::try_from) {
+            let ptr = engine.lookup(name.value()) as usize;
+            if ptr != 0 {
+                symbols.push((ptr, demangle_symbol(name.value())));
+            }
+        }
+    }
+    symbols.sort_unstable_by_key(|(ptr, _)| *ptr);
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for (i, (ptr, name)) in symbols.iter().enumerate() {
+        let size = symbols
+            .get(i + 1)
+            .map_or(PERF_MAP_LAST_FUNCTION_SIZE, |(next_ptr, _)| next_ptr - ptr);
+        writeln!(file, "{ptr:x} {size:x} {name}")?;
+    }
+
+    Ok(())
+}
+
+/// Convert a symbol generated by [`generate_function_name`] back into its Sierra `debug_name`,
+/// keeping the prefix of the C interface wrappers.
+fn demangle_symbol(symbol: &str) -> String {
+    let (prefix, name) = match symbol.strip_prefix("_mlir_ciface_") {
+        Some(name) => ("_mlir_ciface_", name),
+        None => ("", symbol),
+    };
+
+    let name = match name.rfind("(f") {
+        Some(idx) if name.ends_with(')') => &name[..idx],
+        _ => name,
+    };
+
+    format!("{prefix}{name}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{context::NativeContext, utils::test::load_cairo};
+
+    #[test]
+    fn demangle_symbols() {
+        assert_eq!(
+            demangle_symbol("program::program::main(f2)"),
+            "program::program::main"
+        );
+        assert_eq!(
+            demangle_symbol("_mlir_ciface_core::felt252_add(f10)"),
+            "_mlir_ciface_core::felt252_add"
+        );
+        assert_eq!(demangle_symbol("f7"), "f7");
+        assert_eq!(demangle_symbol("_mlir_ciface_f7"), "_mlir_ciface_f7");
+    }
+
+    #[test]
+    fn perf_map() {
+        let (_, program) = load_cairo! {
+            fn run_test() -> felt252 {
+                42
+            }
+        };
+
+        let perf_map_file = tempfile::NamedTempFile::new().unwrap();
+
+        let native_context = NativeContext::new();
+        let native_module = native_context.compile(&program, None).unwrap();
+        let _executor = JitNativeExecutor::from_native_module_with_debug_options(
+            native_module,
+            OptLevel::None,
+            JitDebugOptions {
+                perf_map: Some(perf_map_file.path().to_path_buf()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let perf_map = std::fs::read_to_string(perf_map_file.path()).unwrap();
+        assert!(perf_map
+            .lines()
+            .any(|line| line.ends_with("::run_test") && line.split(' ').count() == 3));
+    }
+}
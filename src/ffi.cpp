@@ -1,10 +1,16 @@
 #include <llvm-c/Support.h>
+#include <llvm/ExecutionEngine/Orc/JITTargetMachineBuilder.h>
 #include <llvm/IR/LLVMContext.h>
 #include <llvm/IR/Module.h>
+#include <mlir/CAPI/ExecutionEngine.h>
 #include <mlir/CAPI/IR.h>
+#include <mlir/CAPI/Pass.h>
 #include <mlir/CAPI/Support.h>
 #include <mlir/CAPI/Wrap.h>
 #include <mlir/Dialect/LLVMIR/LLVMTypes.h>
+#include <mlir/Dialect/LLVMIR/Transforms/DIScopeForLLVMFuncOp.h>
+#include <mlir/ExecutionEngine/ExecutionEngine.h>
+#include <mlir/ExecutionEngine/OptUtils.h>
 #include <mlir/IR/Types.h>
 #include <mlir/Target/LLVMIR/ModuleTranslation.h>
 
@@ -29,3 +35,48 @@ extern "C" LLVMModuleRef mlirTranslateModuleToLLVMIR(MlirOperation module,
 
   return moduleRef;
 }
+
+extern "C" MlirPass mlirCreateDIScopeForLLVMFuncOpPass()
+{
+    return wrap(mlir::LLVM::createDIScopeForLLVMFuncOpPass().release());
+}
+
+// Same as `mlirExecutionEngineCreate`, but allowing to choose which JIT event listeners are
+// registered. The C API always registers both of them.
+extern "C" MlirExecutionEngine mlirExecutionEngineCreateWithListeners(
+    MlirModule op, int optLevel, int numPaths, const MlirStringRef *sharedLibPaths,
+    bool enableObjectDump, bool enableGDBNotificationListener,
+    bool enablePerfNotificationListener)
+{
+    auto tmBuilderOrError = llvm::orc::JITTargetMachineBuilder::detectHost();
+    if (!tmBuilderOrError) {
+        llvm::consumeError(tmBuilderOrError.takeError());
+        return MlirExecutionEngine{nullptr};
+    }
+    auto tmOrError = tmBuilderOrError->createTargetMachine();
+    if (!tmOrError) {
+        llvm::consumeError(tmOrError.takeError());
+        return MlirExecutionEngine{nullptr};
+    }
+
+    llvm::SmallVector<llvm::StringRef, 4> libPaths;
+    for (int i = 0; i < numPaths; ++i)
+        libPaths.push_back(llvm::StringRef(sharedLibPaths[i].data, sharedLibPaths[i].length));
+
+    mlir::ExecutionEngineOptions jitOptions;
+    jitOptions.transformer = mlir::makeOptimizingTransformer(optLevel, /*sizeLevel=*/0,
+                                                             tmOrError->get());
+    jitOptions.jitCodeGenOptLevel = static_cast<llvm::CodeGenOpt::Level>(optLevel);
+    jitOptions.sharedLibPaths = libPaths;
+    jitOptions.enableObjectDump = enableObjectDump;
+    jitOptions.enableGDBNotificationListener = enableGDBNotificationListener;
+    jitOptions.enablePerfNotificationListener = enablePerfNotificationListener;
+
+    auto jitOrError = mlir::ExecutionEngine::create(unwrap(op), jitOptions);
+    if (!jitOrError) {
+        llvm::consumeError(jitOrError.takeError());
+        return MlirExecutionEngine{nullptr};
+    }
+
+    return wrap(jitOrError->release());
+}
@@ -15,8 +15,15 @@ use llvm_sys::{
         LLVMTargetMachineEmitToMemoryBuffer, LLVMTargetRef,
     },
 };
-use melior::ir::{Module, Type, TypeLike};
-use mlir_sys::MlirOperation;
+use melior::{
+    ir::{Module, Type, TypeLike},
+    pass::Pass,
+    StringRef,
+};
+use mlir_sys::{
+    mlirExecutionEngineDestroy, mlirExecutionEngineLookup, mlirExecutionEngineRegisterSymbol,
+    MlirExecutionEngine, MlirModule, MlirOperation, MlirPass, MlirStringRef,
+};
 use std::{
     borrow::Cow,
     error::Error,
@@ -41,6 +48,20 @@ extern "C" {
         module_operation_ptr: MlirOperation,
         llvm_context: LLVMContextRef,
     ) -> LLVMModuleRef;
+
+    /// Create the `ensure-debug-info-scope-on-llvm-func` pass.
+    fn mlirCreateDIScopeForLLVMFuncOpPass() -> MlirPass;
+
+    /// Same as `mlirExecutionEngineCreate`, but choosing which JIT event listeners to register.
+    fn mlirExecutionEngineCreateWithListeners(
+        module: MlirModule,
+        opt_level: i32,
+        num_paths: i32,
+        shared_lib_paths: *const MlirStringRef,
+        enable_object_dump: bool,
+        enable_gdb_notification_listener: bool,
+        enable_perf_notification_listener: bool,
+    ) -> MlirExecutionEngine;
 }
 
 /// For any `!llvm.struct<...>` type, return the MLIR type of the field at the requested index.
@@ -51,6 +72,73 @@ pub fn get_struct_field_type_at<'c>(r#type: &Type<'c>, index: usize) -> Type<'c>
     unsafe { Type::from_raw(ty_ptr) }
 }
 
+/// Create a pass which attaches a DWARF subprogram (and a compile unit) to every `llvm.func`.
+///
+/// Without a subprogram scope, operation locations are dropped when translating to LLVM IR. With
+/// it, they're lowered into line tables which end up in both the object files and the JIT code.
+pub fn create_di_scope_for_llvm_func_op() -> Pass {
+    unsafe { Pass::from_raw(mlirCreateDIScopeForLLVMFuncOpPass()) }
+}
+
+/// A MLIR JIT execution engine.
+///
+/// Melior's `ExecutionEngine` can only be created with every JIT event listener enabled, therefore
+/// this type owns the raw handle created by [`create_execution_engine`] instead.
+pub struct ExecutionEngine {
+    raw: MlirExecutionEngine,
+}
+
+impl ExecutionEngine {
+    /// Look up a symbol of the compiled code, returning a null pointer if it doesn't exist.
+    pub fn lookup(&self, name: &str) -> *mut () {
+        unsafe { mlirExecutionEngineLookup(self.raw, StringRef::from(name).to_raw()) as *mut () }
+    }
+
+    /// Register a symbol which the compiled code can link against.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must be valid for every use the compiled code makes of the symbol.
+    pub unsafe fn register_symbol(&self, name: &str, ptr: *mut ()) {
+        mlirExecutionEngineRegisterSymbol(self.raw, StringRef::from(name).to_raw(), ptr.cast());
+    }
+}
+
+impl Drop for ExecutionEngine {
+    fn drop(&mut self) {
+        unsafe { mlirExecutionEngineDestroy(self.raw) }
+    }
+}
+
+/// Create an execution engine for the module, choosing whether the generated code should be
+/// registered with the GDB JIT interface and with LLVM's perf JIT event listener.
+pub fn create_execution_engine(
+    module: &Module,
+    opt_level: usize,
+    enable_gdb_listener: bool,
+    enable_perf_listener: bool,
+) -> Result<ExecutionEngine, CompileError> {
+    let raw = unsafe {
+        mlirExecutionEngineCreateWithListeners(
+            module.to_raw(),
+            opt_level as i32,
+            0,
+            std::ptr::null(),
+            false,
+            enable_gdb_listener,
+            enable_perf_listener,
+        )
+    };
+
+    if raw.ptr.is_null() {
+        return Err(CompileError::LLVMCompileError(
+            "failed to create the execution engine".to_string(),
+        ));
+    }
+
+    Ok(ExecutionEngine { raw })
+}
+
 #[derive(Debug, Clone)]
 pub struct LLVMCompileError(String);
 
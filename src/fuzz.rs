@@ -0,0 +1,439 @@
+//! # Fuzzing
+//!
+//! A property-based fuzzing harness for compiled functions. Random, well-typed arguments are
+//! generated from the function's signature (check out [`value_strategy`]) and the function is run
+//! natively with them, optionally comparing every result against the Cairo VM (see
+//! [the VM comparison](crate::vm)). When a case fails, it's shrunk down to a minimal failing input.
+//!
+//! The generation and shrinking are provided by [`proptest`]. A case fails when the native
+//! execution returns an error or panics, or when it differs from the VM. Cairo panics aren't
+//! failures on their own, since they're the expected outcome for many inputs.
+
+use crate::{
+    error::{Error, Result},
+    executor::NativeExecutor,
+    types::TypeBuilder,
+    values::JitValue,
+    vm::{compare_with_vm, vm_args_from_jit},
+};
+use cairo_lang_runner::{SierraCasmRunner, StarknetState};
+use cairo_lang_sierra::{
+    extensions::{
+        core::{CoreLibfunc, CoreType, CoreTypeConcrete},
+        starknet::StarkNetTypeConcrete,
+    },
+    ids::{ConcreteTypeId, FunctionId},
+    program_registry::ProgramRegistry,
+};
+use itertools::Itertools;
+use num_traits::Bounded;
+use proptest::{
+    arbitrary::{any, Arbitrary},
+    collection::vec,
+    prop_oneof,
+    strategy::{BoxedStrategy, Just, Strategy, Union},
+    test_runner::{Config, RngAlgorithm, TestCaseError, TestError, TestRng, TestRunner},
+};
+use starknet_types_core::felt::Felt;
+use std::fmt::Debug;
+
+/// The fuzzing parameters.
+#[derive(Clone, Debug)]
+pub struct FuzzConfig {
+    /// The number of successful cases required to pass.
+    pub cases: u32,
+    /// The maximum length of the generated arrays.
+    pub max_array_len: usize,
+    /// The seed of the random generator, to make runs reproducible.
+    pub seed: Option<u64>,
+}
+
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        Self {
+            cases: 256,
+            max_array_len: 16,
+            seed: None,
+        }
+    }
+}
+
+/// A failing case, after shrinking.
+#[derive(Clone, Debug)]
+pub struct FuzzFailure {
+    /// The arguments of the function.
+    pub args: Vec<JitValue>,
+    /// Why the case failed.
+    pub reason: String,
+}
+
+/// Fuzz a function, returning the minimal failing case found, if any.
+///
+/// When a VM runner (along with the gas available to the function) is provided, every case is run
+/// on the VM too and fails if the results differ. The runner must have been built from the same
+/// program as the executor and the registry.
+pub fn fuzz_function(
+    executor: &NativeExecutor,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    function_id: &FunctionId,
+    initial_gas: Option<u128>,
+    vm: Option<(&SierraCasmRunner, Option<usize>)>,
+    config: &FuzzConfig,
+) -> Result<Option<FuzzFailure>> {
+    let strategy = arguments_strategy(registry, function_id, config)?;
+    let function = registry.get_function(function_id)?;
+
+    let runner_config = Config {
+        cases: config.cases,
+        failure_persistence: None,
+        ..Config::default()
+    };
+    let mut runner = match config.seed {
+        Some(seed) => {
+            let seed = [seed.to_le_bytes(); 4].concat();
+            TestRunner::new_with_rng(
+                runner_config,
+                TestRng::from_seed(RngAlgorithm::ChaCha, &seed),
+            )
+        }
+        None => TestRunner::new(runner_config),
+    };
+
+    let outcome = runner.run(&strategy, |args| {
+        let fail = |error: &dyn ToString| TestCaseError::fail(error.to_string());
+
+        let native_result = executor
+            .invoke_dynamic(function_id, &args, initial_gas)
+            .map_err(|error| fail(&error))?;
+
+        if let Some((vm_runner, available_gas)) = vm {
+            let vm_args =
+                vm_args_from_jit(registry, function_id, &args).map_err(|error| fail(&error))?;
+            let vm_result = vm_runner
+                .run_function_with_starknet_context(
+                    function,
+                    &vm_args,
+                    available_gas,
+                    StarknetState::default(),
+                )
+                .map_err(|error| fail(&error))?;
+
+            let mismatches = compare_with_vm(registry, function_id, &native_result, &vm_result)
+                .map_err(|error| fail(&error))?;
+            if !mismatches.is_empty() {
+                return Err(TestCaseError::fail(mismatches.iter().join("; ")));
+            }
+        }
+
+        Ok(())
+    });
+
+    match outcome {
+        Ok(()) => Ok(None),
+        Err(TestError::Fail(reason, args)) => Ok(Some(FuzzFailure {
+            args,
+            reason: reason.to_string(),
+        })),
+        Err(TestError::Abort(reason)) => Err(Error::FuzzAborted(reason.to_string())),
+    }
+}
+
+/// Build a strategy generating the arguments of a function, as taken by the executors (one for
+/// every parameter which is neither a builtin nor zero-sized).
+///
+/// Functions taking a syscall handler aren't supported.
+pub fn arguments_strategy(
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    function_id: &FunctionId,
+    config: &FuzzConfig,
+) -> Result<BoxedStrategy<Vec<JitValue>>> {
+    let function = registry.get_function(function_id)?;
+
+    let mut strategies = Vec::new();
+    for type_id in &function.signature.param_types {
+        let type_info = registry.get_type(type_id)?;
+        if let CoreTypeConcrete::StarkNet(StarkNetTypeConcrete::System(_)) = type_info {
+            return Err(Error::UnsupportedArgumentType(type_id.to_string()));
+        }
+        if type_info.is_builtin() || type_info.is_zst(registry) {
+            continue;
+        }
+
+        strategies.push(value_strategy(registry, type_id, config)?);
+    }
+
+    Ok(strategies.boxed())
+}
+
+/// Build a strategy generating values of the given type.
+///
+/// Integers cover their whole range (with a bias towards their bounds), felts are biased towards
+/// small values and the ones close to the prime, non-zero wrappers never generate zeros, and
+/// arrays are up to [`FuzzConfig::max_array_len`] elements long. Values are shrunk towards zero,
+/// shorter arrays and the first variant of enums.
+pub fn value_strategy(
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    type_id: &ConcreteTypeId,
+    config: &FuzzConfig,
+) -> Result<BoxedStrategy<JitValue>> {
+    Ok(match registry.get_type(type_id)? {
+        CoreTypeConcrete::Felt252(_) => felt_strategy().prop_map(JitValue::Felt252).boxed(),
+        CoreTypeConcrete::Bytes31(_) => any::<[u8; 31]>().prop_map(JitValue::Bytes31).boxed(),
+        CoreTypeConcrete::Uint8(_) => int_strategy(JitValue::Uint8),
+        CoreTypeConcrete::Uint16(_) => int_strategy(JitValue::Uint16),
+        CoreTypeConcrete::Uint32(_) => int_strategy(JitValue::Uint32),
+        CoreTypeConcrete::Uint64(_) => int_strategy(JitValue::Uint64),
+        CoreTypeConcrete::Uint128(_) => int_strategy(JitValue::Uint128),
+        CoreTypeConcrete::Sint8(_) => int_strategy(JitValue::Sint8),
+        CoreTypeConcrete::Sint16(_) => int_strategy(JitValue::Sint16),
+        CoreTypeConcrete::Sint32(_) => int_strategy(JitValue::Sint32),
+        CoreTypeConcrete::Sint64(_) => int_strategy(JitValue::Sint64),
+        CoreTypeConcrete::Sint128(_) => int_strategy(JitValue::Sint128),
+        CoreTypeConcrete::Array(info) => vec(
+            value_strategy(registry, &info.ty, config)?,
+            0..=config.max_array_len,
+        )
+        .prop_map(JitValue::Array)
+        .boxed(),
+        CoreTypeConcrete::Struct(info) => {
+            let mut fields = Vec::with_capacity(info.members.len());
+            for member_ty in &info.members {
+                fields.push(value_strategy(registry, member_ty, config)?);
+            }
+
+            fields
+                .prop_map(|fields| JitValue::Struct {
+                    fields,
+                    debug_name: None,
+                })
+                .boxed()
+        }
+        CoreTypeConcrete::Enum(info) => {
+            if info.variants.is_empty() {
+                return Err(Error::UnsupportedArgumentType(type_id.to_string()));
+            }
+
+            let mut variants = Vec::with_capacity(info.variants.len());
+            for (tag, variant_ty) in info.variants.iter().enumerate() {
+                variants.push(
+                    value_strategy(registry, variant_ty, config)?
+                        .prop_map(move |value| JitValue::Enum {
+                            tag,
+                            value: Box::new(value),
+                            debug_name: None,
+                        })
+                        .boxed(),
+                );
+            }
+
+            Union::new(variants).boxed()
+        }
+        CoreTypeConcrete::NonZero(info) => value_strategy(registry, &info.ty, config)?
+            .prop_filter("the value is zero", |value| !is_zero(value))
+            .boxed(),
+        CoreTypeConcrete::Snapshot(info) => value_strategy(registry, &info.ty, config)?,
+        _ => return Err(Error::UnsupportedArgumentType(type_id.to_string())),
+    })
+}
+
+fn felt_strategy() -> BoxedStrategy<Felt> {
+    prop_oneof![
+        1 => Just(Felt::ZERO),
+        1 => Just(Felt::ONE),
+        1 => Just(-Felt::ONE),
+        4 => any::<u128>().prop_map(Felt::from),
+        4 => any::<[u8; 32]>().prop_map(|mut bytes| {
+            // Keep the values below the prime.
+            bytes[0] &= 0x07;
+            Felt::from_bytes_be(&bytes)
+        }),
+    ]
+    .boxed()
+}
+
+fn int_strategy<T>(wrap: fn(T) -> JitValue) -> BoxedStrategy<JitValue>
+where
+    T: Arbitrary + Bounded + Clone + Debug + 'static,
+{
+    prop_oneof![
+        1 => Just(T::min_value()),
+        1 => Just(T::max_value()),
+        8 => any::<T>(),
+    ]
+    .prop_map(wrap)
+    .boxed()
+}
+
+fn is_zero(value: &JitValue) -> bool {
+    match value {
+        JitValue::Felt252(value) => *value == Felt::ZERO,
+        JitValue::Uint8(value) => *value == 0,
+        JitValue::Uint16(value) => *value == 0,
+        JitValue::Uint32(value) => *value == 0,
+        JitValue::Uint64(value) => *value == 0,
+        JitValue::Uint128(value) => *value == 0,
+        JitValue::Sint8(value) => *value == 0,
+        JitValue::Sint16(value) => *value == 0,
+        JitValue::Sint32(value) => *value == 0,
+        JitValue::Sint64(value) => *value == 0,
+        JitValue::Sint128(value) => *value == 0,
+        // Ex. `u256`.
+        JitValue::Struct { fields, .. } => fields.iter().all(is_zero),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{context::NativeContext, executor::JitNativeExecutor, utils::test::load_cairo};
+    use cairo_lang_runner::SierraCasmRunner;
+    use cairo_lang_sierra::program::{GenericArg, Program};
+    use proptest::strategy::ValueTree;
+
+    fn find_function(module_name: &str, program: &Program, name: &str) -> FunctionId {
+        program
+            .funcs
+            .iter()
+            .find(|x| {
+                x.id.debug_name.as_deref()
+                    == Some(format!("{0}::{0}::{name}", module_name).as_str())
+            })
+            .unwrap()
+            .id
+            .clone()
+    }
+
+    #[test]
+    fn generate_arguments() {
+        let (module_name, program) = load_cairo! {
+            fn run_test(a: u8, b: NonZero<felt252>, c: Array<i16>, d: Option<u32>) -> u8 {
+                a
+            }
+        };
+
+        let registry = ProgramRegistry::new(&program).unwrap();
+        let function_id = find_function(&module_name, &program, "run_test");
+        let config = FuzzConfig {
+            max_array_len: 4,
+            ..FuzzConfig::default()
+        };
+        let strategy = arguments_strategy(&registry, &function_id, &config).unwrap();
+
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..64 {
+            let args = strategy.new_tree(&mut runner).unwrap().current();
+
+            assert_eq!(args.len(), 4);
+            assert!(matches!(args[0], JitValue::Uint8(_)));
+            assert!(matches!(&args[1], JitValue::Felt252(x) if *x != Felt::ZERO));
+            assert!(matches!(&args[2], JitValue::Array(values) if values.len() <= 4));
+            assert!(matches!(args[3], JitValue::Enum { tag: 0 | 1, .. }));
+        }
+    }
+
+    #[test]
+    fn fuzz_against_vm() {
+        let (module_name, program) = load_cairo! {
+            fn run_test(a: u32, b: u32, values: Array<u8>) -> u32 {
+                a + b + values.len()
+            }
+        };
+
+        let registry = ProgramRegistry::new(&program).unwrap();
+        let function_id = find_function(&module_name, &program, "run_test");
+        let native_module = NativeContext::new().compile(&program, None).unwrap();
+        let executor: NativeExecutor =
+            JitNativeExecutor::from_native_module(native_module, Default::default()).into();
+        let runner = SierraCasmRunner::new(
+            program.clone(),
+            Some(Default::default()),
+            Default::default(),
+            false,
+        )
+        .unwrap();
+
+        // The additions overflow (and panic) for many inputs, which isn't a failure as long as
+        // both executions agree.
+        let failure = fuzz_function(
+            &executor,
+            &registry,
+            &function_id,
+            None,
+            Some((&runner, None)),
+            &FuzzConfig {
+                cases: 64,
+                seed: Some(0),
+                ..FuzzConfig::default()
+            },
+        )
+        .unwrap();
+        assert!(failure.is_none(), "{failure:?}");
+    }
+
+    #[test]
+    fn shrink_failure() {
+        let (module_name, program) = load_cairo! {
+            fn run_test(value: u8) -> bool {
+                value < 100
+            }
+        };
+
+        let registry = ProgramRegistry::new(&program).unwrap();
+        let function_id = find_function(&module_name, &program, "run_test");
+        let native_module = NativeContext::new().compile(&program, None).unwrap();
+        let executor: NativeExecutor =
+            JitNativeExecutor::from_native_module(native_module, Default::default()).into();
+
+        // The VM compares against 50 instead, so both executions disagree for `50 <= value < 100`.
+        let mut vm_program = program.clone();
+        let mut replaced = false;
+        for declaration in &mut vm_program.libfunc_declarations {
+            for arg in &mut declaration.long_id.generic_args {
+                if *arg == GenericArg::Value(100.into()) {
+                    *arg = GenericArg::Value(50.into());
+                    replaced = true;
+                }
+            }
+        }
+        assert!(replaced);
+        let runner = SierraCasmRunner::new(
+            vm_program,
+            Some(Default::default()),
+            Default::default(),
+            false,
+        )
+        .unwrap();
+
+        let failure = fuzz_function(
+            &executor,
+            &registry,
+            &function_id,
+            None,
+            Some((&runner, None)),
+            &FuzzConfig {
+                seed: Some(0),
+                ..FuzzConfig::default()
+            },
+        )
+        .unwrap()
+        .expect("the executions should disagree");
+        assert_eq!(failure.args, vec![JitValue::Uint8(50)]);
+    }
+
+    #[test]
+    fn unsupported_arguments() {
+        let (module_name, program) = load_cairo! {
+            fn run_test(value: Box<u8>) -> u8 {
+                value.unbox()
+            }
+        };
+
+        let registry = ProgramRegistry::new(&program).unwrap();
+        let function_id = find_function(&module_name, &program, "run_test");
+        assert!(matches!(
+            arguments_strategy(&registry, &function_id, &FuzzConfig::default()),
+            Err(Error::UnsupportedArgumentType(_))
+        ));
+    }
+}
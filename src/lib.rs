@@ -70,19 +70,28 @@ pub use self::{
     ffi::{module_to_object, object_to_shared_lib, LLVMCompileError, OptLevel},
 };
 
+pub mod backtrace;
 pub(crate) mod block_ext;
 pub mod cache;
 mod compiler;
 pub mod context;
+pub mod coverage;
 pub mod debug_info;
 pub mod error;
 pub mod execution_result;
 pub mod executor;
 mod ffi;
+#[cfg(feature = "build-cli")]
+pub mod fuzz;
 pub mod libfuncs;
 pub mod metadata;
 pub mod module;
+pub mod profiler;
+pub mod sierra_version;
 pub mod starknet;
+pub mod statement_trace;
 pub mod types;
 pub mod utils;
 pub mod values;
+#[cfg(feature = "build-cli")]
+pub mod vm;
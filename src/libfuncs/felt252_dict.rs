@@ -4,17 +4,23 @@ use super::LibfuncHelper;
 use crate::{
     error::Result,
     metadata::{runtime_bindings::RuntimeBindingsMeta, MetadataStorage},
+    types::TypeBuilder,
 };
 use cairo_lang_sierra::{
     extensions::{
-        core::{CoreLibfunc, CoreType},
+        core::{CoreLibfunc, CoreType, CoreTypeConcrete},
         felt252_dict::Felt252DictConcreteLibfunc,
         lib_func::SignatureOnlyConcreteLibfunc,
+        ConcreteLibfunc,
     },
     program_registry::ProgramRegistry,
 };
+use cairo_lang_sierra_gas::core_libfunc_cost::{
+    DICT_SQUASH_REPEATED_ACCESS_COST, DICT_SQUASH_UNIQUE_KEY_COST,
+};
 use melior::{
-    ir::{Block, Location},
+    dialect::arith,
+    ir::{attribute::IntegerAttribute, r#type::IntegerType, Block, Location},
     Context,
 };
 
@@ -40,21 +46,58 @@ pub fn build<'ctx, 'this>(
 
 pub fn build_new<'ctx, 'this>(
     context: &'ctx Context,
-    _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
     entry: &'this Block<'ctx>,
     location: Location<'ctx>,
     helper: &LibfuncHelper<'ctx, 'this>,
     metadata: &mut MetadataStorage,
-    _info: &SignatureOnlyConcreteLibfunc,
+    info: &SignatureOnlyConcreteLibfunc,
 ) -> Result<()> {
     let segment_arena =
         super::increment_builtin_counter(context, entry, location, entry.argument(0)?.into())?;
 
+    let value_layout = match registry.get_type(&info.branch_signatures()[0].vars[1].ty)? {
+        CoreTypeConcrete::Felt252Dict(info) => registry.get_type(&info.ty)?.layout(registry)?,
+        _ => unreachable!("felt252_dict_new always returns a Felt252Dict"),
+    };
+
+    let value_size = entry
+        .append_operation(arith::constant(
+            context,
+            IntegerAttribute::new(
+                IntegerType::new(context, 64).into(),
+                value_layout.size() as i64,
+            )
+            .into(),
+            location,
+        ))
+        .result(0)?
+        .into();
+    let value_align = entry
+        .append_operation(arith::constant(
+            context,
+            IntegerAttribute::new(
+                IntegerType::new(context, 64).into(),
+                value_layout.align() as i64,
+            )
+            .into(),
+            location,
+        ))
+        .result(0)?
+        .into();
+
     let runtime_bindings = metadata
         .get_mut::<RuntimeBindingsMeta>()
         .expect("Runtime library not available.");
 
-    let op = runtime_bindings.dict_alloc_new(context, helper, entry, location)?;
+    let op = runtime_bindings.dict_alloc_new(
+        context,
+        helper,
+        entry,
+        value_size,
+        value_align,
+        location,
+    )?;
     let dict_ptr = op.result(0)?.into();
 
     entry.append_operation(helper.br(0, &[segment_arena, dict_ptr], location));
@@ -67,7 +110,7 @@ pub fn build_squash<'ctx, 'this>(
     entry: &'this Block<'ctx>,
     location: Location<'ctx>,
     helper: &LibfuncHelper<'ctx, 'this>,
-    _metadata: &MetadataStorage,
+    metadata: &mut MetadataStorage,
     _info: &SignatureOnlyConcreteLibfunc,
 ) -> Result<()> {
     let range_check =
@@ -75,14 +118,50 @@ pub fn build_squash<'ctx, 'this>(
     let segment_arena =
         super::increment_builtin_counter(context, entry, location, entry.argument(2)?.into())?;
 
+    let gas_builtin = entry.argument(1)?.into();
+    let dict_ptr = entry.argument(3)?.into();
+
+    // Every access was charged `DICT_SQUASH_UNIQUE_KEY_COST` by `felt252_dict_entry_get`, but only
+    // the first access to each key costs that much when squashing. The VM refunds the difference
+    // for every repeated access, and so do we.
+    let runtime_bindings = metadata
+        .get_mut::<RuntimeBindingsMeta>()
+        .expect("Runtime library not available.");
+    let repeated_accesses = runtime_bindings
+        .dict_repeated_accesses(context, helper, entry, dict_ptr, location)?
+        .result(0)?
+        .into();
+
+    let gas_ty = IntegerType::new(context, 128).into();
+    let repeated_accesses = entry
+        .append_operation(arith::extui(repeated_accesses, gas_ty, location))
+        .result(0)?
+        .into();
+    let refund_per_access = entry
+        .append_operation(arith::constant(
+            context,
+            IntegerAttribute::new(
+                gas_ty,
+                (DICT_SQUASH_UNIQUE_KEY_COST.cost() - DICT_SQUASH_REPEATED_ACCESS_COST.cost())
+                    as i64,
+            )
+            .into(),
+            location,
+        ))
+        .result(0)?
+        .into();
+    let refund = entry
+        .append_operation(arith::muli(repeated_accesses, refund_per_access, location))
+        .result(0)?
+        .into();
+    let gas_builtin = entry
+        .append_operation(arith::addi(gas_builtin, refund, location))
+        .result(0)?
+        .into();
+
     entry.append_operation(helper.br(
         0,
-        &[
-            range_check,
-            entry.argument(1)?.into(),
-            segment_arena,
-            entry.argument(3)?.into(),
-        ],
+        &[range_check, gas_builtin, segment_arena, dict_ptr],
         location,
     ));
 
@@ -91,7 +170,10 @@ pub fn build_squash<'ctx, 'this>(
 
 #[cfg(test)]
 mod test {
-    use crate::utils::test::{jit_dict, jit_struct, load_cairo, run_program_assert_output};
+    use crate::{
+        utils::test::{jit_dict, jit_struct, load_cairo, run_program_assert_output},
+        values::{DictAccess, JitValue, SquashedDictEntry},
+    };
 
     #[test]
     fn run_dict_new() {
@@ -183,4 +265,149 @@ mod test {
             ),
         );
     }
+
+    #[test]
+    fn run_dict_squash() {
+        let program = load_cairo!(
+            use traits::Default;
+            use dict::Felt252DictTrait;
+
+            fn run_test() -> SquashedFelt252Dict<u32> {
+                let mut dict: Felt252Dict<u32> = Default::default();
+                dict.insert(2, 1_u32);
+                dict.insert(1, 2_u32);
+                dict.insert(2, 3_u32);
+                let _ = dict.get(2);
+                dict.squash()
+            }
+        );
+
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[],
+            JitValue::SquashedFelt252Dict {
+                value: vec![
+                    SquashedDictEntry {
+                        key: 1.into(),
+                        accesses: vec![DictAccess {
+                            prev_value: Some(0u32.into()),
+                            new_value: Some(2u32.into()),
+                        }],
+                        value: 2u32.into(),
+                    },
+                    SquashedDictEntry {
+                        key: 2.into(),
+                        accesses: vec![
+                            DictAccess {
+                                prev_value: Some(0u32.into()),
+                                new_value: Some(1u32.into()),
+                            },
+                            DictAccess {
+                                prev_value: Some(1u32.into()),
+                                new_value: Some(3u32.into()),
+                            },
+                            DictAccess {
+                                prev_value: Some(3u32.into()),
+                                new_value: Some(3u32.into()),
+                            },
+                        ],
+                        value: 3u32.into(),
+                    },
+                ],
+                debug_name: None,
+            },
+        );
+    }
+
+    #[test]
+    fn run_dict_squash_nullable() {
+        let program = load_cairo!(
+            use traits::Default;
+            use box::BoxTrait;
+            use dict::Felt252DictTrait;
+            use nullable::nullable_from_box;
+
+            fn run_test() -> SquashedFelt252Dict<Nullable<u32>> {
+                let mut dict: Felt252Dict<Nullable<u32>> = Default::default();
+                dict.insert(1, nullable_from_box(BoxTrait::new(2_u32)));
+                dict.insert(1, nullable_from_box(BoxTrait::new(3_u32)));
+                dict.squash()
+            }
+        );
+
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[],
+            JitValue::SquashedFelt252Dict {
+                value: vec![SquashedDictEntry {
+                    key: 1.into(),
+                    accesses: vec![
+                        DictAccess {
+                            prev_value: None,
+                            new_value: None,
+                        },
+                        DictAccess {
+                            prev_value: None,
+                            new_value: None,
+                        },
+                    ],
+                    value: 3u32.into(),
+                }],
+                debug_name: None,
+            },
+        );
+    }
+
+    #[test]
+    fn run_squashed_dict_deserialize() {
+        let program = load_cairo!(
+            use traits::Default;
+            use dict::Felt252DictTrait;
+
+            fn run_test(dict: SquashedFelt252Dict<u32>) -> SquashedFelt252Dict<u32> {
+                dict
+            }
+        );
+
+        let squashed_dict = JitValue::SquashedFelt252Dict {
+            value: vec![
+                SquashedDictEntry {
+                    key: 1.into(),
+                    accesses: vec![DictAccess {
+                        prev_value: Some(0u32.into()),
+                        new_value: Some(2u32.into()),
+                    }],
+                    value: 2u32.into(),
+                },
+                SquashedDictEntry {
+                    key: 2.into(),
+                    accesses: vec![
+                        DictAccess {
+                            prev_value: Some(0u32.into()),
+                            new_value: Some(1u32.into()),
+                        },
+                        DictAccess {
+                            prev_value: Some(1u32.into()),
+                            new_value: Some(3u32.into()),
+                        },
+                        DictAccess {
+                            prev_value: Some(3u32.into()),
+                            new_value: Some(3u32.into()),
+                        },
+                    ],
+                    value: 3u32.into(),
+                },
+            ],
+            debug_name: None,
+        };
+
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[squashed_dict.clone()],
+            squashed_dict,
+        );
+    }
 }
@@ -3,12 +3,9 @@
 use super::LibfuncHelper;
 use crate::{
     error::Result,
-    metadata::{
-        realloc_bindings::ReallocBindingsMeta, runtime_bindings::RuntimeBindingsMeta,
-        MetadataStorage,
-    },
+    metadata::{runtime_bindings::RuntimeBindingsMeta, MetadataStorage},
     types::TypeBuilder,
-    utils::{get_integer_layout, ProgramRegistryExt},
+    utils::ProgramRegistryExt,
 };
 use cairo_lang_sierra::{
     extensions::{
@@ -62,10 +59,6 @@ pub fn build_get<'ctx, 'this>(
     metadata: &mut MetadataStorage,
     info: &SignatureAndTypeConcreteLibfunc,
 ) -> Result<()> {
-    if metadata.get::<ReallocBindingsMeta>().is_none() {
-        metadata.insert(ReallocBindingsMeta::new(context, helper));
-    }
-
     let (key_ty, key_layout) = registry.build_type_with_layout(
         context,
         helper,
@@ -179,20 +172,20 @@ pub fn build_get<'ctx, 'this>(
 
     // null block
     {
-        let op = block_is_null.append_operation(arith::constant(
+        // Reserve the value's slot in the dictionary, which will be written when finalizing the
+        // entry.
+        let runtime_bindings = metadata
+            .get_mut::<RuntimeBindingsMeta>()
+            .expect("Runtime library not available.");
+
+        let op = runtime_bindings.dict_insert(
             context,
-            IntegerAttribute::new(
-                IntegerType::new(context, 64).into(),
-                value_layout.size() as i64,
-            )
-            .into(),
+            helper,
+            block_is_null,
+            dict_ptr,
+            key_ptr,
             location,
-        ));
-        let alloc_size = op.result(0)?.into();
-
-        let op = block_is_null.append_operation(ReallocBindingsMeta::realloc(
-            context, result_ptr, alloc_size, location,
-        ));
+        )?;
         let value_ptr = op.result(0)?.into();
 
         let default_value = registry
@@ -276,21 +269,9 @@ pub fn build_finalize<'ctx, 'this>(
     let value_type = registry.get_type(&info.param_signatures()[1].ty)?;
     let value_layout = value_type.layout(registry)?;
 
-    let key_ty = IntegerType::new(context, 252).into();
-    let key_layout = get_integer_layout(252);
-
     let entry_value = entry.argument(0)?.into();
     let new_value = entry.argument(1)?.into();
 
-    let op = entry.append_operation(llvm::extract_value(
-        context,
-        entry_value,
-        DenseI64ArrayAttribute::new(context, &[0]),
-        key_ty,
-        location,
-    ));
-    let key_value = op.result(0)?.into();
-
     let op = entry.append_operation(llvm::extract_value(
         context,
         entry_value,
@@ -309,6 +290,8 @@ pub fn build_finalize<'ctx, 'this>(
     ));
     let dict_ptr = op.result(0)?.into();
 
+    // The value pointer always points to the value's slot within the dictionary, therefore
+    // writing it is enough.
     entry.append_operation(llvm::store(
         context,
         new_value,
@@ -320,53 +303,11 @@ pub fn build_finalize<'ctx, 'this>(
         ))),
     ));
 
-    let const_1 = helper
-        .init_block()
-        .append_operation(arith::constant(
-            context,
-            IntegerAttribute::new(IntegerType::new(context, 64).into(), 1).into(),
-            location,
-        ))
-        .result(0)?
-        .into();
-
-    let op = helper.init_block().append_operation(
-        OperationBuilder::new("llvm.alloca", location)
-            .add_attributes(&[(
-                Identifier::new(context, "alignment"),
-                IntegerAttribute::new(
-                    IntegerType::new(context, 64).into(),
-                    key_layout.align().try_into()?,
-                )
-                .into(),
-            )])
-            .add_operands(&[const_1])
-            .add_results(&[llvm::r#type::pointer(key_ty, 0)])
-            .build()?,
-    );
-
-    let key_ptr = op.result(0)?.into();
-
-    entry.append_operation(llvm::store(
-        context,
-        key_value,
-        key_ptr,
-        location,
-        LoadStoreOptions::new().align(Some(IntegerAttribute::new(
-            IntegerType::new(context, 64).into(),
-            key_layout.align() as i64,
-        ))),
-    ));
-
-    // call insert
-
+    // Record the new value in the access log.
     let runtime_bindings = metadata
         .get_mut::<RuntimeBindingsMeta>()
         .expect("Runtime library not available.");
-
-    runtime_bindings.dict_insert(
-        context, helper, entry, dict_ptr, key_ptr, value_ptr, location,
-    )?;
+    runtime_bindings.dict_finalize_access(context, helper, entry, dict_ptr, location)?;
 
     entry.append_operation(helper.br(0, &[dict_ptr], location));
 
@@ -2,9 +2,10 @@
 
 use super::LibfuncHelper;
 use crate::{
+    block_ext::BlockExt,
     error::Result,
     ffi::get_struct_field_type_at,
-    metadata::MetadataStorage,
+    metadata::{cheatcode::CheatcodeMeta, MetadataStorage},
     starknet::handler::StarknetSyscallHandlerCallbacks,
     types::felt252::PRIME,
     utils::{get_integer_layout, ProgramRegistryExt},
@@ -14,7 +15,10 @@ use cairo_lang_sierra::{
         consts::SignatureAndConstConcreteLibfunc,
         core::{CoreLibfunc, CoreType},
         lib_func::SignatureOnlyConcreteLibfunc,
-        starknet::StarkNetConcreteLibfunc,
+        starknet::{
+            testing::{CheatcodeConcreteLibfunc, TestingConcreteLibfunc},
+            StarkNetConcreteLibfunc,
+        },
         ConcreteLibfunc,
     },
     program_registry::ProgramRegistry,
@@ -137,7 +141,9 @@ pub fn build<'ctx, 'this>(
         StarkNetConcreteLibfunc::Secp256(selector) => self::secp256::build(
             context, registry, entry, location, helper, metadata, selector,
         ),
-        StarkNetConcreteLibfunc::Testing(_) => todo!("implement starknet testing libfunc"),
+        StarkNetConcreteLibfunc::Testing(TestingConcreteLibfunc::Cheatcode(info)) => {
+            build_cheatcode(context, registry, entry, location, helper, metadata, info)
+        }
     }
 }
 
@@ -4569,6 +4575,88 @@ pub fn build_send_message_to_l1<'ctx, 'this>(
     Ok(())
 }
 
+pub fn build_cheatcode<'ctx, 'this>(
+    context: &'ctx Context,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    entry: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
+    info: &CheatcodeConcreteLibfunc,
+) -> Result<()> {
+    let span_ty = registry.build_type(
+        context,
+        helper,
+        registry,
+        metadata,
+        &info.param_signatures()[0].ty,
+    )?;
+    let array_ty = get_struct_field_type_at(&span_ty, 0);
+    let felt252_ty = IntegerType::new(context, 252).into();
+    let felt252_align = get_integer_layout(252).align();
+
+    // The selector, the input and the output are passed to the host by pointer.
+    let selector_ptr =
+        helper
+            .init_block()
+            .alloca1(context, location, felt252_ty, Some(felt252_align))?;
+    let input_ptr = helper
+        .init_block()
+        .alloca1(context, location, span_ty, None)?;
+    let output_ptr = helper
+        .init_block()
+        .alloca1(context, location, span_ty, None)?;
+
+    let selector = entry.append_op_result(arith::constant(
+        context,
+        Attribute::parse(context, &format!("{} : i252", info.selector)).unwrap(),
+        location,
+    ))?;
+    entry.store(
+        context,
+        location,
+        selector_ptr,
+        selector,
+        Some(felt252_align),
+    );
+    entry.store(
+        context,
+        location,
+        input_ptr,
+        entry.argument(0)?.into(),
+        None,
+    );
+
+    // The output is an empty span unless the host overwrites it.
+    let null_ptr = entry.append_op_result(llvm::nullptr(
+        get_struct_field_type_at(&array_ty, 0),
+        location,
+    ))?;
+    let k0 = entry.const_int(context, location, 0, 32)?;
+    let empty_array = entry.append_op_result(llvm::undef(array_ty, location))?;
+    let empty_array =
+        entry.insert_values(context, location, empty_array, &[null_ptr, k0, k0, k0])?;
+    let empty_span = entry.append_op_result(llvm::undef(span_ty, location))?;
+    let empty_span = entry.insert_value(context, location, empty_span, empty_array, 0)?;
+    entry.store(context, location, output_ptr, empty_span, None);
+
+    metadata
+        .get_or_insert_with(CheatcodeMeta::default)
+        .build_call(
+            context,
+            helper.module,
+            entry,
+            location,
+            output_ptr,
+            selector_ptr,
+            input_ptr,
+        )?;
+
+    let output = entry.load(context, location, output_ptr, span_ty, None)?;
+    entry.append_operation(helper.br(0, &[output], location));
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use crate::utils::test::{jit_enum, jit_struct, load_cairo, run_program_assert_output};
@@ -4605,6 +4693,13 @@ mod test {
                 storage_address_to_felt252(value)
             }
         };
+        static ref CHEATCODE: (String, Program) = load_cairo! {
+            use starknet::{contract_address_const, testing::pop_log_raw};
+
+            fn run_program() -> Option<(Span<felt252>, Span<felt252>)> {
+                pop_log_raw(contract_address_const::<0>())
+            }
+        };
         static ref STORAGE_ADDRESS_TRY_FROM_FELT252: (String, Program) = load_cairo! {
             use starknet::storage_access::{StorageAddress, storage_address_try_from_felt252};
 
@@ -4836,4 +4931,10 @@ mod test {
             jit_enum!(1, jit_struct!()),
         );
     }
+
+    #[test]
+    fn cheatcode_unsupported() {
+        // The test syscall handler doesn't support cheatcodes, therefore they return an empty span.
+        run_program_assert_output(&CHEATCODE, "run_program", &[], jit_enum!(1, jit_struct!()));
+    }
 }
@@ -14,13 +14,19 @@ use std::{
     collections::{hash_map::Entry, HashMap},
 };
 
+pub mod cheatcode;
+pub mod coverage;
 pub mod debug_utils;
 pub mod enum_snapshot_variants;
 pub mod gas;
+pub mod host_hook;
+pub mod panic_trace;
 pub mod prime_modulo;
+pub mod profiler;
 pub mod realloc_bindings;
 pub mod runtime_bindings;
 pub mod snapshot_clones;
+pub mod statement_trace;
 pub mod tail_recursion;
 
 /// Metadata container.
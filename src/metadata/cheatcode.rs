@@ -0,0 +1,60 @@
+//! # Cheatcodes
+//!
+//! The `cheatcode` libfunc of the test plugin (used by the `starknet::testing` functions of the
+//! corelib) is implemented by forwarding its selector, its input and a pointer to its output to
+//! the host through a [host hook](super::host_hook). Unlike syscalls, cheatcodes don't receive the
+//! syscall handler pointer, therefore the host has to keep track of the handler of the running
+//! program on its own. Check out
+//! [`StarknetTestingHandler`](crate::starknet::StarknetTestingHandler) for the host side.
+//!
+//! If the hook has not been set, the output is an empty span.
+
+use super::host_hook::{build_hook_call, declare_hook};
+use crate::error::Result;
+use melior::{
+    ir::{Block, Location, Module, Value},
+    Context,
+};
+
+/// Name of the global which holds the host's cheatcode hook.
+pub const CHEATCODE_HOOK_SYMBOL: &str = "cairo_native__cheatcode_hook";
+
+/// The cheatcode metadata.
+///
+/// Check out [the module](self) for more information about how cheatcodes are run.
+#[derive(Debug, Default)]
+pub struct CheatcodeMeta {
+    hook_declared: bool,
+}
+
+impl CheatcodeMeta {
+    /// Run the cheatcode through the host's hook, if it has been set.
+    ///
+    /// All the arguments are pointers: the output span, the selector (a felt) and the input span.
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_call<'c, 'a>(
+        &mut self,
+        context: &'c Context,
+        module: &Module<'c>,
+        block: &'a Block<'c>,
+        location: Location<'c>,
+        output_ptr: Value<'c, 'a>,
+        selector_ptr: Value<'c, 'a>,
+        input_ptr: Value<'c, 'a>,
+    ) -> Result<()> {
+        declare_hook(
+            context,
+            module,
+            CHEATCODE_HOOK_SYMBOL,
+            &mut self.hook_declared,
+        )?;
+
+        build_hook_call(
+            context,
+            block,
+            location,
+            CHEATCODE_HOOK_SYMBOL,
+            &[output_ptr, selector_ptr, input_ptr],
+        )
+    }
+}
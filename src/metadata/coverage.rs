@@ -0,0 +1,61 @@
+//! # Coverage instrumentation
+//!
+//! When this metadata is present, the compiler reports the index of every Sierra statement to the
+//! host right before executing it, so that the host can count how many times each one has been
+//! executed. Unlike the [statement trace](super::statement_trace), the order of execution is not
+//! kept, which makes it suitable for long-running programs such as test suites.
+//!
+//! Statements are reported through a [host hook](super::host_hook). Check out
+//! [the coverage module](crate::coverage) for the host side.
+
+use super::host_hook::{build_hook_call, declare_hook};
+use crate::{block_ext::BlockExt, error::Result};
+use cairo_lang_sierra::program::StatementIdx;
+use melior::{
+    ir::{Block, Location, Module},
+    Context,
+};
+
+/// Name of the global which holds the host's coverage hook.
+pub const COVERAGE_HOOK_SYMBOL: &str = "cairo_native__coverage_hook";
+
+/// The coverage instrumentation metadata.
+///
+/// Check out [the module](self) for more information about how coverage is collected.
+#[derive(Debug, Default)]
+pub struct CoverageMeta {
+    hook_declared: bool,
+}
+
+impl CoverageMeta {
+    /// Return whether the hook global has been declared.
+    pub fn is_hook_declared(&self) -> bool {
+        self.hook_declared
+    }
+
+    /// Report the statement to the host's coverage hook, if it has been set.
+    pub fn build_hit<'c>(
+        &mut self,
+        context: &'c Context,
+        module: &Module<'c>,
+        block: &Block<'c>,
+        location: Location<'c>,
+        statement_idx: StatementIdx,
+    ) -> Result<()> {
+        declare_hook(
+            context,
+            module,
+            COVERAGE_HOOK_SYMBOL,
+            &mut self.hook_declared,
+        )?;
+
+        let statement_idx = block.const_int(context, location, statement_idx.0 as u64, 64)?;
+        build_hook_call(
+            context,
+            block,
+            location,
+            COVERAGE_HOOK_SYMBOL,
+            &[statement_idx],
+        )
+    }
+}
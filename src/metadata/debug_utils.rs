@@ -83,7 +83,7 @@
 
 #![cfg(feature = "with-debug-utils")]
 
-use crate::error::Result;
+use crate::{error::Result, utils::RegisterSymbol};
 use melior::{
     dialect::{
         arith, func,
@@ -632,6 +632,10 @@ impl DebugUtils {
     }
 
     pub fn register_impls(&self, engine: &ExecutionEngine) {
+        self.register_impls_in(engine)
+    }
+
+    pub(crate) fn register_impls_in(&self, engine: &impl RegisterSymbol) {
         if self.active_map.contains(&DebugBinding::BreakpointMarker) {
             unsafe {
                 engine.register_symbol(
@@ -0,0 +1,127 @@
+//! # Host hooks
+//!
+//! Instrumentation which reports to the host (ex. [PanicTraceMeta](super::panic_trace) or
+//! [ProfilerMeta](super::profiler)) does so by calling a function pointer stored in a global of the
+//! module. The global is zero-initialized and set by the executors when loading the code, therefore
+//! the instrumented code does nothing until then, and both JIT and AOT-compiled programs report to
+//! the same host, even though the latter link their own copy of the runtime library.
+
+use crate::{block_ext::BlockExt, error::Result};
+use melior::{
+    dialect::{arith, arith::CmpiPredicate, llvm, scf},
+    ir::{
+        attribute::{FlatSymbolRefAttribute, IntegerAttribute, StringAttribute, TypeAttribute},
+        operation::OperationBuilder,
+        r#type::IntegerType,
+        Attribute, Block, Identifier, Location, Module, Region, Value, ValueLike,
+    },
+    Context,
+};
+
+/// Declare the hook global, unless `declared` is already set.
+pub(crate) fn declare_hook<'c>(
+    context: &'c Context,
+    module: &Module<'c>,
+    symbol: &str,
+    declared: &mut bool,
+) -> Result<()> {
+    if *declared {
+        return Ok(());
+    }
+
+    let i64_ty = IntegerType::new(context, 64).into();
+    module.body().append_operation(
+        OperationBuilder::new("llvm.mlir.global", Location::unknown(context))
+            .add_attributes(&[
+                (
+                    Identifier::new(context, "sym_name"),
+                    StringAttribute::new(context, symbol).into(),
+                ),
+                (
+                    Identifier::new(context, "global_type"),
+                    TypeAttribute::new(i64_ty).into(),
+                ),
+                (
+                    Identifier::new(context, "linkage"),
+                    Attribute::parse(context, "#llvm.linkage<external>").unwrap(),
+                ),
+                (
+                    Identifier::new(context, "value"),
+                    IntegerAttribute::new(i64_ty, 0).into(),
+                ),
+            ])
+            .add_regions([Region::new()])
+            .build()?,
+    );
+    *declared = true;
+
+    Ok(())
+}
+
+/// Call the hook stored in the global with the given arguments, if it has been set.
+///
+/// The global must have been declared using [`declare_hook`].
+pub(crate) fn build_hook_call<'c, 'a>(
+    context: &'c Context,
+    block: &'a Block<'c>,
+    location: Location<'c>,
+    symbol: &str,
+    args: &[Value<'c, 'a>],
+) -> Result<()> {
+    let i64_ty = IntegerType::new(context, 64).into();
+
+    let hook_ptr = block.append_op_result(
+        OperationBuilder::new("llvm.mlir.addressof", location)
+            .add_attributes(&[(
+                Identifier::new(context, "global_name"),
+                FlatSymbolRefAttribute::new(context, symbol).into(),
+            )])
+            .add_results(&[llvm::r#type::opaque_pointer(context)])
+            .build()?,
+    )?;
+    let hook = block.load(context, location, hook_ptr, i64_ty, None)?;
+
+    let k0 = block.const_int(context, location, 0, 64)?;
+    let is_set =
+        block.append_op_result(arith::cmpi(context, CmpiPredicate::Ne, hook, k0, location))?;
+
+    block.append_operation(scf::r#if(
+        is_set,
+        &[],
+        {
+            let region = Region::new();
+            let block = region.append_block(Block::new(&[]));
+
+            let fn_ptr_ty = llvm::r#type::function(
+                llvm::r#type::void(context),
+                &args.iter().map(|arg| arg.r#type()).collect::<Vec<_>>(),
+                false,
+            );
+            let fn_ptr = block.append_op_result(
+                OperationBuilder::new("llvm.inttoptr", location)
+                    .add_operands(&[hook])
+                    .add_results(&[llvm::r#type::pointer(fn_ptr_ty, 0)])
+                    .build()?,
+            )?;
+
+            block.append_operation(
+                OperationBuilder::new("llvm.call", location)
+                    .add_operands(&[&[fn_ptr], args].concat())
+                    .build()?,
+            );
+
+            block.append_operation(scf::r#yield(&[], location));
+            region
+        },
+        {
+            let region = Region::new();
+            let block = region.append_block(Block::new(&[]));
+
+            block.append_operation(scf::r#yield(&[], location));
+            region
+        },
+        location,
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+//! # Panic trace instrumentation
+//!
+//! When this metadata is present, the compiler instruments every statement which creates a panic
+//! (the `Err` variant of a `PanicResult`) to report its statement index to the host. Since Sierra
+//! propagates panics by wrapping them again in every function up the call chain, the reported
+//! statements are the origin of the panic followed by every frame it went through.
+//!
+//! The statements are reported through a [host hook](super::host_hook). Check out
+//! [the backtrace module](crate::backtrace) for the host side.
+
+use super::host_hook::{build_hook_call, declare_hook};
+use crate::{block_ext::BlockExt, error::Result};
+use cairo_lang_sierra::{
+    extensions::{
+        core::{CoreConcreteLibfunc, CoreLibfunc, CoreType, CoreTypeConcrete},
+        enm::EnumConcreteLibfunc,
+        ConcreteLibfunc,
+    },
+    ids::UserTypeId,
+    program::{GenericArg, StatementIdx},
+    program_registry::ProgramRegistry,
+};
+use melior::{
+    ir::{Block, Location, Module},
+    Context,
+};
+
+/// Name of the global which holds the host's panic trace hook.
+pub const PANIC_TRACE_HOOK_SYMBOL: &str = "cairo_native__panic_trace_hook";
+
+/// The panic trace instrumentation metadata.
+///
+/// Check out [the module](self) for more information about how panics are traced.
+#[derive(Debug, Default)]
+pub struct PanicTraceMeta {
+    hook_declared: bool,
+}
+
+impl PanicTraceMeta {
+    /// Return whether the hook global has been declared, which only happens when the program has
+    /// any statement which may panic.
+    pub fn is_hook_declared(&self) -> bool {
+        self.hook_declared
+    }
+
+    /// Return whether the libfunc creates a panic, that is, whether it's the `enum_init` of the
+    /// `Err` variant of a `PanicResult`.
+    pub fn is_panic(
+        registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+        libfunc: &CoreConcreteLibfunc,
+    ) -> Result<bool> {
+        let info = match libfunc {
+            CoreConcreteLibfunc::Enum(EnumConcreteLibfunc::Init(info)) if info.index == 1 => info,
+            _ => return Ok(false),
+        };
+
+        let enum_ty = &info.branch_signatures()[0].vars[0].ty;
+        let err_ty = match registry.get_type(enum_ty)? {
+            CoreTypeConcrete::Enum(info) => &info.variants[1],
+            _ => return Ok(false),
+        };
+
+        // The `Err` variant of a `PanicResult` is a `(core::panics::Panic, Array<felt252>)`.
+        let panic_ty = match registry.get_type(err_ty)? {
+            CoreTypeConcrete::Struct(info) => match info.members.first() {
+                Some(x) => x,
+                None => return Ok(false),
+            },
+            _ => return Ok(false),
+        };
+
+        Ok(match registry.get_type(panic_ty)? {
+            CoreTypeConcrete::Struct(info) => matches!(
+                info.info.long_id.generic_args.first(),
+                Some(GenericArg::UserType(x))
+                    if x.id == UserTypeId::from_string("core::panics::Panic").id
+            ),
+            _ => false,
+        })
+    }
+
+    /// Report the statement to the host's panic trace hook, if it has been set.
+    pub fn build_record<'c>(
+        &mut self,
+        context: &'c Context,
+        module: &Module<'c>,
+        block: &Block<'c>,
+        location: Location<'c>,
+        statement_idx: StatementIdx,
+    ) -> Result<()> {
+        declare_hook(
+            context,
+            module,
+            PANIC_TRACE_HOOK_SYMBOL,
+            &mut self.hook_declared,
+        )?;
+        let statement_idx = block.const_int(context, location, statement_idx.0 as u64, 64)?;
+        build_hook_call(
+            context,
+            block,
+            location,
+            PANIC_TRACE_HOOK_SYMBOL,
+            &[statement_idx],
+        )
+    }
+}
@@ -0,0 +1,87 @@
+//! # Profiler instrumentation
+//!
+//! When this metadata is present, the compiler reports an event to the host every time a Sierra
+//! function is entered, a libfunc is invoked and a function returns. The host then timestamps the
+//! events and aggregates them per function and per statement, keeping track of the call stack so
+//! that they can be exported as folded stacks. Check out [the profiler module](crate::profiler) for
+//! the host side.
+//!
+//! Like the [panic trace](super::panic_trace), events are reported through a
+//! [host hook](super::host_hook).
+
+use super::host_hook::{build_hook_call, declare_hook};
+use crate::{block_ext::BlockExt, error::Result};
+use cairo_lang_sierra::program::StatementIdx;
+use melior::{
+    ir::{Block, Location, Module},
+    Context,
+};
+
+/// Name of the global which holds the host's profiler hook.
+pub const PROFILER_HOOK_SYMBOL: &str = "cairo_native__profiler_hook";
+
+/// The kind of event reported to the profiler hook.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u64)]
+pub enum ProfilerEvent {
+    /// A function has been entered. The statement is the function's entry point.
+    FunctionEnter = 0,
+    /// A libfunc is about to be invoked.
+    Invocation = 1,
+    /// A function is returning.
+    FunctionReturn = 2,
+}
+
+impl ProfilerEvent {
+    /// Convert the raw value received by the hook back into an event.
+    pub fn from_raw(value: u64) -> Option<Self> {
+        Some(match value {
+            0 => Self::FunctionEnter,
+            1 => Self::Invocation,
+            2 => Self::FunctionReturn,
+            _ => return None,
+        })
+    }
+}
+
+/// The profiler instrumentation metadata.
+///
+/// Check out [the module](self) for more information about how programs are profiled.
+#[derive(Debug, Default)]
+pub struct ProfilerMeta {
+    hook_declared: bool,
+}
+
+impl ProfilerMeta {
+    /// Return whether the hook global has been declared.
+    pub fn is_hook_declared(&self) -> bool {
+        self.hook_declared
+    }
+
+    /// Report an event to the host's profiler hook, if it has been set.
+    pub fn build_event<'c>(
+        &mut self,
+        context: &'c Context,
+        module: &Module<'c>,
+        block: &Block<'c>,
+        location: Location<'c>,
+        event: ProfilerEvent,
+        statement_idx: StatementIdx,
+    ) -> Result<()> {
+        declare_hook(
+            context,
+            module,
+            PROFILER_HOOK_SYMBOL,
+            &mut self.hook_declared,
+        )?;
+        let event = block.const_int(context, location, event as u64, 64)?;
+        let statement_idx = block.const_int(context, location, statement_idx.0 as u64, 64)?;
+        build_hook_call(
+            context,
+            block,
+            location,
+            PROFILER_HOOK_SYMBOL,
+            &[event, statement_idx],
+        )
+    }
+}
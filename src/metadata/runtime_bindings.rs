@@ -29,6 +29,8 @@ enum RuntimeBinding {
     DictGet,
     DictInsert,
     DictFree,
+    DictFinalizeAccess,
+    DictRepeatedAccesses,
 }
 
 /// Runtime library bindings metadata.
@@ -475,6 +477,8 @@ impl RuntimeBindingsMeta {
 
     /// Register if necessary, then invoke the `dict_alloc_new()` function.
     ///
+    /// The dictionary stores its values inline, therefore it needs their size and alignment.
+    ///
     /// Returns a opaque pointer as the result.
     #[allow(clippy::too_many_arguments)]
     pub fn dict_alloc_new<'c, 'a>(
@@ -482,6 +486,8 @@ impl RuntimeBindingsMeta {
         context: &'c Context,
         module: &Module,
         block: &'a Block<'c>,
+        value_size: Value<'c, 'a>,  // i64
+        value_align: Value<'c, 'a>, // i64
         location: Location<'c>,
     ) -> Result<OperationRef<'c, 'a>>
     where
@@ -492,8 +498,15 @@ impl RuntimeBindingsMeta {
                 context,
                 StringAttribute::new(context, "cairo_native__alloc_dict"),
                 TypeAttribute::new(
-                    FunctionType::new(context, &[], &[llvm::r#type::opaque_pointer(context)])
-                        .into(),
+                    FunctionType::new(
+                        context,
+                        &[
+                            IntegerType::new(context, 64).into(),
+                            IntegerType::new(context, 64).into(),
+                        ],
+                        &[llvm::r#type::opaque_pointer(context)],
+                    )
+                    .into(),
                 ),
                 Region::new(),
                 &[(
@@ -507,15 +520,15 @@ impl RuntimeBindingsMeta {
         Ok(block.append_operation(func::call(
             context,
             FlatSymbolRefAttribute::new(context, "cairo_native__alloc_dict"),
-            &[],
+            &[value_size, value_align],
             &[llvm::r#type::opaque_pointer(context)],
             location,
         )))
     }
 
-    /// Register if necessary, then invoke the `dict_alloc_new()` function.
+    /// Register if necessary, then invoke the `dict_free()` function.
     ///
-    /// Returns a opaque pointer as the result.
+    /// Frees the dictionary and all its values.
     #[allow(clippy::too_many_arguments)]
     pub fn dict_alloc_free<'c, 'a>(
         &mut self,
@@ -607,7 +620,8 @@ impl RuntimeBindingsMeta {
 
     /// Register if necessary, then invoke the `dict_insert()` function.
     ///
-    /// Inserts the provided key value. Returning the old one or nullptr if there was none.
+    /// Inserts the provided key, returning a pointer to its value. The value is left uninitialized
+    /// if the key wasn't present.
     ///
     /// Returns a opaque pointer as the result.
     #[allow(clippy::too_many_arguments)]
@@ -616,9 +630,8 @@ impl RuntimeBindingsMeta {
         context: &'c Context,
         module: &Module,
         block: &'a Block<'c>,
-        dict_ptr: Value<'c, 'a>,  // ptr to the dict
-        key_ptr: Value<'c, 'a>,   // key must be a ptr to Felt
-        value_ptr: Value<'c, 'a>, // value must be a opaque non null ptr
+        dict_ptr: Value<'c, 'a>, // ptr to the dict
+        key_ptr: Value<'c, 'a>,  // key must be a ptr to Felt
         location: Location<'c>,
     ) -> Result<OperationRef<'c, 'a>>
     where
@@ -634,7 +647,6 @@ impl RuntimeBindingsMeta {
                         &[
                             llvm::r#type::opaque_pointer(context),
                             llvm::r#type::pointer(IntegerType::new(context, 252).into(), 0),
-                            llvm::r#type::opaque_pointer(context),
                         ],
                         &[llvm::r#type::opaque_pointer(context)],
                     )
@@ -652,11 +664,95 @@ impl RuntimeBindingsMeta {
         Ok(block.append_operation(func::call(
             context,
             FlatSymbolRefAttribute::new(context, "cairo_native__dict_insert"),
-            &[dict_ptr, key_ptr, value_ptr],
+            &[dict_ptr, key_ptr],
             &[llvm::r#type::opaque_pointer(context)],
             location,
         )))
     }
+
+    /// Register if necessary, then invoke the `dict_finalize_access()` function.
+    ///
+    /// Records the new value of the last access, which must have been written to its slot.
+    pub fn dict_finalize_access<'c, 'a>(
+        &mut self,
+        context: &'c Context,
+        module: &Module,
+        block: &'a Block<'c>,
+        dict_ptr: Value<'c, 'a>, // ptr to the dict
+        location: Location<'c>,
+    ) -> Result<OperationRef<'c, 'a>>
+    where
+        'c: 'a,
+    {
+        if self.active_map.insert(RuntimeBinding::DictFinalizeAccess) {
+            module.body().append_operation(func::func(
+                context,
+                StringAttribute::new(context, "cairo_native__dict_finalize_access"),
+                TypeAttribute::new(
+                    FunctionType::new(context, &[llvm::r#type::opaque_pointer(context)], &[])
+                        .into(),
+                ),
+                Region::new(),
+                &[(
+                    Identifier::new(context, "sym_visibility"),
+                    StringAttribute::new(context, "private").into(),
+                )],
+                Location::unknown(context),
+            ));
+        }
+
+        Ok(block.append_operation(func::call(
+            context,
+            FlatSymbolRefAttribute::new(context, "cairo_native__dict_finalize_access"),
+            &[dict_ptr],
+            &[],
+            location,
+        )))
+    }
+
+    /// Register if necessary, then invoke the `dict_repeated_accesses()` function.
+    ///
+    /// Returns the number of accesses which weren't the first one to their key as an i64.
+    pub fn dict_repeated_accesses<'c, 'a>(
+        &mut self,
+        context: &'c Context,
+        module: &Module,
+        block: &'a Block<'c>,
+        dict_ptr: Value<'c, 'a>, // ptr to the dict
+        location: Location<'c>,
+    ) -> Result<OperationRef<'c, 'a>>
+    where
+        'c: 'a,
+    {
+        if self.active_map.insert(RuntimeBinding::DictRepeatedAccesses) {
+            module.body().append_operation(func::func(
+                context,
+                StringAttribute::new(context, "cairo_native__dict_repeated_accesses"),
+                TypeAttribute::new(
+                    FunctionType::new(
+                        context,
+                        &[llvm::r#type::opaque_pointer(context)],
+                        &[IntegerType::new(context, 64).into()],
+                    )
+                    .into(),
+                ),
+                Region::new(),
+                &[(
+                    Identifier::new(context, "sym_visibility"),
+                    StringAttribute::new(context, "private").into(),
+                )],
+                Location::unknown(context),
+            ));
+        }
+
+        Ok(block.append_operation(func::call(
+            context,
+            FlatSymbolRefAttribute::new(context, "cairo_native__dict_repeated_accesses"),
+            &[dict_ptr],
+            &[IntegerType::new(context, 64).into()],
+            location,
+        )))
+    }
 }
 
 impl Default for RuntimeBindingsMeta {
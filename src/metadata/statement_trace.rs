@@ -0,0 +1,241 @@
+//! # Statement trace instrumentation
+//!
+//! When this metadata is present, the compiler reports the index of every Sierra statement to the
+//! host right before executing it. Optionally, once a statement has been executed, the branch it
+//! took and the values of its integer and felt outputs in that branch are reported too.
+//!
+//! Events are reported through a [host hook](super::host_hook) taking seven `i64`s: the kind of
+//! event, the statement index, either the branch or the output index and the four little-endian
+//! 64-bit limbs of the value (zero for other events). Check out
+//! [the statement trace module](crate::statement_trace) for the host side.
+
+use super::host_hook::{build_hook_call, declare_hook};
+use crate::{block_ext::BlockExt, error::Result};
+use cairo_lang_sierra::{
+    extensions::core::{CoreLibfunc, CoreType, CoreTypeConcrete},
+    ids::ConcreteTypeId,
+    program::StatementIdx,
+    program_registry::ProgramRegistry,
+};
+use melior::{
+    dialect::arith,
+    ir::{r#type::IntegerType, Block, Location, Module, Value},
+    Context,
+};
+
+/// Name of the global which holds the host's statement trace hook.
+pub const STATEMENT_TRACE_HOOK_SYMBOL: &str = "cairo_native__statement_trace_hook";
+
+/// Event kind of an invocation statement about to be executed.
+pub const STATEMENT_TRACE_STATEMENT: u64 = 0;
+/// Event kind of a return statement about to be executed.
+pub const STATEMENT_TRACE_RETURN: u64 = 1;
+/// Event kind of an executed invocation statement taking a branch.
+pub const STATEMENT_TRACE_BRANCH: u64 = 2;
+/// Event kind of an unsigned (or felt) output of the last statement which took a branch.
+pub const STATEMENT_TRACE_UNSIGNED_VALUE: u64 = 3;
+/// Event kind of a signed output of the last statement which took a branch, in two's complement.
+pub const STATEMENT_TRACE_SIGNED_VALUE: u64 = 4;
+
+/// What the statement trace instrumentation should report.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum StatementTraceMode {
+    /// Report only the executed statements.
+    #[default]
+    Statements,
+    /// Report the executed statements along with the branches they took and the values of their
+    /// integer and felt outputs.
+    StatementsAndValues,
+}
+
+/// The statement trace instrumentation metadata.
+///
+/// Check out [the module](self) for more information about how statements are traced.
+#[derive(Debug, Default)]
+pub struct StatementTraceMeta {
+    mode: StatementTraceMode,
+    hook_declared: bool,
+}
+
+impl StatementTraceMeta {
+    pub fn new(mode: StatementTraceMode) -> Self {
+        Self {
+            mode,
+            hook_declared: false,
+        }
+    }
+
+    /// Return whether the hook global has been declared.
+    pub fn is_hook_declared(&self) -> bool {
+        self.hook_declared
+    }
+
+    /// Return whether the branches and output values of the statements are traced.
+    pub fn traces_values(&self) -> bool {
+        self.mode == StatementTraceMode::StatementsAndValues
+    }
+
+    /// Report an invocation statement, about to be executed, to the host's statement trace hook.
+    pub fn build_statement<'c>(
+        &mut self,
+        context: &'c Context,
+        module: &Module<'c>,
+        block: &Block<'c>,
+        location: Location<'c>,
+        statement_idx: StatementIdx,
+    ) -> Result<()> {
+        self.build_event(
+            context,
+            module,
+            block,
+            location,
+            STATEMENT_TRACE_STATEMENT,
+            statement_idx,
+        )
+    }
+
+    /// Report a return statement, about to be executed, to the host's statement trace hook.
+    pub fn build_return<'c>(
+        &mut self,
+        context: &'c Context,
+        module: &Module<'c>,
+        block: &Block<'c>,
+        location: Location<'c>,
+        statement_idx: StatementIdx,
+    ) -> Result<()> {
+        self.build_event(
+            context,
+            module,
+            block,
+            location,
+            STATEMENT_TRACE_RETURN,
+            statement_idx,
+        )
+    }
+
+    /// Report the branch taken by an executed statement, along with its outputs, to the host's
+    /// statement trace hook.
+    ///
+    /// The outputs must be the branch's values along with their types. Only integers and felts are
+    /// reported.
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_branch<'c, 'a>(
+        &mut self,
+        context: &'c Context,
+        module: &Module<'c>,
+        registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+        block: &'a Block<'c>,
+        location: Location<'c>,
+        statement_idx: StatementIdx,
+        branch_idx: usize,
+        outputs: &[(Value<'c, 'a>, &ConcreteTypeId)],
+    ) -> Result<()> {
+        declare_hook(
+            context,
+            module,
+            STATEMENT_TRACE_HOOK_SYMBOL,
+            &mut self.hook_declared,
+        )?;
+
+        let kind = block.const_int(context, location, STATEMENT_TRACE_BRANCH, 64)?;
+        let statement_idx = block.const_int(context, location, statement_idx.0 as u64, 64)?;
+        let branch_idx = block.const_int(context, location, branch_idx as u64, 64)?;
+        let k0 = block.const_int(context, location, 0, 64)?;
+        build_hook_call(
+            context,
+            block,
+            location,
+            STATEMENT_TRACE_HOOK_SYMBOL,
+            &[kind, statement_idx, branch_idx, k0, k0, k0, k0],
+        )?;
+
+        for (output_idx, (value, type_id)) in outputs.iter().enumerate() {
+            let is_signed = match registry.get_type(type_id)? {
+                CoreTypeConcrete::Felt252(_)
+                | CoreTypeConcrete::Uint8(_)
+                | CoreTypeConcrete::Uint16(_)
+                | CoreTypeConcrete::Uint32(_)
+                | CoreTypeConcrete::Uint64(_)
+                | CoreTypeConcrete::Uint128(_) => false,
+                CoreTypeConcrete::Sint8(_)
+                | CoreTypeConcrete::Sint16(_)
+                | CoreTypeConcrete::Sint32(_)
+                | CoreTypeConcrete::Sint64(_)
+                | CoreTypeConcrete::Sint128(_) => true,
+                _ => continue,
+            };
+
+            let i64_ty = IntegerType::new(context, 64).into();
+            let i256_ty = IntegerType::new(context, 256).into();
+            let value = block.append_op_result(if is_signed {
+                arith::extsi(*value, i256_ty, location)
+            } else {
+                arith::extui(*value, i256_ty, location)
+            })?;
+
+            let mut limbs = Vec::with_capacity(4);
+            for i in 0..4 {
+                let shift = block.const_int_from_type(context, location, 64 * i, i256_ty)?;
+                let limb = block.append_op_result(arith::shrui(value, shift, location))?;
+                limbs.push(block.append_op_result(arith::trunci(limb, i64_ty, location))?);
+            }
+
+            let kind = block.const_int(
+                context,
+                location,
+                if is_signed {
+                    STATEMENT_TRACE_SIGNED_VALUE
+                } else {
+                    STATEMENT_TRACE_UNSIGNED_VALUE
+                },
+                64,
+            )?;
+            let output_idx = block.const_int(context, location, output_idx as u64, 64)?;
+            build_hook_call(
+                context,
+                block,
+                location,
+                STATEMENT_TRACE_HOOK_SYMBOL,
+                &[
+                    kind,
+                    statement_idx,
+                    output_idx,
+                    limbs[0],
+                    limbs[1],
+                    limbs[2],
+                    limbs[3],
+                ],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn build_event<'c>(
+        &mut self,
+        context: &'c Context,
+        module: &Module<'c>,
+        block: &Block<'c>,
+        location: Location<'c>,
+        kind: u64,
+        statement_idx: StatementIdx,
+    ) -> Result<()> {
+        declare_hook(
+            context,
+            module,
+            STATEMENT_TRACE_HOOK_SYMBOL,
+            &mut self.hook_declared,
+        )?;
+
+        let kind = block.const_int(context, location, kind, 64)?;
+        let statement_idx = block.const_int(context, location, statement_idx.0 as u64, 64)?;
+        let k0 = block.const_int(context, location, 0, 64)?;
+        build_hook_call(
+            context,
+            block,
+            location,
+            STATEMENT_TRACE_HOOK_SYMBOL,
+            &[kind, statement_idx, k0, k0, k0, k0, k0],
+        )
+    }
+}
@@ -0,0 +1,359 @@
+//! # Profiler
+//!
+//! Programs compiled with the profiler enabled (check out
+//! [`NativeContext::with_profiler`](crate::context::NativeContext::with_profiler)) report every
+//! function entry, libfunc invocation and function return (see
+//! [the instrumentation](crate::metadata::profiler)). This module timestamps those events and
+//! aggregates them into a [`ProfileData`], which can be inspected per function and per statement or
+//! exported in the folded stacks format understood by `flamegraph.pl` and `inferno`.
+//!
+//! The time of every libfunc invocation is measured from the moment it's invoked until the next
+//! event, therefore it includes the overhead of the instrumentation itself. The numbers are meant to
+//! be compared between themselves, not as absolute timings.
+//!
+//! The counters are kept per thread and accumulate over invocations until they are taken.
+
+use crate::{
+    metadata::{gas::GasMetadata, profiler::ProfilerEvent},
+    utils::find_statement_function,
+};
+use cairo_lang_sierra::{
+    extensions::gas::CostTokenType,
+    ids::FunctionId,
+    program::{Program, Statement, StatementIdx},
+};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+thread_local! {
+    static PROFILER: RefCell<ProfilerState> = RefCell::new(ProfilerState::default());
+}
+
+#[derive(Debug, Default)]
+struct ProfilerState {
+    /// The active frames, as their function's entry point and the time they were entered.
+    frames: Vec<(StatementIdx, Instant)>,
+    /// The libfunc invocation being measured, if any.
+    pending: Option<(StatementIdx, Instant)>,
+
+    data: ProfileData,
+}
+
+impl ProfilerState {
+    fn record(&mut self, event: ProfilerEvent, statement_idx: StatementIdx) {
+        let now = Instant::now();
+
+        if let Some((pending_idx, start)) = self.pending.take() {
+            let elapsed = now - start;
+
+            self.data
+                .statements
+                .entry(pending_idx)
+                .or_default()
+                .total_time += elapsed;
+            self.data
+                .stacks
+                .entry(
+                    self.frames
+                        .iter()
+                        .map(|(entry_point, _)| *entry_point)
+                        .chain([pending_idx])
+                        .collect(),
+                )
+                .or_default()
+                .total_time += elapsed;
+        }
+
+        match event {
+            ProfilerEvent::FunctionEnter => {
+                self.data.functions.entry(statement_idx).or_default().calls += 1;
+                self.frames.push((statement_idx, now));
+            }
+            ProfilerEvent::Invocation => {
+                self.data
+                    .statements
+                    .entry(statement_idx)
+                    .or_default()
+                    .invocations += 1;
+                self.pending = Some((statement_idx, now));
+            }
+            ProfilerEvent::FunctionReturn => {
+                if let Some((entry_point, start)) = self.frames.pop() {
+                    self.data
+                        .functions
+                        .entry(entry_point)
+                        .or_default()
+                        .total_time += now - start;
+                }
+            }
+        }
+    }
+}
+
+extern "C" fn record_profiler_event(event: u64, statement_idx: u64) {
+    if let Some(event) = ProfilerEvent::from_raw(event) {
+        PROFILER.with(|profiler| {
+            profiler
+                .borrow_mut()
+                .record(event, StatementIdx(statement_idx as usize))
+        });
+    }
+}
+
+/// Point the profiler hook global of a compiled program to this module's collector.
+///
+/// # Safety
+///
+/// The pointer must point to the hook global of a program compiled with the profiler enabled.
+pub(crate) unsafe fn install_profiler_hook(hook_ptr: *mut u64) {
+    *hook_ptr = record_profiler_event as extern "C" fn(u64, u64) as usize as u64;
+}
+
+/// Counters of a single Sierra function.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FunctionCounters {
+    /// Number of times the function has been entered.
+    pub calls: u64,
+    /// Time spent within the function, including its callees.
+    ///
+    /// Recursive functions count the time spent in nested calls once per frame.
+    pub total_time: Duration,
+}
+
+/// Counters of a single libfunc invocation statement.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct StatementCounters {
+    /// Number of times the statement has been executed.
+    pub invocations: u64,
+    /// Time spent executing the statement.
+    pub total_time: Duration,
+}
+
+/// Counters of a call stack ending in a libfunc invocation statement.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct StackCounters {
+    /// Time spent executing the last statement with this call stack.
+    pub total_time: Duration,
+}
+
+/// The profiling data collected from the instrumented programs.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ProfileData {
+    /// Counters of every function, by their entry point.
+    pub functions: HashMap<StatementIdx, FunctionCounters>,
+    /// Counters of every libfunc invocation statement.
+    pub statements: HashMap<StatementIdx, StatementCounters>,
+    /// Counters of every call stack, as the entry points of the active functions followed by the
+    /// executed statement.
+    pub stacks: HashMap<Vec<StatementIdx>, StackCounters>,
+}
+
+impl ProfileData {
+    /// Take the data collected by the current thread and reset its counters.
+    pub fn take() -> Self {
+        PROFILER
+            .with(|profiler| std::mem::take(&mut *profiler.borrow_mut()))
+            .data
+    }
+
+    /// Return the counters of every function of the program which has been called, sorted by
+    /// decreasing total time.
+    pub fn function_counters(&self, program: &Program) -> Vec<(FunctionId, FunctionCounters)> {
+        let mut counters = program
+            .funcs
+            .iter()
+            .filter_map(|function| {
+                self.functions
+                    .get(&function.entry_point)
+                    .map(|counters| (function.id.clone(), *counters))
+            })
+            .collect::<Vec<_>>();
+        counters.sort_by(|a, b| b.1.total_time.cmp(&a.1.total_time));
+
+        counters
+    }
+
+    /// Return the counters aggregated by libfunc, sorted by decreasing total time.
+    pub fn libfunc_counters(&self, program: &Program) -> Vec<(String, StatementCounters)> {
+        let mut libfuncs = HashMap::<String, StatementCounters>::new();
+        for (statement_idx, counters) in &self.statements {
+            let entry = libfuncs
+                .entry(statement_name(program, *statement_idx))
+                .or_default();
+            entry.invocations += counters.invocations;
+            entry.total_time += counters.total_time;
+        }
+
+        let mut libfuncs = libfuncs.into_iter().collect::<Vec<_>>();
+        libfuncs.sort_by(|a, b| b.1.total_time.cmp(&a.1.total_time).then(a.0.cmp(&b.0)));
+
+        libfuncs
+    }
+
+    /// Return the gas withdrawn by every function, sorted by decreasing amount.
+    ///
+    /// Gas is withdrawn in advance for the whole branch that follows each `withdraw_gas` statement,
+    /// therefore the gas is attributed to the function which withdraws it.
+    pub fn function_gas(
+        &self,
+        program: &Program,
+        gas_metadata: &GasMetadata,
+    ) -> Vec<(FunctionId, u128)> {
+        let mut gas = HashMap::<StatementIdx, u128>::new();
+        for (statement_idx, counters) in &self.statements {
+            if let Some(cost) =
+                gas_metadata.get_gas_cost_for_statement(*statement_idx, CostTokenType::Const)
+            {
+                if let Some(function) = find_statement_function(program, *statement_idx) {
+                    *gas.entry(function.entry_point).or_default() +=
+                        cost * counters.invocations as u128;
+                }
+            }
+        }
+
+        let mut gas = program
+            .funcs
+            .iter()
+            .filter_map(|function| {
+                gas.get(&function.entry_point)
+                    .map(|gas| (function.id.clone(), *gas))
+            })
+            .collect::<Vec<_>>();
+        gas.sort_by(|a, b| b.1.cmp(&a.1));
+
+        gas
+    }
+
+    /// Export the data in the folded stacks format, with one line per call stack and the time
+    /// spent on it in nanoseconds.
+    ///
+    /// Frames are the function names followed by the libfunc name, separated by semicolons. The
+    /// lines are sorted so that the output is deterministic.
+    pub fn to_folded_stacks(&self, program: &Program) -> String {
+        let function_names = program
+            .funcs
+            .iter()
+            .map(|function| (function.entry_point, function.id.to_string()))
+            .collect::<HashMap<_, _>>();
+
+        let mut lines = self
+            .stacks
+            .iter()
+            .filter_map(|(stack, counters)| {
+                let (statement_idx, frames) = stack.split_last()?;
+                let mut line = frames
+                    .iter()
+                    .map(|entry_point| {
+                        function_names
+                            .get(entry_point)
+                            .cloned()
+                            .unwrap_or_else(|| format!("[{entry_point}]"))
+                    })
+                    .chain([statement_name(program, *statement_idx)])
+                    .map(|frame| frame.replace(';', ":"))
+                    .collect::<Vec<_>>()
+                    .join(";");
+                write!(line, " {}", counters.total_time.as_nanos()).unwrap();
+
+                Some(line)
+            })
+            .collect::<Vec<_>>();
+        lines.sort();
+
+        let mut output = lines.join("\n");
+        output.push('\n');
+        output
+    }
+}
+
+/// Return the name of the libfunc invoked by a statement.
+fn statement_name(program: &Program, statement_idx: StatementIdx) -> String {
+    match program.statements.get(statement_idx.0) {
+        Some(Statement::Invocation(invocation)) => invocation.libfunc_id.to_string(),
+        Some(Statement::Return(_)) => "return".to_string(),
+        None => format!("[{statement_idx}]"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::NativeContext, executor::JitNativeExecutor, utils::test::load_cairo, OptLevel,
+    };
+
+    #[test]
+    fn record_events() {
+        let mut state = ProfilerState::default();
+        state.record(ProfilerEvent::FunctionEnter, StatementIdx(0));
+        state.record(ProfilerEvent::Invocation, StatementIdx(1));
+        state.record(ProfilerEvent::Invocation, StatementIdx(1));
+        state.record(ProfilerEvent::FunctionEnter, StatementIdx(5));
+        state.record(ProfilerEvent::Invocation, StatementIdx(6));
+        state.record(ProfilerEvent::FunctionReturn, StatementIdx(7));
+        state.record(ProfilerEvent::FunctionReturn, StatementIdx(2));
+
+        assert!(state.frames.is_empty());
+        assert_eq!(state.data.functions[&StatementIdx(0)].calls, 1);
+        assert_eq!(state.data.functions[&StatementIdx(5)].calls, 1);
+        assert_eq!(state.data.statements[&StatementIdx(1)].invocations, 2);
+        assert_eq!(state.data.statements[&StatementIdx(6)].invocations, 1);
+
+        let mut stacks = state.data.stacks.keys().cloned().collect::<Vec<_>>();
+        stacks.sort();
+        assert_eq!(
+            stacks,
+            [
+                vec![StatementIdx(0), StatementIdx(1)],
+                vec![StatementIdx(0), StatementIdx(5), StatementIdx(6)],
+            ]
+        );
+    }
+
+    #[test]
+    fn profile_program() {
+        let (module_name, program) = load_cairo! {
+            #[inline(never)]
+            fn inner(x: felt252) -> felt252 {
+                x * 2
+            }
+
+            fn run_test() -> felt252 {
+                inner(1) + inner(2)
+            }
+        };
+
+        let native_context = NativeContext::new().with_profiler(true);
+        let native_module = native_context.compile(&program, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(native_module, OptLevel::None);
+
+        let run_test_name = format!("{0}::{0}::run_test", module_name);
+        let inner_name = format!("{0}::{0}::inner", module_name);
+        let entry_point = program
+            .funcs
+            .iter()
+            .find(|x| x.id.debug_name.as_deref() == Some(run_test_name.as_str()))
+            .unwrap();
+
+        ProfileData::take();
+        executor.invoke_dynamic(&entry_point.id, &[], None).unwrap();
+        let profile = ProfileData::take();
+
+        let calls = profile
+            .function_counters(&program)
+            .into_iter()
+            .map(|(id, counters)| (id.to_string(), counters.calls))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(calls[&run_test_name], 1);
+        assert_eq!(calls[&inner_name], 2);
+
+        let folded_stacks = profile.to_folded_stacks(&program);
+        assert!(folded_stacks
+            .lines()
+            .any(|line| line.starts_with(&format!("{run_test_name};{inner_name};felt252_mul"))));
+    }
+}
@@ -0,0 +1,302 @@
+//! # Sierra version compatibility
+//!
+//! Contract classes embed the version of the Sierra compiler that generated them. This crate is
+//! written against a specific Sierra version, so before generating any code the program's version
+//! is compared against the supported one and its libfuncs are validated against one of the
+//! starknet allowed libfunc lists of that version.
+
+use crate::error::Error;
+use cairo_lang_sierra::program::Program;
+use cairo_lang_starknet::{
+    allowed_libfuncs::{lookup_allowed_libfuncs_list, AllowedLibfuncs, ListSelector},
+    compiler_version::{current_sierra_version_id, VersionId},
+    contract_class::ContractClass,
+    felt252_serde::sierra_from_felt252s,
+};
+
+/// What to do when a program doesn't pass the compatibility checks.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MismatchPolicy {
+    /// Return an error and abort the compilation.
+    #[default]
+    Reject,
+    /// Log a warning and compile the program anyway.
+    Warn,
+}
+
+/// Configuration of the Sierra version and allowed libfuncs checks.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SierraVersionCheck {
+    /// The allowed libfuncs list to validate the program against.
+    pub allowed_libfuncs: ListSelector,
+    /// What to do when a mismatch is found.
+    pub on_mismatch: MismatchPolicy,
+}
+
+impl SierraVersionCheck {
+    pub fn new(allowed_libfuncs: ListSelector, on_mismatch: MismatchPolicy) -> Self {
+        Self {
+            allowed_libfuncs,
+            on_mismatch,
+        }
+    }
+
+    /// Validate both the Sierra version and the libfuncs used by the program.
+    ///
+    /// Errors loading the allowed libfuncs list are always returned, regardless of the policy.
+    pub fn check(&self, sierra_version: &VersionId, program: &Program) -> Result<(), Error> {
+        let result = validate_sierra_version(sierra_version).and_then(|_| {
+            validate_libfuncs(program, sierra_version, self.allowed_libfuncs.clone())
+        });
+
+        match (result, self.on_mismatch) {
+            (Err(e @ Error::AllowedLibfuncsError(_)), _) => Err(e),
+            (Err(e), MismatchPolicy::Warn) => {
+                tracing::warn!("{e}");
+                Ok(())
+            }
+            (result, _) => result,
+        }
+    }
+}
+
+/// Return the latest Sierra version supported by this crate.
+pub fn supported_sierra_version() -> VersionId {
+    current_sierra_version_id()
+}
+
+/// Extract the Sierra version, the compiler version and the program from a contract class.
+pub fn extract_contract_class_program(
+    contract_class: &ContractClass,
+) -> Result<(VersionId, VersionId, Program), Error> {
+    sierra_from_felt252s(&contract_class.sierra_program)
+        .map_err(|e| Error::SierraProgramDeserialization(e.to_string()))
+}
+
+/// Check whether a Sierra version can be compiled by this crate.
+///
+/// Sierra minor versions are backwards compatible, therefore any version with the same major and an
+/// equal or lower minor version than the supported one is accepted.
+pub fn validate_sierra_version(sierra_version: &VersionId) -> Result<(), Error> {
+    let supported = supported_sierra_version();
+
+    if sierra_version.major != supported.major || sierra_version.minor > supported.minor {
+        return Err(Error::UnsupportedSierraVersion(
+            sierra_version.to_string(),
+            supported.to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Load the selected allowed libfuncs list of a Sierra version.
+///
+/// The predefined lists (selected by name or by default) change between Sierra versions, and only
+/// the ones of the supported version are shipped with `cairo-lang-starknet`. Versions without
+/// lists are rejected. Lists read from a file are used as is, whatever the version.
+pub fn allowed_libfuncs_list(
+    sierra_version: &VersionId,
+    list_selector: ListSelector,
+) -> Result<AllowedLibfuncs, Error> {
+    match list_selector {
+        ListSelector::ListName(_) | ListSelector::DefaultList => {
+            let supported = supported_sierra_version();
+            if (sierra_version.major, sierra_version.minor) != (supported.major, supported.minor) {
+                return Err(Error::MissingAllowedLibfuncsList(
+                    list_selector.to_string(),
+                    sierra_version.to_string(),
+                ));
+            }
+
+            Ok(lookup_allowed_libfuncs_list(list_selector)?)
+        }
+        list_selector => Ok(lookup_allowed_libfuncs_list(list_selector)?),
+    }
+}
+
+/// Check that every libfunc declared by the program is in the selected allowed libfuncs list of
+/// its Sierra version.
+pub fn validate_libfuncs(
+    program: &Program,
+    sierra_version: &VersionId,
+    list_selector: ListSelector,
+) -> Result<(), Error> {
+    let list_name = list_selector.to_string();
+    let allowed_libfuncs = allowed_libfuncs_list(sierra_version, list_selector)?.allowed_libfuncs;
+
+    match program
+        .libfunc_declarations
+        .iter()
+        .find(|decl| !allowed_libfuncs.contains(&decl.long_id.generic_id))
+    {
+        Some(decl) => Err(Error::DisallowedLibfunc(
+            decl.long_id.generic_id.to_string(),
+            list_name,
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::test::load_cairo;
+    use cairo_lang_starknet::allowed_libfuncs::{
+        BUILTIN_ALL_LIBFUNCS_LIST, BUILTIN_AUDITED_LIBFUNCS_LIST,
+    };
+
+    #[test]
+    fn sierra_version_supported() {
+        let supported = supported_sierra_version();
+
+        assert!(validate_sierra_version(&supported).is_ok());
+        assert!(validate_sierra_version(&VersionId {
+            major: supported.major,
+            minor: 0,
+            patch: 0,
+        })
+        .is_ok());
+    }
+
+    #[test]
+    fn sierra_version_unsupported() {
+        let supported = supported_sierra_version();
+
+        assert!(matches!(
+            validate_sierra_version(&VersionId {
+                major: supported.major,
+                minor: supported.minor + 1,
+                patch: 0,
+            }),
+            Err(Error::UnsupportedSierraVersion(..))
+        ));
+        assert!(matches!(
+            validate_sierra_version(&VersionId {
+                major: supported.major + 1,
+                minor: 0,
+                patch: 0,
+            }),
+            Err(Error::UnsupportedSierraVersion(..))
+        ));
+    }
+
+    #[test]
+    fn libfuncs_allowed() {
+        let (_, program) = load_cairo! {
+            fn run_test(lhs: felt252, rhs: felt252) -> felt252 {
+                lhs + rhs
+            }
+        };
+
+        let version = supported_sierra_version();
+
+        assert!(validate_libfuncs(
+            &program,
+            &version,
+            ListSelector::ListName(BUILTIN_AUDITED_LIBFUNCS_LIST.to_string())
+        )
+        .is_ok());
+        assert!(validate_libfuncs(
+            &program,
+            &version,
+            ListSelector::ListName(BUILTIN_ALL_LIBFUNCS_LIST.to_string())
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn libfuncs_disallowed() {
+        let (_, program) = load_cairo! {
+            use core::debug::PrintTrait;
+
+            fn run_test() {
+                let x: felt252 = 1;
+                x.print();
+            }
+        };
+
+        assert!(matches!(
+            validate_libfuncs(
+                &program,
+                &supported_sierra_version(),
+                ListSelector::ListName(BUILTIN_AUDITED_LIBFUNCS_LIST.to_string())
+            ),
+            Err(Error::DisallowedLibfunc(..))
+        ));
+    }
+
+    #[test]
+    fn libfuncs_list_by_version() {
+        let (_, program) = load_cairo! {
+            fn run_test(lhs: felt252, rhs: felt252) -> felt252 {
+                lhs + rhs
+            }
+        };
+        let supported = supported_sierra_version();
+
+        // Patch versions share the lists of their minor version.
+        assert!(validate_libfuncs(
+            &program,
+            &VersionId {
+                patch: supported.patch + 1,
+                ..supported
+            },
+            ListSelector::DefaultList
+        )
+        .is_ok());
+
+        // Older versions allowed fewer libfuncs, but their lists aren't available.
+        assert!(matches!(
+            validate_libfuncs(
+                &program,
+                &VersionId {
+                    major: supported.major,
+                    minor: 0,
+                    patch: 0,
+                },
+                ListSelector::ListName(BUILTIN_AUDITED_LIBFUNCS_LIST.to_string())
+            ),
+            Err(Error::MissingAllowedLibfuncsList(..))
+        ));
+        assert!(matches!(
+            SierraVersionCheck::default().check(
+                &VersionId {
+                    major: supported.major,
+                    minor: 0,
+                    patch: 0,
+                },
+                &program
+            ),
+            Err(Error::MissingAllowedLibfuncsList(..))
+        ));
+    }
+
+    #[test]
+    fn check_warn_policy() {
+        let (_, program) = load_cairo! {
+            use core::debug::PrintTrait;
+
+            fn run_test() {
+                let x: felt252 = 1;
+                x.print();
+            }
+        };
+        let version = supported_sierra_version();
+
+        assert!(SierraVersionCheck::default()
+            .check(&version, &program)
+            .is_err());
+        assert!(
+            SierraVersionCheck::new(ListSelector::DefaultList, MismatchPolicy::Warn)
+                .check(&version, &program)
+                .is_ok()
+        );
+        assert!(SierraVersionCheck::new(
+            ListSelector::ListName("unknown".to_string()),
+            MismatchPolicy::Warn
+        )
+        .check(&version, &program)
+        .is_err());
+    }
+}
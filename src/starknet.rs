@@ -1,7 +1,28 @@
 //! Starknet related code for `cairo_native`
 
+#[cfg(feature = "with-serde")]
+pub use self::abi::{AbiValue, ContractAbi};
+pub use self::{
+    contract::{ContractClassProgram, EntryPoint, EntryPoints, NativeContractClass},
+    gas::SyscallGasCosts,
+    hash::{calculate_class_hash, calculate_compiled_class_hash, calculate_contract_address},
+    replay::{
+        RecordedSyscall, RecordingSyscallHandler, ReplaySyscallHandler, Syscall, SyscallRecording,
+    },
+    state::{InMemoryStarknetState, L2ToL1Message, StarknetEvent},
+    testing::StarknetTestingHandler,
+};
 use starknet_types_core::felt::Felt;
 
+#[cfg(feature = "with-serde")]
+mod abi;
+mod contract;
+mod gas;
+mod hash;
+mod replay;
+mod state;
+pub(crate) mod testing;
+
 pub type SyscallResult<T> = std::result::Result<T, Vec<Felt>>;
 
 #[repr(C)]
@@ -19,14 +40,32 @@ pub struct ArrayAbi<T> {
 #[cfg_attr(not(target_arch = "x86_64"), repr(C, align(16)))]
 pub struct Felt252Abi(pub [u8; 32]);
 /// Binary representation of a `u256` (in MLIR).
+///
+/// The field order matches Sierra's `u256 { low, high }` memory layout. Older versions declared
+/// the fields as `{ hi, lo }`, which swapped the halves of every value seen by syscall handlers:
+/// code building or reading `U256` positionally (by transmuting it or through a non self-describing
+/// serde format) must be updated, while code using the field names is not affected. Values still
+/// compare by `hi` first.
 // TODO: This shouldn't need to be public.
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
 #[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(target_arch = "x86_64", repr(C, align(8)))]
 #[cfg_attr(not(target_arch = "x86_64"), repr(C, align(16)))]
 pub struct U256 {
-    pub hi: u128,
     pub lo: u128,
+    pub hi: u128,
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.hi, self.lo).cmp(&(other.hi, other.lo))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -39,7 +78,7 @@ pub struct ExecutionInfo {
     pub entry_point_selector: Felt,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExecutionInfoV2 {
     pub block_info: BlockInfo,
@@ -49,7 +88,7 @@ pub struct ExecutionInfoV2 {
     pub entry_point_selector: Felt,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TxV2Info {
     pub version: Felt,
@@ -75,7 +114,7 @@ pub struct ResourceBounds {
     pub max_price_per_unit: u128,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BlockInfo {
     pub block_number: u64,
@@ -83,7 +122,7 @@ pub struct BlockInfo {
     pub sequencer_address: Felt,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TxInfo {
     pub version: Felt,
@@ -96,17 +135,84 @@ pub struct TxInfo {
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Secp256k1Point {
     pub x: U256,
     pub y: U256,
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Secp256r1Point {
     pub x: U256,
     pub y: U256,
 }
 
+#[cfg(feature = "with-runtime")]
+mod runtime_conversions {
+    use super::{Secp256k1Point, Secp256r1Point, U256};
+    use cairo_native_runtime::syscalls;
+
+    impl From<syscalls::U256> for U256 {
+        fn from(value: syscalls::U256) -> Self {
+            Self {
+                lo: value.lo,
+                hi: value.hi,
+            }
+        }
+    }
+
+    impl From<U256> for syscalls::U256 {
+        fn from(value: U256) -> Self {
+            Self {
+                lo: value.lo,
+                hi: value.hi,
+            }
+        }
+    }
+
+    macro_rules! impl_point_conversions {
+        ( $point:ident ) => {
+            impl From<syscalls::Secp256Point> for $point {
+                fn from(value: syscalls::Secp256Point) -> Self {
+                    Self {
+                        x: value.x.into(),
+                        y: value.y.into(),
+                    }
+                }
+            }
+
+            impl From<$point> for syscalls::Secp256Point {
+                fn from(value: $point) -> Self {
+                    Self {
+                        x: value.x.into(),
+                        y: value.y.into(),
+                    }
+                }
+            }
+        };
+    }
+
+    impl_point_conversions!(Secp256k1Point);
+    impl_point_conversions!(Secp256r1Point);
+}
+
+/// Run the default implementation of a syscall, which is provided by the runtime library.
+macro_rules! with_runtime {
+    ( $body:block ) => {{
+        #[cfg(feature = "with-runtime")]
+        $body
+        #[cfg(not(feature = "with-runtime"))]
+        unimplemented!("this syscall requires either the `with-runtime` feature or a custom handler")
+    }};
+}
+
+/// A handler for the Starknet syscalls made by the executed contracts.
+///
+/// The trait is object safe: executors take handlers as `&mut dyn StarknetSyscallHandler`, so they
+/// can be chosen at runtime and inspected after the execution. It's also implemented for `&mut T`
+/// and `Box<T>`, which forward every syscall to the handler they point to.
+#[cfg_attr(not(feature = "with-runtime"), allow(unused_variables))]
 pub trait StarknetSyscallHandler {
     fn get_block_hash(
         &mut self,
@@ -174,130 +280,470 @@ pub trait StarknetSyscallHandler {
         remaining_gas: &mut u128,
     ) -> SyscallResult<()>;
 
-    fn keccak(&mut self, input: &[u64], remaining_gas: &mut u128) -> SyscallResult<U256>;
+    /// Return the class hash of the contract deployed at the given address, or zero if there is
+    /// none.
+    ///
+    /// The default implementation fails, since it requires access to the state.
+    fn get_class_hash_at(
+        &mut self,
+        _contract_address: Felt,
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Felt> {
+        Err(vec![Felt::from_bytes_be_slice(
+            b"GET_CLASS_HASH_AT_UNIMPLEMENTED",
+        )])
+    }
+
+    /// Call a contract as if it were the target of a version 0 transaction with the given
+    /// signature: the callee sees a zero caller address, and the version and signature of the
+    /// transaction info are overridden for the duration of the call.
+    ///
+    /// The default implementation fails, since it requires access to the state.
+    fn meta_tx_v0(
+        &mut self,
+        _address: Felt,
+        _entry_point_selector: Felt,
+        _calldata: &[Felt],
+        _signature: &[Felt],
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        Err(vec![Felt::from_bytes_be_slice(b"META_TX_V0_UNIMPLEMENTED")])
+    }
+
+    /// Compute the keccak hash of the (already padded) input.
+    ///
+    /// The default implementation is provided by the runtime library and charges gas per round
+    /// like the VM does.
+    fn keccak(&mut self, input: &[u64], remaining_gas: &mut u128) -> SyscallResult<U256> {
+        with_runtime!({
+            cairo_native_runtime::syscalls::keccak(input, remaining_gas).map(Into::into)
+        })
+    }
+
+    /// Apply the sha256 compression function of a single 512-bit block to `state`.
+    ///
+    /// The default implementation is provided by the runtime library.
+    fn sha256_process_block(
+        &mut self,
+        state: &mut [u32; 8],
+        block: &[u32; 16],
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        with_runtime!({ cairo_native_runtime::syscalls::sha256_process_block(state, block) })
+    }
 
     fn secp256k1_new(
         &mut self,
         x: U256,
         y: U256,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<Option<Secp256k1Point>>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256k1Point>> {
+        with_runtime!({
+            cairo_native_runtime::syscalls::secp256k1_new(x.into(), y.into())
+                .map(|p| p.map(Into::into))
+        })
+    }
 
     fn secp256k1_add(
         &mut self,
         p0: Secp256k1Point,
         p1: Secp256k1Point,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<Secp256k1Point>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256k1Point> {
+        with_runtime!({
+            cairo_native_runtime::syscalls::secp256k1_add(p0.into(), p1.into()).map(Into::into)
+        })
+    }
 
     fn secp256k1_mul(
         &mut self,
         p: Secp256k1Point,
         m: U256,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<Secp256k1Point>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256k1Point> {
+        with_runtime!({
+            cairo_native_runtime::syscalls::secp256k1_mul(p.into(), m.into()).map(Into::into)
+        })
+    }
 
     fn secp256k1_get_point_from_x(
         &mut self,
         x: U256,
         y_parity: bool,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<Option<Secp256k1Point>>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256k1Point>> {
+        with_runtime!({
+            cairo_native_runtime::syscalls::secp256k1_get_point_from_x(x.into(), y_parity)
+                .map(|p| p.map(Into::into))
+        })
+    }
 
     fn secp256k1_get_xy(
         &mut self,
         p: Secp256k1Point,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<(U256, U256)>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<(U256, U256)> {
+        with_runtime!({
+            cairo_native_runtime::syscalls::secp256k1_get_xy(p.into())
+                .map(|(x, y)| (x.into(), y.into()))
+        })
+    }
 
     fn secp256r1_new(
         &mut self,
         x: U256,
         y: U256,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<Option<Secp256r1Point>>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256r1Point>> {
+        with_runtime!({
+            cairo_native_runtime::syscalls::secp256r1_new(x.into(), y.into())
+                .map(|p| p.map(Into::into))
+        })
+    }
 
     fn secp256r1_add(
         &mut self,
         p0: Secp256r1Point,
         p1: Secp256r1Point,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<Secp256r1Point>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256r1Point> {
+        with_runtime!({
+            cairo_native_runtime::syscalls::secp256r1_add(p0.into(), p1.into()).map(Into::into)
+        })
+    }
 
     fn secp256r1_mul(
         &mut self,
         p: Secp256r1Point,
         m: U256,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<Secp256r1Point>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256r1Point> {
+        with_runtime!({
+            cairo_native_runtime::syscalls::secp256r1_mul(p.into(), m.into()).map(Into::into)
+        })
+    }
 
     fn secp256r1_get_point_from_x(
         &mut self,
         x: U256,
         y_parity: bool,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<Option<Secp256r1Point>>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256r1Point>> {
+        with_runtime!({
+            cairo_native_runtime::syscalls::secp256r1_get_point_from_x(x.into(), y_parity)
+                .map(|p| p.map(Into::into))
+        })
+    }
 
     fn secp256r1_get_xy(
         &mut self,
         p: Secp256r1Point,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<(U256, U256)>;
-
-    // Testing syscalls.
-    fn pop_log(&mut self) {
-        unimplemented!()
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<(U256, U256)> {
+        with_runtime!({
+            cairo_native_runtime::syscalls::secp256r1_get_xy(p.into())
+                .map(|(x, y)| (x.into(), y.into()))
+        })
     }
 
-    fn set_account_contract_address(&mut self, _contract_address: Felt) {
-        unimplemented!()
-    }
+    // Gas costs.
 
-    fn set_block_number(&mut self, _block_number: u64) {
-        unimplemented!()
+    /// The base cost of every syscall, which is deducted before calling the handler.
+    ///
+    /// Handlers only have to charge the dynamic part of a syscall's cost. If there isn't enough
+    /// gas for the base cost, the syscall fails with `Syscall out of gas` without calling them.
+    fn syscall_gas_costs(&self) -> SyscallGasCosts {
+        SyscallGasCosts::default()
     }
 
-    fn set_block_timestamp(&mut self, _block_timestamp: u64) {
-        unimplemented!()
-    }
+    // Call frames.
 
-    fn set_caller_address(&mut self, _address: Felt) {
-        unimplemented!()
-    }
+    /// Open a call frame before running a nested `deploy`, `library_call` or `call_contract`.
+    ///
+    /// Every frame is closed by either [`commit_call_frame`](Self::commit_call_frame) or
+    /// [`revert_call_frame`](Self::revert_call_frame), depending on the result of the call. Only
+    /// handlers whose state must be rolled back when a nested call fails need to implement them.
+    fn begin_call_frame(&mut self) {}
 
-    fn set_chain_id(&mut self, _chain_id: Felt) {
-        unimplemented!()
-    }
+    /// Close the innermost call frame keeping its changes, which now belong to its parent frame.
+    fn commit_call_frame(&mut self) {}
 
-    fn set_contract_address(&mut self, _address: Felt) {
-        unimplemented!()
-    }
+    /// Close the innermost call frame discarding its storage writes, events and messages.
+    fn revert_call_frame(&mut self) {}
 
-    fn set_max_fee(&mut self, _max_fee: u128) {
-        unimplemented!()
-    }
+    // Testing.
 
-    fn set_nonce(&mut self, _nonce: Felt) {
-        unimplemented!()
+    /// Return the handler which runs the cheatcodes of the test plugin (ex. `set_caller_address`
+    /// or `pop_log`), or `None` to ignore them.
+    fn testing_handler(&mut self) -> Option<&mut dyn StarknetTestingHandler> {
+        None
     }
+}
 
-    fn set_sequencer_address(&mut self, _address: Felt) {
-        unimplemented!()
-    }
+/// Implement [`StarknetSyscallHandler`] for a pointer type by forwarding every syscall (including
+/// the provided ones) to the handler it points to.
+macro_rules! forward_syscall_handler {
+    ( $ty:ty ) => {
+        impl<T> StarknetSyscallHandler for $ty
+        where
+            T: StarknetSyscallHandler + ?Sized,
+        {
+            fn get_block_hash(
+                &mut self,
+                block_number: u64,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<Felt> {
+                (**self).get_block_hash(block_number, remaining_gas)
+            }
 
-    fn set_signature(&mut self, _signature: &[Felt]) {
-        unimplemented!()
-    }
+            fn get_execution_info(
+                &mut self,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<ExecutionInfo> {
+                (**self).get_execution_info(remaining_gas)
+            }
 
-    fn set_transaction_hash(&mut self, _transaction_hash: Felt) {
-        unimplemented!()
-    }
+            fn get_execution_info_v2(
+                &mut self,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<ExecutionInfoV2> {
+                (**self).get_execution_info_v2(remaining_gas)
+            }
 
-    fn set_version(&mut self, _version: Felt) {
-        unimplemented!()
-    }
+            fn deploy(
+                &mut self,
+                class_hash: Felt,
+                contract_address_salt: Felt,
+                calldata: &[Felt],
+                deploy_from_zero: bool,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<(Felt, Vec<Felt>)> {
+                (**self).deploy(
+                    class_hash,
+                    contract_address_salt,
+                    calldata,
+                    deploy_from_zero,
+                    remaining_gas,
+                )
+            }
+
+            fn replace_class(
+                &mut self,
+                class_hash: Felt,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<()> {
+                (**self).replace_class(class_hash, remaining_gas)
+            }
+
+            fn library_call(
+                &mut self,
+                class_hash: Felt,
+                function_selector: Felt,
+                calldata: &[Felt],
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<Vec<Felt>> {
+                (**self).library_call(class_hash, function_selector, calldata, remaining_gas)
+            }
+
+            fn call_contract(
+                &mut self,
+                address: Felt,
+                entry_point_selector: Felt,
+                calldata: &[Felt],
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<Vec<Felt>> {
+                (**self).call_contract(address, entry_point_selector, calldata, remaining_gas)
+            }
+
+            fn storage_read(
+                &mut self,
+                address_domain: u32,
+                address: Felt,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<Felt> {
+                (**self).storage_read(address_domain, address, remaining_gas)
+            }
+
+            fn storage_write(
+                &mut self,
+                address_domain: u32,
+                address: Felt,
+                value: Felt,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<()> {
+                (**self).storage_write(address_domain, address, value, remaining_gas)
+            }
+
+            fn emit_event(
+                &mut self,
+                keys: &[Felt],
+                data: &[Felt],
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<()> {
+                (**self).emit_event(keys, data, remaining_gas)
+            }
+
+            fn send_message_to_l1(
+                &mut self,
+                to_address: Felt,
+                payload: &[Felt],
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<()> {
+                (**self).send_message_to_l1(to_address, payload, remaining_gas)
+            }
+
+            fn keccak(&mut self, input: &[u64], remaining_gas: &mut u128) -> SyscallResult<U256> {
+                (**self).keccak(input, remaining_gas)
+            }
+
+            fn get_class_hash_at(
+                &mut self,
+                contract_address: Felt,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<Felt> {
+                (**self).get_class_hash_at(contract_address, remaining_gas)
+            }
+
+            fn meta_tx_v0(
+                &mut self,
+                address: Felt,
+                entry_point_selector: Felt,
+                calldata: &[Felt],
+                signature: &[Felt],
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<Vec<Felt>> {
+                (**self).meta_tx_v0(
+                    address,
+                    entry_point_selector,
+                    calldata,
+                    signature,
+                    remaining_gas,
+                )
+            }
+
+            fn sha256_process_block(
+                &mut self,
+                state: &mut [u32; 8],
+                block: &[u32; 16],
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<()> {
+                (**self).sha256_process_block(state, block, remaining_gas)
+            }
+
+            fn secp256k1_new(
+                &mut self,
+                x: U256,
+                y: U256,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<Option<Secp256k1Point>> {
+                (**self).secp256k1_new(x, y, remaining_gas)
+            }
+
+            fn secp256k1_add(
+                &mut self,
+                p0: Secp256k1Point,
+                p1: Secp256k1Point,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<Secp256k1Point> {
+                (**self).secp256k1_add(p0, p1, remaining_gas)
+            }
+
+            fn secp256k1_mul(
+                &mut self,
+                p: Secp256k1Point,
+                m: U256,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<Secp256k1Point> {
+                (**self).secp256k1_mul(p, m, remaining_gas)
+            }
+
+            fn secp256k1_get_point_from_x(
+                &mut self,
+                x: U256,
+                y_parity: bool,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<Option<Secp256k1Point>> {
+                (**self).secp256k1_get_point_from_x(x, y_parity, remaining_gas)
+            }
+
+            fn secp256k1_get_xy(
+                &mut self,
+                p: Secp256k1Point,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<(U256, U256)> {
+                (**self).secp256k1_get_xy(p, remaining_gas)
+            }
+
+            fn secp256r1_new(
+                &mut self,
+                x: U256,
+                y: U256,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<Option<Secp256r1Point>> {
+                (**self).secp256r1_new(x, y, remaining_gas)
+            }
+
+            fn secp256r1_add(
+                &mut self,
+                p0: Secp256r1Point,
+                p1: Secp256r1Point,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<Secp256r1Point> {
+                (**self).secp256r1_add(p0, p1, remaining_gas)
+            }
+
+            fn secp256r1_mul(
+                &mut self,
+                p: Secp256r1Point,
+                m: U256,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<Secp256r1Point> {
+                (**self).secp256r1_mul(p, m, remaining_gas)
+            }
+
+            fn secp256r1_get_point_from_x(
+                &mut self,
+                x: U256,
+                y_parity: bool,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<Option<Secp256r1Point>> {
+                (**self).secp256r1_get_point_from_x(x, y_parity, remaining_gas)
+            }
+
+            fn secp256r1_get_xy(
+                &mut self,
+                p: Secp256r1Point,
+                remaining_gas: &mut u128,
+            ) -> SyscallResult<(U256, U256)> {
+                (**self).secp256r1_get_xy(p, remaining_gas)
+            }
+
+            fn syscall_gas_costs(&self) -> SyscallGasCosts {
+                (**self).syscall_gas_costs()
+            }
+
+            fn begin_call_frame(&mut self) {
+                (**self).begin_call_frame()
+            }
+
+            fn commit_call_frame(&mut self) {
+                (**self).commit_call_frame()
+            }
+
+            fn revert_call_frame(&mut self) {
+                (**self).revert_call_frame()
+            }
+
+            fn testing_handler(&mut self) -> Option<&mut dyn StarknetTestingHandler> {
+                (**self).testing_handler()
+            }
+        }
+    };
 }
 
+forward_syscall_handler!(&mut T);
+forward_syscall_handler!(Box<T>);
+
 pub struct DummySyscallHandler;
 
 impl StarknetSyscallHandler for DummySyscallHandler {
@@ -391,98 +837,6 @@ impl StarknetSyscallHandler for DummySyscallHandler {
     ) -> SyscallResult<()> {
         unimplemented!()
     }
-
-    fn keccak(&mut self, _input: &[u64], _remaining_gas: &mut u128) -> SyscallResult<U256> {
-        unimplemented!()
-    }
-
-    fn secp256k1_new(
-        &mut self,
-        _x: U256,
-        _y: U256,
-        _remaining_gas: &mut u128,
-    ) -> SyscallResult<Option<Secp256k1Point>> {
-        unimplemented!()
-    }
-
-    fn secp256k1_add(
-        &mut self,
-        _p0: Secp256k1Point,
-        _p1: Secp256k1Point,
-        _remaining_gas: &mut u128,
-    ) -> SyscallResult<Secp256k1Point> {
-        unimplemented!()
-    }
-
-    fn secp256k1_mul(
-        &mut self,
-        _p: Secp256k1Point,
-        _m: U256,
-        _remaining_gas: &mut u128,
-    ) -> SyscallResult<Secp256k1Point> {
-        unimplemented!()
-    }
-
-    fn secp256k1_get_point_from_x(
-        &mut self,
-        _x: U256,
-        _y_parity: bool,
-        _remaining_gas: &mut u128,
-    ) -> SyscallResult<Option<Secp256k1Point>> {
-        unimplemented!()
-    }
-
-    fn secp256k1_get_xy(
-        &mut self,
-        _p: Secp256k1Point,
-        _remaining_gas: &mut u128,
-    ) -> SyscallResult<(U256, U256)> {
-        unimplemented!()
-    }
-
-    fn secp256r1_new(
-        &mut self,
-        _x: U256,
-        _y: U256,
-        _remaining_gas: &mut u128,
-    ) -> SyscallResult<Option<Secp256r1Point>> {
-        unimplemented!()
-    }
-
-    fn secp256r1_add(
-        &mut self,
-        _p0: Secp256r1Point,
-        _p1: Secp256r1Point,
-        _remaining_gas: &mut u128,
-    ) -> SyscallResult<Secp256r1Point> {
-        unimplemented!()
-    }
-
-    fn secp256r1_mul(
-        &mut self,
-        _p: Secp256r1Point,
-        _m: U256,
-        _remaining_gas: &mut u128,
-    ) -> SyscallResult<Secp256r1Point> {
-        unimplemented!()
-    }
-
-    fn secp256r1_get_point_from_x(
-        &mut self,
-        _x: U256,
-        _y_parity: bool,
-        _remaining_gas: &mut u128,
-    ) -> SyscallResult<Option<Secp256r1Point>> {
-        unimplemented!()
-    }
-
-    fn secp256r1_get_xy(
-        &mut self,
-        _p: Secp256r1Point,
-        _remaining_gas: &mut u128,
-    ) -> SyscallResult<(U256, U256)> {
-        unimplemented!()
-    }
 }
 
 // TODO: Move to the correct place or remove if unused.
@@ -743,6 +1097,29 @@ pub(crate) mod handler {
             gas: &mut u128,
             p: &Secp256r1Point,
         ),
+
+        get_class_hash_at: extern "C" fn(
+            result_ptr: &mut SyscallResultAbi<Felt252Abi>,
+            ptr: &mut T,
+            gas: &mut u128,
+            contract_address: &Felt252Abi,
+        ),
+        sha256_process_block: extern "C" fn(
+            result_ptr: &mut SyscallResultAbi<*mut [u32; 8]>,
+            ptr: &mut T,
+            gas: &mut u128,
+            state: *mut [u32; 8],
+            block: &[u32; 16],
+        ),
+        meta_tx_v0: extern "C" fn(
+            result_ptr: &mut SyscallResultAbi<ArrayAbi<Felt252Abi>>,
+            ptr: &mut T,
+            gas: &mut u128,
+            address: &Felt252Abi,
+            entry_point_selector: &Felt252Abi,
+            calldata: &ArrayAbi<Felt252Abi>,
+            signature: &ArrayAbi<Felt252Abi>,
+        ),
     }
 
     impl<'a, T> StarknetSyscallHandlerCallbacks<'a, T>
@@ -775,6 +1152,17 @@ pub(crate) mod handler {
         pub const SECP256R1_GET_POINT_FROM_X: usize =
             field_offset!(Self, secp256r1_get_point_from_x) >> 3;
         pub const SECP256R1_GET_XY: usize = field_offset!(Self, secp256r1_get_xy) >> 3;
+
+        pub const GET_CLASS_HASH_AT: usize = field_offset!(Self, get_class_hash_at) >> 3;
+        pub const SHA256_PROCESS_BLOCK: usize = field_offset!(Self, sha256_process_block) >> 3;
+        pub const META_TX_V0: usize = field_offset!(Self, meta_tx_v0) >> 3;
+    }
+
+    impl<'a, T> StarknetSyscallHandlerCallbacks<'a, T> {
+        /// Return the handler the callbacks forward the syscalls to.
+        pub(crate) fn handler(&mut self) -> &mut T {
+            self.self_ptr
+        }
     }
 
     #[allow(unused_variables)]
@@ -807,6 +1195,9 @@ pub(crate) mod handler {
                 secp256r1_mul: Self::wrap_secp256r1_mul,
                 secp256r1_get_point_from_x: Self::wrap_secp256r1_get_point_from_x,
                 secp256r1_get_xy: Self::wrap_secp256r1_get_xy,
+                get_class_hash_at: Self::wrap_get_class_hash_at,
+                sha256_process_block: Self::wrap_sha256_process_block,
+                meta_tx_v0: Self::wrap_meta_tx_v0,
             }
         }
 
@@ -837,6 +1228,43 @@ pub(crate) mod handler {
             }
         }
 
+        /// Read the felts of an array or span.
+        unsafe fn read_felt_array(data: &ArrayAbi<Felt252Abi>) -> Vec<Felt> {
+            if data.ptr.is_null() {
+                return Vec::new();
+            }
+
+            let len = (data.until - data.since) as usize;
+            std::slice::from_raw_parts(data.ptr.add(data.since as usize), len)
+                .iter()
+                .map(|x| Felt::from_bytes_le(&x.0))
+                .collect()
+        }
+
+        /// Run a nested call inside a call frame, which is reverted if the call fails.
+        fn in_call_frame<R>(
+            ptr: &mut T,
+            call: impl FnOnce(&mut T) -> SyscallResult<R>,
+        ) -> SyscallResult<R> {
+            ptr.begin_call_frame();
+            let result = call(ptr);
+            match result {
+                Ok(_) => ptr.commit_call_frame(),
+                Err(_) => ptr.revert_call_frame(),
+            }
+
+            result
+        }
+
+        /// Deduct the base cost of a syscall before calling its handler.
+        fn charge_base_gas(
+            ptr: &T,
+            gas: &mut u128,
+            cost: impl FnOnce(SyscallGasCosts) -> u128,
+        ) -> SyscallResult<()> {
+            cairo_native_runtime::syscalls::deduct_gas(gas, cost(ptr.syscall_gas_costs()))
+        }
+
         fn wrap_error<E>(e: &[Felt]) -> SyscallResultAbi<E> {
             SyscallResultAbi {
                 err: ManuallyDrop::new(SyscallResultAbiErr {
@@ -855,7 +1283,8 @@ pub(crate) mod handler {
             gas: &mut u128,
             block_number: u64,
         ) {
-            let result = ptr.get_block_hash(block_number, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.get_block_hash)
+                .and_then(|()| ptr.get_block_hash(block_number, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -873,7 +1302,8 @@ pub(crate) mod handler {
             ptr: &mut T,
             gas: &mut u128,
         ) {
-            let result = ptr.get_execution_info(gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.get_execution_info)
+                .and_then(|()| ptr.get_execution_info(gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -938,7 +1368,8 @@ pub(crate) mod handler {
             ptr: &mut T,
             gas: &mut u128,
         ) {
-            let result = ptr.get_execution_info_v2(gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.get_execution_info)
+                .and_then(|()| ptr.get_execution_info_v2(gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1066,13 +1497,17 @@ pub(crate) mod handler {
             })
             .collect();
 
-            let result = ptr.deploy(
-                class_hash,
-                contract_address_salt,
-                &calldata,
-                deploy_from_zero,
-                gas,
-            );
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.deploy).and_then(|()| {
+                Self::in_call_frame(ptr, |ptr| {
+                    ptr.deploy(
+                        class_hash,
+                        contract_address_salt,
+                        &calldata,
+                        deploy_from_zero,
+                        gas,
+                    )
+                })
+            });
 
             *result_ptr = match result {
                 Ok(x) => {
@@ -1100,7 +1535,8 @@ pub(crate) mod handler {
                 data.reverse();
                 data
             });
-            let result = ptr.replace_class(class_hash, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.replace_class)
+                .and_then(|()| ptr.replace_class(class_hash, gas));
 
             *result_ptr = match result {
                 Ok(_) => SyscallResultAbi {
@@ -1149,7 +1585,12 @@ pub(crate) mod handler {
             })
             .collect();
 
-            let result = ptr.library_call(class_hash, function_selector, &calldata, gas);
+            let result =
+                Self::charge_base_gas(ptr, gas, |costs| costs.library_call).and_then(|()| {
+                    Self::in_call_frame(ptr, |ptr| {
+                        ptr.library_call(class_hash, function_selector, &calldata, gas)
+                    })
+                });
 
             *result_ptr = match result {
                 Ok(x) => {
@@ -1202,7 +1643,12 @@ pub(crate) mod handler {
             })
             .collect();
 
-            let result = ptr.call_contract(address, entry_point_selector, &calldata, gas);
+            let result =
+                Self::charge_base_gas(ptr, gas, |costs| costs.call_contract).and_then(|()| {
+                    Self::in_call_frame(ptr, |ptr| {
+                        ptr.call_contract(address, entry_point_selector, &calldata, gas)
+                    })
+                });
 
             *result_ptr = match result {
                 Ok(x) => {
@@ -1231,7 +1677,8 @@ pub(crate) mod handler {
                 data.reverse();
                 data
             });
-            let result = ptr.storage_read(address_domain, address, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.storage_read)
+                .and_then(|()| ptr.storage_read(address_domain, address, gas));
 
             *result_ptr = match result {
                 Ok(res) => SyscallResultAbi {
@@ -1262,7 +1709,8 @@ pub(crate) mod handler {
                 data.reverse();
                 data
             });
-            let result = ptr.storage_write(address_domain, address, value, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.storage_write)
+                .and_then(|()| ptr.storage_write(address_domain, address, value, gas));
 
             *result_ptr = match result {
                 Ok(_) => SyscallResultAbi {
@@ -1316,7 +1764,8 @@ pub(crate) mod handler {
             })
             .collect();
 
-            let result = ptr.emit_event(&keys, &data, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.emit_event)
+                .and_then(|()| ptr.emit_event(&keys, &data, gas));
 
             *result_ptr = match result {
                 Ok(_) => SyscallResultAbi {
@@ -1358,7 +1807,8 @@ pub(crate) mod handler {
             })
             .collect();
 
-            let result = ptr.send_message_to_l1(to_address, &payload, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.send_message_to_l1)
+                .and_then(|()| ptr.send_message_to_l1(to_address, &payload, gas));
 
             *result_ptr = match result {
                 Ok(_) => SyscallResultAbi {
@@ -1385,7 +1835,8 @@ pub(crate) mod handler {
                 std::slice::from_raw_parts(input.ptr.add(since_offset), len)
             };
 
-            let result = ptr.keccak(input, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.keccak)
+                .and_then(|()| ptr.keccak(input, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1405,7 +1856,8 @@ pub(crate) mod handler {
             x: &U256,
             y: &U256,
         ) {
-            let result = ptr.secp256k1_new(*x, *y, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.secp256k1_new)
+                .and_then(|()| ptr.secp256k1_new(*x, *y, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1428,7 +1880,8 @@ pub(crate) mod handler {
             p0: &Secp256k1Point,
             p1: &Secp256k1Point,
         ) {
-            let result = ptr.secp256k1_add(*p0, *p1, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.secp256k1_add)
+                .and_then(|()| ptr.secp256k1_add(*p0, *p1, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1448,7 +1901,8 @@ pub(crate) mod handler {
             p: &Secp256k1Point,
             scalar: &U256,
         ) {
-            let result = ptr.secp256k1_mul(*p, *scalar, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.secp256k1_mul)
+                .and_then(|()| ptr.secp256k1_mul(*p, *scalar, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1468,7 +1922,8 @@ pub(crate) mod handler {
             x: &U256,
             y_parity: &bool,
         ) {
-            let result = ptr.secp256k1_get_point_from_x(*x, *y_parity, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.secp256k1_get_point_from_x)
+                .and_then(|()| ptr.secp256k1_get_point_from_x(*x, *y_parity, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1490,7 +1945,8 @@ pub(crate) mod handler {
             gas: &mut u128,
             p: &Secp256k1Point,
         ) {
-            let result = ptr.secp256k1_get_xy(*p, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.secp256k1_get_xy)
+                .and_then(|()| ptr.secp256k1_get_xy(*p, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1510,7 +1966,8 @@ pub(crate) mod handler {
             x: &U256,
             y: &U256,
         ) {
-            let result = ptr.secp256r1_new(*x, *y, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.secp256r1_new)
+                .and_then(|()| ptr.secp256r1_new(*x, *y, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1533,7 +1990,8 @@ pub(crate) mod handler {
             p0: &Secp256r1Point,
             p1: &Secp256r1Point,
         ) {
-            let result = ptr.secp256r1_add(*p0, *p1, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.secp256r1_add)
+                .and_then(|()| ptr.secp256r1_add(*p0, *p1, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1553,7 +2011,8 @@ pub(crate) mod handler {
             p: &Secp256r1Point,
             scalar: &U256,
         ) {
-            let result = ptr.secp256r1_mul(*p, *scalar, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.secp256r1_mul)
+                .and_then(|()| ptr.secp256r1_mul(*p, *scalar, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1573,7 +2032,8 @@ pub(crate) mod handler {
             x: &U256,
             y_parity: &bool,
         ) {
-            let result = ptr.secp256r1_get_point_from_x(*x, *y_parity, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.secp256r1_get_point_from_x)
+                .and_then(|()| ptr.secp256r1_get_point_from_x(*x, *y_parity, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1595,7 +2055,8 @@ pub(crate) mod handler {
             gas: &mut u128,
             p: &Secp256r1Point,
         ) {
-            let result = ptr.secp256r1_get_xy(*p, gas);
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.secp256r1_get_xy)
+                .and_then(|()| ptr.secp256r1_get_xy(*p, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1607,5 +2068,84 @@ pub(crate) mod handler {
                 Err(e) => Self::wrap_error(&e),
             };
         }
+
+        extern "C" fn wrap_get_class_hash_at(
+            result_ptr: &mut SyscallResultAbi<Felt252Abi>,
+            ptr: &mut T,
+            gas: &mut u128,
+            contract_address: &Felt252Abi,
+        ) {
+            let contract_address = Felt::from_bytes_le(&contract_address.0);
+
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.get_class_hash_at)
+                .and_then(|()| ptr.get_class_hash_at(contract_address, gas));
+
+            *result_ptr = match result {
+                Ok(x) => SyscallResultAbi {
+                    ok: ManuallyDrop::new(SyscallResultAbiOk {
+                        tag: 0u8,
+                        payload: ManuallyDrop::new(Felt252Abi(x.to_bytes_le())),
+                    }),
+                },
+                Err(e) => Self::wrap_error(&e),
+            };
+        }
+
+        extern "C" fn wrap_sha256_process_block(
+            result_ptr: &mut SyscallResultAbi<*mut [u32; 8]>,
+            ptr: &mut T,
+            gas: &mut u128,
+            state: *mut [u32; 8],
+            block: &[u32; 16],
+        ) {
+            // The state is updated in place and returned as the new state handle.
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.sha256_process_block)
+                .and_then(|()| ptr.sha256_process_block(unsafe { &mut *state }, block, gas));
+
+            *result_ptr = match result {
+                Ok(()) => SyscallResultAbi {
+                    ok: ManuallyDrop::new(SyscallResultAbiOk {
+                        tag: 0u8,
+                        payload: ManuallyDrop::new(state),
+                    }),
+                },
+                Err(e) => Self::wrap_error(&e),
+            };
+        }
+
+        extern "C" fn wrap_meta_tx_v0(
+            result_ptr: &mut SyscallResultAbi<ArrayAbi<Felt252Abi>>,
+            ptr: &mut T,
+            gas: &mut u128,
+            address: &Felt252Abi,
+            entry_point_selector: &Felt252Abi,
+            calldata: &ArrayAbi<Felt252Abi>,
+            signature: &ArrayAbi<Felt252Abi>,
+        ) {
+            let address = Felt::from_bytes_le(&address.0);
+            let entry_point_selector = Felt::from_bytes_le(&entry_point_selector.0);
+            let calldata = unsafe { Self::read_felt_array(calldata) };
+            let signature = unsafe { Self::read_felt_array(signature) };
+
+            let result = Self::charge_base_gas(ptr, gas, |costs| costs.meta_tx_v0).and_then(|()| {
+                Self::in_call_frame(ptr, |ptr| {
+                    ptr.meta_tx_v0(address, entry_point_selector, &calldata, &signature, gas)
+                })
+            });
+
+            *result_ptr = match result {
+                Ok(x) => {
+                    let felts: Vec<_> = x.iter().map(|x| Felt252Abi(x.to_bytes_le())).collect();
+                    let felts_ptr = unsafe { Self::alloc_mlir_array(&felts) };
+                    SyscallResultAbi {
+                        ok: ManuallyDrop::new(SyscallResultAbiOk {
+                            tag: 0u8,
+                            payload: ManuallyDrop::new(felts_ptr),
+                        }),
+                    }
+                }
+                Err(e) => Self::wrap_error(&e),
+            };
+        }
     }
 }
@@ -0,0 +1,932 @@
+//! # Contract ABI
+//!
+//! The ABI of a contract class describes the Cairo types of its entry points' inputs and outputs.
+//! [`ContractAbi`] uses it to convert between the felts passed to and returned by the entry points
+//! and [`AbiValue`]s, following the `Serde` implementations of the corelib:
+//!
+//!   - Felts, contract addresses, class hashes and other felt-like types are a single felt.
+//!   - Booleans and integers are a single felt. Negative integers are represented as `-x`.
+//!   - `u256` is its low and high halves.
+//!   - Arrays and spans are their length followed by their elements.
+//!   - Tuples and structs are their elements or members, in order.
+//!   - Enums are their variant index followed by the variant's value.
+//!   - Byte arrays are their full 31-byte words as an array, followed by the remaining bytes as a
+//!     felt and their count.
+//!
+//! Events are split into keys and data, as described by the ABI's event definitions. Every variant
+//! of an event enum adds its selector to the keys, unless it's marked as flat.
+
+use super::{L2ToL1Message, U256};
+use crate::error::Error;
+use cairo_lang_starknet::{
+    abi::{EnumVariant, Event, EventFieldKind, EventKind, Input, Item, Output, StructMember},
+    contract::starknet_keccak,
+    contract_class::ContractClass,
+};
+use num_traits::ToPrimitive;
+use starknet_types_core::felt::Felt;
+use std::collections::HashSet;
+
+/// A Cairo value described by an ABI type.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AbiValue {
+    /// A `felt252` or a felt-like type, like `ContractAddress` or `ClassHash`.
+    Felt(Felt),
+    Bool(bool),
+    /// An unsigned integer up to `u128`.
+    Uint(u128),
+    /// A signed integer up to `i128`.
+    Int(i128),
+    U256(U256),
+    /// A `ByteArray`'s bytes.
+    ByteArray(Vec<u8>),
+    /// An `Array` or a `Span`.
+    Array(Vec<AbiValue>),
+    Tuple(Vec<AbiValue>),
+    /// A struct's members by name, in declaration order.
+    Struct(Vec<(String, AbiValue)>),
+    Enum {
+        variant: String,
+        value: Box<AbiValue>,
+    },
+}
+
+/// The ABI of a contract class.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ContractAbi {
+    items: Vec<Item>,
+}
+
+impl ContractAbi {
+    /// Parse the ABI from its JSON representation, as found in contract class files.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        Ok(Self {
+            items: serde_json::from_str(json)?,
+        })
+    }
+
+    /// Return the ABI of a contract class, if it has one.
+    pub fn from_contract_class(contract_class: &ContractClass) -> Result<Option<Self>, Error> {
+        contract_class
+            .abi
+            .as_ref()
+            .map(|abi| {
+                Ok(Self {
+                    items: serde_json::from_value(serde_json::to_value(abi)?)?,
+                })
+            })
+            .transpose()
+    }
+
+    /// Return the top level items of the ABI.
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    /// Encode the arguments of an entry point (including the constructor and L1 handlers).
+    pub fn encode_calldata(&self, function: &str, args: &[AbiValue]) -> Result<Vec<Felt>, Error> {
+        let (inputs, _) = self.find_function(function)?;
+        if inputs.len() != args.len() {
+            return Err(Error::AbiMismatch(format!(
+                "'{function}' takes {} arguments but {} were given",
+                inputs.len(),
+                args.len(),
+            )));
+        }
+
+        let mut calldata = Vec::new();
+        for (input, arg) in inputs.iter().zip(args) {
+            self.encode(&input.ty, arg, &mut calldata)?;
+        }
+
+        Ok(calldata)
+    }
+
+    /// Decode the arguments of an entry point by name.
+    pub fn decode_calldata(
+        &self,
+        function: &str,
+        calldata: &[Felt],
+    ) -> Result<Vec<(String, AbiValue)>, Error> {
+        let (inputs, _) = self.find_function(function)?;
+
+        let mut data = calldata;
+        let args = inputs
+            .iter()
+            .map(|input| Ok((input.name.clone(), self.decode(&input.ty, &mut data)?)))
+            .collect::<Result<_, Error>>()?;
+        expect_consumed(data)?;
+
+        Ok(args)
+    }
+
+    /// Decode the return values of an entry point.
+    pub fn decode_output(&self, function: &str, output: &[Felt]) -> Result<Vec<AbiValue>, Error> {
+        let (_, outputs) = self.find_function(function)?;
+
+        let mut data = output;
+        let values = outputs
+            .iter()
+            .map(|output| self.decode(&output.ty, &mut data))
+            .collect::<Result<_, _>>()?;
+        expect_consumed(data)?;
+
+        Ok(values)
+    }
+
+    /// Encode a value of the given ABI type, appending it to `output`.
+    pub fn encode(&self, ty: &str, value: &AbiValue, output: &mut Vec<Felt>) -> Result<(), Error> {
+        let mismatch = || Error::UnexpectedValue(ty.to_string());
+
+        match (TypeName::parse(ty), value) {
+            (TypeName::Felt, AbiValue::Felt(value)) => output.push(*value),
+            (TypeName::Bool, AbiValue::Bool(value)) => output.push(Felt::from(*value as u8)),
+            (TypeName::Uint(bits), AbiValue::Uint(value)) => {
+                if *value > u128::MAX >> (128 - bits) {
+                    return Err(mismatch());
+                }
+                output.push(Felt::from(*value));
+            }
+            (TypeName::Int(bits), AbiValue::Int(value)) => {
+                if *value < i128::MIN >> (128 - bits) || *value > i128::MAX >> (128 - bits) {
+                    return Err(mismatch());
+                }
+                let abs = Felt::from(value.unsigned_abs());
+                output.push(if *value < 0 { -abs } else { abs });
+            }
+            (TypeName::U256, AbiValue::U256(value)) => {
+                output.extend([Felt::from(value.lo), Felt::from(value.hi)]);
+            }
+            (TypeName::ByteArray, AbiValue::ByteArray(value)) => {
+                let words = value.chunks_exact(31);
+                let pending_word = words.remainder();
+
+                output.push(Felt::from(words.len()));
+                output.extend(words.map(Felt::from_bytes_be_slice));
+                output.extend([
+                    Felt::from_bytes_be_slice(pending_word),
+                    Felt::from(pending_word.len()),
+                ]);
+            }
+            (TypeName::Array(element_ty), AbiValue::Array(values)) => {
+                output.push(Felt::from(values.len()));
+                for value in values {
+                    self.encode(element_ty, value, output)?;
+                }
+            }
+            (TypeName::Tuple(element_tys), AbiValue::Tuple(values)) => {
+                if element_tys.len() != values.len() {
+                    return Err(mismatch());
+                }
+                for (element_ty, value) in element_tys.iter().zip(values) {
+                    self.encode(element_ty, value, output)?;
+                }
+            }
+            (TypeName::Named(name), AbiValue::Struct(values)) => {
+                let members = self.find_struct(name)?;
+                if members.len() != values.len()
+                    || members.iter().zip(values).any(|(x, (y, _))| x.name != *y)
+                {
+                    return Err(mismatch());
+                }
+                for (member, (_, value)) in members.iter().zip(values) {
+                    self.encode(&member.ty, value, output)?;
+                }
+            }
+            (TypeName::Named(name), AbiValue::Enum { variant, value }) => {
+                let variants = self.find_enum(name)?;
+                let (index, variant) = variants
+                    .iter()
+                    .enumerate()
+                    .find(|(_, x)| x.name == *variant)
+                    .ok_or_else(mismatch)?;
+
+                output.push(Felt::from(index));
+                self.encode(&variant.ty, value, output)?;
+            }
+            _ => return Err(mismatch()),
+        }
+
+        Ok(())
+    }
+
+    /// Decode a value of the given ABI type from the start of `data`, advancing it past the value.
+    pub fn decode(&self, ty: &str, data: &mut &[Felt]) -> Result<AbiValue, Error> {
+        let mismatch = || Error::UnexpectedValue(ty.to_string());
+
+        Ok(match TypeName::parse(ty) {
+            TypeName::Felt => AbiValue::Felt(take_felt(ty, data)?),
+            TypeName::Bool => {
+                let value = take_felt(ty, data)?;
+                if value == Felt::ZERO {
+                    AbiValue::Bool(false)
+                } else if value == Felt::ONE {
+                    AbiValue::Bool(true)
+                } else {
+                    return Err(mismatch());
+                }
+            }
+            TypeName::Uint(bits) => {
+                let value = take_felt(ty, data)?.to_biguint().to_u128();
+                match value {
+                    Some(value) if value <= u128::MAX >> (128 - bits) => AbiValue::Uint(value),
+                    _ => return Err(mismatch()),
+                }
+            }
+            TypeName::Int(bits) => {
+                let value = take_felt(ty, data)?;
+                let value = match value.to_biguint().to_i128() {
+                    Some(value) => Some(value),
+                    None => (-value)
+                        .to_biguint()
+                        .to_u128()
+                        .and_then(|value| 0i128.checked_sub_unsigned(value)),
+                };
+                match value {
+                    Some(value)
+                        if value >= i128::MIN >> (128 - bits)
+                            && value <= i128::MAX >> (128 - bits) =>
+                    {
+                        AbiValue::Int(value)
+                    }
+                    _ => return Err(mismatch()),
+                }
+            }
+            TypeName::U256 => {
+                let lo = take_felt(ty, data)?.to_biguint().to_u128();
+                let hi = take_felt(ty, data)?.to_biguint().to_u128();
+                match (lo, hi) {
+                    (Some(lo), Some(hi)) => AbiValue::U256(U256 { lo, hi }),
+                    _ => return Err(mismatch()),
+                }
+            }
+            TypeName::ByteArray => {
+                let len = take_felt(ty, data)?
+                    .to_biguint()
+                    .to_usize()
+                    .filter(|len| *len <= data.len())
+                    .ok_or_else(mismatch)?;
+
+                let mut bytes = Vec::new();
+                for _ in 0..len {
+                    let word = take_felt(ty, data)?.to_bytes_be();
+                    if word[0] != 0 {
+                        return Err(mismatch());
+                    }
+                    bytes.extend_from_slice(&word[1..]);
+                }
+
+                let pending_word = take_felt(ty, data)?.to_bytes_be();
+                let pending_word_len = take_felt(ty, data)?
+                    .to_biguint()
+                    .to_usize()
+                    .filter(|len| *len < 31)
+                    .ok_or_else(mismatch)?;
+                let (padding, pending_word) = pending_word.split_at(32 - pending_word_len);
+                if padding.iter().any(|x| *x != 0) {
+                    return Err(mismatch());
+                }
+                bytes.extend_from_slice(pending_word);
+
+                AbiValue::ByteArray(bytes)
+            }
+            TypeName::Array(element_ty) => {
+                let len = take_felt(ty, data)?
+                    .to_biguint()
+                    .to_usize()
+                    .filter(|len| *len <= data.len())
+                    .ok_or_else(mismatch)?;
+                AbiValue::Array(
+                    (0..len)
+                        .map(|_| self.decode(element_ty, data))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            TypeName::Tuple(element_tys) => AbiValue::Tuple(
+                element_tys
+                    .iter()
+                    .map(|element_ty| self.decode(element_ty, data))
+                    .collect::<Result<_, _>>()?,
+            ),
+            TypeName::Named(name) => match self.find_struct(name) {
+                Ok(members) => AbiValue::Struct(
+                    members
+                        .iter()
+                        .map(|member| Ok((member.name.clone(), self.decode(&member.ty, data)?)))
+                        .collect::<Result<_, Error>>()?,
+                ),
+                Err(_) => {
+                    let variants = self.find_enum(name)?;
+                    let variant = take_felt(ty, data)?
+                        .to_biguint()
+                        .to_usize()
+                        .and_then(|index| variants.get(index))
+                        .ok_or_else(mismatch)?;
+
+                    AbiValue::Enum {
+                        variant: variant.name.clone(),
+                        value: Box::new(self.decode(&variant.ty, data)?),
+                    }
+                }
+            },
+        })
+    }
+
+    /// Decode an event emitted by the contract, as its event enum.
+    pub fn decode_event(&self, keys: &[Felt], data: &[Felt]) -> Result<AbiValue, Error> {
+        self.decode_event_as(self.find_root_event()?, keys, data)
+    }
+
+    /// Decode an event of the given event type.
+    pub fn decode_event_as(
+        &self,
+        ty: &str,
+        keys: &[Felt],
+        data: &[Felt],
+    ) -> Result<AbiValue, Error> {
+        let (mut keys, mut data) = (keys, data);
+        let value = self.decode_event_fields(ty, &mut keys, &mut data)?;
+        expect_consumed(keys)?;
+        expect_consumed(data)?;
+
+        Ok(value)
+    }
+
+    /// Decode the payload of a message sent to L1 as a value of the given ABI type.
+    pub fn decode_l2_to_l1_message(
+        &self,
+        ty: &str,
+        message: &L2ToL1Message,
+    ) -> Result<AbiValue, Error> {
+        let mut data = message.payload.as_slice();
+        let value = self.decode(ty, &mut data)?;
+        expect_consumed(data)?;
+
+        Ok(value)
+    }
+
+    fn decode_event_fields(
+        &self,
+        ty: &str,
+        keys: &mut &[Felt],
+        data: &mut &[Felt],
+    ) -> Result<AbiValue, Error> {
+        match &self.find_event(ty)?.kind {
+            EventKind::Struct { members } => Ok(AbiValue::Struct(
+                members
+                    .iter()
+                    .map(|member| {
+                        let value = match member.kind {
+                            EventFieldKind::KeySerde => self.decode(&member.ty, keys)?,
+                            EventFieldKind::DataSerde => self.decode(&member.ty, data)?,
+                            EventFieldKind::Nested | EventFieldKind::Flat => {
+                                self.decode_event_fields(&member.ty, keys, data)?
+                            }
+                        };
+                        Ok((member.name.clone(), value))
+                    })
+                    .collect::<Result<_, Error>>()?,
+            )),
+            EventKind::Enum { variants } => {
+                for variant in variants {
+                    // Flat variants don't have a selector, so they match if their own variants do.
+                    let value = if variant.kind == EventFieldKind::Flat {
+                        let (mut variant_keys, mut variant_data) = (*keys, *data);
+                        match self.decode_event_fields(
+                            &variant.ty,
+                            &mut variant_keys,
+                            &mut variant_data,
+                        ) {
+                            Ok(value) => {
+                                (*keys, *data) = (variant_keys, variant_data);
+                                value
+                            }
+                            Err(_) => continue,
+                        }
+                    } else {
+                        match keys.split_first() {
+                            Some((key, rest)) if *key == event_selector(&variant.name) => {
+                                *keys = rest;
+                                self.decode_event_fields(&variant.ty, keys, data)?
+                            }
+                            _ => continue,
+                        }
+                    };
+
+                    return Ok(AbiValue::Enum {
+                        variant: variant.name.clone(),
+                        value: Box::new(value),
+                    });
+                }
+
+                Err(Error::AbiMismatch(format!(
+                    "the keys don't match any variant of the event '{ty}'"
+                )))
+            }
+        }
+    }
+
+    /// Find the contract's event enum, which is the only one not used by other events.
+    fn find_root_event(&self) -> Result<&str, Error> {
+        let events = self.items.iter().filter_map(|item| match item {
+            Item::Event(event) => Some(event),
+            _ => None,
+        });
+        let nested = events
+            .clone()
+            .flat_map(|event| match &event.kind {
+                EventKind::Struct { members } => members,
+                EventKind::Enum { variants } => variants,
+            })
+            .map(|field| field.ty.as_str())
+            .collect::<HashSet<_>>();
+
+        events
+            .filter(|event| matches!(event.kind, EventKind::Enum { .. }))
+            .map(|event| event.name.as_str())
+            .find(|name| !nested.contains(name))
+            .ok_or_else(|| Error::UnknownAbiItem("Event".to_string()))
+    }
+
+    fn find_event(&self, name: &str) -> Result<&Event, Error> {
+        self.items
+            .iter()
+            .find_map(|item| match item {
+                Item::Event(x) if x.name == name => Some(x),
+                _ => None,
+            })
+            .ok_or_else(|| Error::UnknownAbiItem(name.to_string()))
+    }
+
+    /// Find a function, constructor or L1 handler by name, including those of the interfaces.
+    fn find_function(&self, name: &str) -> Result<(&[Input], &[Output]), Error> {
+        self.items
+            .iter()
+            .flat_map(|item| match item {
+                Item::Interface(interface) => interface.items.iter().collect::<Vec<_>>(),
+                item => vec![item],
+            })
+            .find_map(|item| match item {
+                Item::Function(function) if function.name == name => {
+                    Some((function.inputs.as_slice(), function.outputs.as_slice()))
+                }
+                Item::Constructor(constructor) if constructor.name == name => {
+                    Some((constructor.inputs.as_slice(), [].as_slice()))
+                }
+                Item::L1Handler(l1_handler) if l1_handler.name == name => {
+                    Some((l1_handler.inputs.as_slice(), l1_handler.outputs.as_slice()))
+                }
+                _ => None,
+            })
+            .ok_or_else(|| Error::UnknownAbiItem(name.to_string()))
+    }
+
+    fn find_struct(&self, name: &str) -> Result<&[StructMember], Error> {
+        self.items
+            .iter()
+            .find_map(|item| match item {
+                Item::Struct(x) if x.name == name => Some(x.members.as_slice()),
+                _ => None,
+            })
+            .ok_or_else(|| Error::UnknownAbiItem(name.to_string()))
+    }
+
+    fn find_enum(&self, name: &str) -> Result<&[EnumVariant], Error> {
+        self.items
+            .iter()
+            .find_map(|item| match item {
+                Item::Enum(x) if x.name == name => Some(x.variants.as_slice()),
+                _ => None,
+            })
+            .ok_or_else(|| Error::UnknownAbiItem(name.to_string()))
+    }
+}
+
+/// An ABI type name, classified by how it's serialized.
+#[derive(Debug, Eq, PartialEq)]
+enum TypeName<'a> {
+    Felt,
+    Bool,
+    Uint(u32),
+    Int(u32),
+    U256,
+    ByteArray,
+    Array(&'a str),
+    Tuple(Vec<&'a str>),
+    Named(&'a str),
+}
+
+impl<'a> TypeName<'a> {
+    fn parse(ty: &'a str) -> Self {
+        match ty {
+            "core::felt252"
+            | "core::bytes_31::bytes31"
+            | "core::starknet::contract_address::ContractAddress"
+            | "core::starknet::class_hash::ClassHash"
+            | "core::starknet::storage_access::StorageAddress" => return Self::Felt,
+            "core::bool" => return Self::Bool,
+            "core::integer::u256" => return Self::U256,
+            "core::byte_array::ByteArray" => return Self::ByteArray,
+            _ => {}
+        }
+
+        if let Some(bits) = ty.strip_prefix("core::integer::u") {
+            if let Some(bits) = parse_integer_width(bits) {
+                return Self::Uint(bits);
+            }
+        }
+        if let Some(bits) = ty.strip_prefix("core::integer::i") {
+            if let Some(bits) = parse_integer_width(bits) {
+                return Self::Int(bits);
+            }
+        }
+
+        if let Some(element_ty) = ["core::array::Array::<", "core::array::Span::<"]
+            .iter()
+            .find_map(|prefix| ty.strip_prefix(prefix)?.strip_suffix('>'))
+        {
+            return Self::Array(element_ty);
+        }
+
+        if let Some(inner) = ty.strip_prefix('(').and_then(|x| x.strip_suffix(')')) {
+            return Self::Tuple(split_tuple(inner));
+        }
+
+        Self::Named(ty)
+    }
+}
+
+fn parse_integer_width(bits: &str) -> Option<u32> {
+    bits.parse()
+        .ok()
+        .filter(|bits| [8, 16, 32, 64, 128].contains(bits))
+}
+
+/// Split the element types of a tuple, ignoring the commas of nested types.
+fn split_tuple(inner: &str) -> Vec<&str> {
+    let mut elements = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, c) in inner.char_indices() {
+        match c {
+            '(' | '<' => depth += 1,
+            ')' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                elements.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    let last = inner[start..].trim();
+    if !last.is_empty() {
+        elements.push(last);
+    }
+
+    elements
+}
+
+/// The selector of an event enum variant, which is the `starknet_keccak` of its name.
+fn event_selector(name: &str) -> Felt {
+    Felt::from_bytes_be_slice(&starknet_keccak(name.as_bytes()).to_bytes_be())
+}
+
+fn take_felt(ty: &str, data: &mut &[Felt]) -> Result<Felt, Error> {
+    let (value, rest) = data
+        .split_first()
+        .ok_or_else(|| Error::AbiMismatch(format!("missing data for a value of type '{ty}'")))?;
+
+    *data = rest;
+    Ok(*value)
+}
+
+fn expect_consumed(data: &[Felt]) -> Result<(), Error> {
+    match data.len() {
+        0 => Ok(()),
+        len => Err(Error::AbiMismatch(format!(
+            "{len} felts were left after decoding"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ABI: &str = r#"[
+        {
+            "type": "struct",
+            "name": "test::Point",
+            "members": [
+                { "name": "x", "type": "core::integer::i32" },
+                { "name": "y", "type": "core::integer::i32" }
+            ]
+        },
+        {
+            "type": "enum",
+            "name": "core::option::Option::<core::integer::u256>",
+            "variants": [
+                { "name": "Some", "type": "core::integer::u256" },
+                { "name": "None", "type": "()" }
+            ]
+        },
+        {
+            "type": "interface",
+            "name": "test::ITest",
+            "items": [
+                {
+                    "type": "function",
+                    "name": "move_points",
+                    "inputs": [
+                        { "name": "points", "type": "core::array::Span::<test::Point>" },
+                        { "name": "offset", "type": "(core::integer::i32, core::integer::i32)" }
+                    ],
+                    "outputs": [
+                        { "type": "core::option::Option::<core::integer::u256>" }
+                    ],
+                    "state_mutability": "external"
+                }
+            ]
+        },
+        {
+            "type": "constructor",
+            "name": "constructor",
+            "inputs": [
+                { "name": "owner", "type": "core::starknet::contract_address::ContractAddress" },
+                { "name": "enabled", "type": "core::bool" }
+            ]
+        }
+    ]"#;
+
+    fn point(x: i128, y: i128) -> AbiValue {
+        AbiValue::Struct(vec![
+            ("x".to_string(), AbiValue::Int(x)),
+            ("y".to_string(), AbiValue::Int(y)),
+        ])
+    }
+
+    #[test]
+    fn split_tuple_types() {
+        assert_eq!(split_tuple(""), Vec::<&str>::new());
+        assert_eq!(
+            split_tuple("core::felt252, (core::bool, core::integer::u8), core::integer::u32"),
+            [
+                "core::felt252",
+                "(core::bool, core::integer::u8)",
+                "core::integer::u32"
+            ],
+        );
+        assert_eq!(
+            TypeName::parse("core::array::Array::<(core::felt252, core::bool)>"),
+            TypeName::Array("(core::felt252, core::bool)"),
+        );
+    }
+
+    #[test]
+    fn calldata_roundtrip() {
+        let abi = ContractAbi::from_json(ABI).unwrap();
+
+        let args = [
+            AbiValue::Array(vec![point(1, -2), point(-3, 4)]),
+            AbiValue::Tuple(vec![AbiValue::Int(-1), AbiValue::Int(1)]),
+        ];
+        let calldata = abi.encode_calldata("move_points", &args).unwrap();
+        assert_eq!(
+            calldata,
+            [
+                Felt::from(2),
+                Felt::from(1),
+                -Felt::from(2),
+                -Felt::from(3),
+                Felt::from(4),
+                -Felt::ONE,
+                Felt::ONE,
+            ],
+        );
+
+        assert_eq!(
+            abi.decode_calldata("move_points", &calldata).unwrap(),
+            [
+                ("points".to_string(), args[0].clone()),
+                ("offset".to_string(), args[1].clone()),
+            ],
+        );
+    }
+
+    #[test]
+    fn constructor_calldata() {
+        let abi = ContractAbi::from_json(ABI).unwrap();
+
+        let args = [AbiValue::Felt(Felt::from(0x1234)), AbiValue::Bool(true)];
+        assert_eq!(
+            abi.encode_calldata("constructor", &args).unwrap(),
+            [Felt::from(0x1234), Felt::ONE],
+        );
+    }
+
+    #[test]
+    fn decode_enum_output() {
+        let abi = ContractAbi::from_json(ABI).unwrap();
+
+        assert_eq!(
+            abi.decode_output("move_points", &[Felt::ZERO, Felt::from(5), Felt::ZERO])
+                .unwrap(),
+            [AbiValue::Enum {
+                variant: "Some".to_string(),
+                value: Box::new(AbiValue::U256(U256 { lo: 5, hi: 0 })),
+            }],
+        );
+        assert_eq!(
+            abi.decode_output("move_points", &[Felt::ONE]).unwrap(),
+            [AbiValue::Enum {
+                variant: "None".to_string(),
+                value: Box::new(AbiValue::Tuple(vec![])),
+            }],
+        );
+    }
+
+    const EVENTS_ABI: &str = r#"[
+        {
+            "type": "event",
+            "name": "test::Contract::Transfer",
+            "kind": "struct",
+            "members": [
+                {
+                    "name": "from",
+                    "type": "core::starknet::contract_address::ContractAddress",
+                    "kind": "key"
+                },
+                {
+                    "name": "to",
+                    "type": "core::starknet::contract_address::ContractAddress",
+                    "kind": "key"
+                },
+                { "name": "memo", "type": "core::byte_array::ByteArray", "kind": "data" }
+            ]
+        },
+        {
+            "type": "event",
+            "name": "test::ownable::OwnershipTransferred",
+            "kind": "struct",
+            "members": [
+                {
+                    "name": "owner",
+                    "type": "core::starknet::contract_address::ContractAddress",
+                    "kind": "data"
+                }
+            ]
+        },
+        {
+            "type": "event",
+            "name": "test::ownable::Event",
+            "kind": "enum",
+            "variants": [
+                {
+                    "name": "OwnershipTransferred",
+                    "type": "test::ownable::OwnershipTransferred",
+                    "kind": "nested"
+                }
+            ]
+        },
+        {
+            "type": "event",
+            "name": "test::Contract::Event",
+            "kind": "enum",
+            "variants": [
+                { "name": "Transfer", "type": "test::Contract::Transfer", "kind": "nested" },
+                { "name": "OwnableEvent", "type": "test::ownable::Event", "kind": "flat" }
+            ]
+        }
+    ]"#;
+
+    #[test]
+    fn byte_array_roundtrip() {
+        let abi = ContractAbi::default();
+        let ty = "core::byte_array::ByteArray";
+
+        let value = AbiValue::ByteArray(vec![b'a'; 40]);
+        let mut data = Vec::new();
+        abi.encode(ty, &value, &mut data).unwrap();
+        assert_eq!(
+            data,
+            [
+                Felt::ONE,
+                Felt::from_bytes_be_slice(&[b'a'; 31]),
+                Felt::from_bytes_be_slice(&[b'a'; 9]),
+                Felt::from(9),
+            ],
+        );
+        assert_eq!(abi.decode(ty, &mut data.as_slice()).unwrap(), value);
+
+        let mut data = Vec::new();
+        abi.encode(ty, &AbiValue::ByteArray(Vec::new()), &mut data)
+            .unwrap();
+        assert_eq!(data, [Felt::ZERO; 3]);
+
+        // The pending word must fit in its length.
+        assert!(matches!(
+            abi.decode(
+                ty,
+                &mut [Felt::ZERO, Felt::from(0x100), Felt::ONE].as_slice()
+            ),
+            Err(Error::UnexpectedValue(_)),
+        ));
+    }
+
+    #[test]
+    fn decode_nested_and_flat_events() {
+        let abi = ContractAbi::from_json(EVENTS_ABI).unwrap();
+
+        let mut memo = Vec::new();
+        abi.encode(
+            "core::byte_array::ByteArray",
+            &AbiValue::ByteArray(b"thanks".to_vec()),
+            &mut memo,
+        )
+        .unwrap();
+        assert_eq!(
+            abi.decode_event(
+                &[event_selector("Transfer"), Felt::ONE, Felt::from(2)],
+                &memo
+            )
+            .unwrap(),
+            AbiValue::Enum {
+                variant: "Transfer".to_string(),
+                value: Box::new(AbiValue::Struct(vec![
+                    ("from".to_string(), AbiValue::Felt(Felt::ONE)),
+                    ("to".to_string(), AbiValue::Felt(Felt::from(2))),
+                    ("memo".to_string(), AbiValue::ByteArray(b"thanks".to_vec())),
+                ])),
+            },
+        );
+
+        assert_eq!(
+            abi.decode_event(&[event_selector("OwnershipTransferred")], &[Felt::from(3)])
+                .unwrap(),
+            AbiValue::Enum {
+                variant: "OwnableEvent".to_string(),
+                value: Box::new(AbiValue::Enum {
+                    variant: "OwnershipTransferred".to_string(),
+                    value: Box::new(AbiValue::Struct(vec![(
+                        "owner".to_string(),
+                        AbiValue::Felt(Felt::from(3)),
+                    )])),
+                }),
+            },
+        );
+
+        assert!(matches!(
+            abi.decode_event(&[event_selector("Approval")], &[]),
+            Err(Error::AbiMismatch(_)),
+        ));
+        assert!(matches!(
+            ContractAbi::default().decode_event(&[], &[]),
+            Err(Error::UnknownAbiItem(_)),
+        ));
+    }
+
+    #[test]
+    fn decode_message_payload() {
+        let abi = ContractAbi::from_json(ABI).unwrap();
+        let mut message = L2ToL1Message {
+            to_address: Felt::ONE,
+            payload: vec![Felt::from(5), -Felt::ONE],
+        };
+
+        assert_eq!(
+            abi.decode_l2_to_l1_message("test::Point", &message)
+                .unwrap(),
+            point(5, -1),
+        );
+
+        message.payload.push(Felt::ZERO);
+        assert!(matches!(
+            abi.decode_l2_to_l1_message("test::Point", &message),
+            Err(Error::AbiMismatch(_)),
+        ));
+    }
+
+    #[test]
+    fn invalid_values() {
+        let abi = ContractAbi::from_json(ABI).unwrap();
+
+        assert!(matches!(
+            abi.encode_calldata("unknown", &[]),
+            Err(Error::UnknownAbiItem(_)),
+        ));
+        assert!(matches!(
+            abi.encode("core::integer::u8", &AbiValue::Uint(256), &mut Vec::new()),
+            Err(Error::UnexpectedValue(_)),
+        ));
+        assert!(matches!(
+            abi.decode_calldata("constructor", &[Felt::ONE, Felt::from(2)]),
+            Err(Error::UnexpectedValue(_)),
+        ));
+        assert!(matches!(
+            abi.decode_output("move_points", &[Felt::ONE, Felt::ONE]),
+            Err(Error::AbiMismatch(_)),
+        ));
+    }
+}
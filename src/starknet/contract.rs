@@ -0,0 +1,116 @@
+//! # Contract classes
+//!
+//! A [`ContractClass`] holds its Sierra program serialized as felts, the program's debug info and
+//! the function indices of its entry points. This module extracts them into the types used by the
+//! rest of the crate, resolving the function of every entry point.
+
+use crate::{
+    error::Error, module::NativeModule, sierra_version::extract_contract_class_program,
+    utils::find_entry_point_by_idx,
+};
+use cairo_lang_sierra::{ids::FunctionId, program::Program};
+use cairo_lang_starknet::{
+    compiler_version::VersionId,
+    contract_class::{ContractClass, ContractEntryPoint},
+};
+use starknet_types_core::felt::Felt;
+
+/// An entry point of a contract class.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct EntryPoint {
+    pub selector: Felt,
+    pub function_id: FunctionId,
+}
+
+impl EntryPoint {
+    fn extract(program: &Program, entry_point: &ContractEntryPoint) -> Result<Self, Error> {
+        let function = find_entry_point_by_idx(program, entry_point.function_idx)
+            .ok_or(Error::MissingEntryPoint(entry_point.function_idx))?;
+
+        Ok(Self {
+            selector: Felt::from_bytes_be_slice(&entry_point.selector.to_bytes_be()),
+            function_id: function.id.clone(),
+        })
+    }
+}
+
+/// The entry points of a contract class, by type.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EntryPoints {
+    pub constructor: Option<EntryPoint>,
+    pub external: Vec<EntryPoint>,
+    pub l1_handler: Vec<EntryPoint>,
+}
+
+impl EntryPoints {
+    /// Return the function of the external entry point with the given selector.
+    pub fn external(&self, selector: Felt) -> Option<&FunctionId> {
+        find_selector(&self.external, selector)
+    }
+
+    /// Return the function of the L1 handler with the given selector.
+    pub fn l1_handler(&self, selector: Felt) -> Option<&FunctionId> {
+        find_selector(&self.l1_handler, selector)
+    }
+}
+
+/// The Sierra program of a contract class, along with its version and entry points.
+#[derive(Clone, Debug)]
+pub struct ContractClassProgram {
+    pub sierra_version: VersionId,
+    pub program: Program,
+    pub entry_points: EntryPoints,
+}
+
+impl ContractClassProgram {
+    /// Extract the program and entry points of a contract class.
+    ///
+    /// If the class has debug info, its names are assigned to the program's types, libfuncs and
+    /// functions.
+    pub fn extract(contract_class: &ContractClass) -> Result<Self, Error> {
+        let (sierra_version, _, mut program) = extract_contract_class_program(contract_class)?;
+        if let Some(debug_info) = &contract_class.sierra_program_debug_info {
+            debug_info.populate(&mut program);
+        }
+
+        let entry_points = &contract_class.entry_points_by_type;
+        let entry_points = EntryPoints {
+            constructor: entry_points
+                .constructor
+                .first()
+                .map(|entry_point| EntryPoint::extract(&program, entry_point))
+                .transpose()?,
+            external: entry_points
+                .external
+                .iter()
+                .map(|entry_point| EntryPoint::extract(&program, entry_point))
+                .collect::<Result<_, _>>()?,
+            l1_handler: entry_points
+                .l1_handler
+                .iter()
+                .map(|entry_point| EntryPoint::extract(&program, entry_point))
+                .collect::<Result<_, _>>()?,
+        };
+
+        Ok(Self {
+            sierra_version,
+            program,
+            entry_points,
+        })
+    }
+}
+
+/// A natively compiled contract class, returned by
+/// [`compile_contract_class`](crate::context::NativeContext::compile_contract_class).
+#[derive(Debug)]
+pub struct NativeContractClass<'m> {
+    pub module: NativeModule<'m>,
+    pub entry_points: EntryPoints,
+}
+
+fn find_selector(entry_points: &[EntryPoint], selector: Felt) -> Option<&FunctionId> {
+    entry_points
+        .iter()
+        .find(|entry_point| entry_point.selector == selector)
+        .map(|entry_point| &entry_point.function_id)
+}
@@ -0,0 +1,77 @@
+//! # Syscall base gas costs
+//!
+//! Every syscall has a fixed base cost which is charged before its handler is called. The
+//! handlers only have to charge the dynamic part of the cost, like the keccak rounds.
+
+const STEP: u128 = 100;
+const RANGE_CHECK: u128 = 70;
+const BITWISE: u128 = 594;
+
+const ENTRY_POINT_INITIAL_BUDGET: u128 = 100 * STEP;
+const ENTRY_POINT: u128 = ENTRY_POINT_INITIAL_BUDGET + 500 * STEP;
+
+/// The base gas cost of every syscall.
+///
+/// The default values are the same as in `cairo-lang-runner`, which duplicates the
+/// `SYSCALL_BASE_GAS_COST` table of the blockifier minus the amount already charged by the
+/// compiled code. They can be overridden through
+/// [`StarknetSyscallHandler::syscall_gas_costs`](super::StarknetSyscallHandler::syscall_gas_costs).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyscallGasCosts {
+    pub call_contract: u128,
+    pub deploy: u128,
+    pub emit_event: u128,
+    pub get_block_hash: u128,
+    pub get_class_hash_at: u128,
+    pub get_execution_info: u128,
+    pub library_call: u128,
+    pub meta_tx_v0: u128,
+    pub replace_class: u128,
+    pub send_message_to_l1: u128,
+    pub storage_read: u128,
+    pub storage_write: u128,
+    pub keccak: u128,
+    pub sha256_process_block: u128,
+    pub secp256k1_new: u128,
+    pub secp256k1_add: u128,
+    pub secp256k1_mul: u128,
+    pub secp256k1_get_point_from_x: u128,
+    pub secp256k1_get_xy: u128,
+    pub secp256r1_new: u128,
+    pub secp256r1_add: u128,
+    pub secp256r1_mul: u128,
+    pub secp256r1_get_point_from_x: u128,
+    pub secp256r1_get_xy: u128,
+}
+
+impl Default for SyscallGasCosts {
+    fn default() -> Self {
+        Self {
+            call_contract: 10 * STEP + ENTRY_POINT,
+            deploy: 200 * STEP + ENTRY_POINT,
+            emit_event: 10 * STEP,
+            get_block_hash: 50 * STEP,
+            get_class_hash_at: 50 * STEP,
+            get_execution_info: 10 * STEP,
+            library_call: 10 * STEP + ENTRY_POINT,
+            meta_tx_v0: 10 * STEP + ENTRY_POINT,
+            replace_class: 50 * STEP,
+            send_message_to_l1: 50 * STEP,
+            storage_read: 50 * STEP,
+            storage_write: 50 * STEP,
+            keccak: 0,
+            sha256_process_block: 1852 * STEP + 65 * RANGE_CHECK + 1115 * BITWISE,
+            secp256k1_new: 340 * STEP + 36 * RANGE_CHECK,
+            secp256k1_add: 254 * STEP + 29 * RANGE_CHECK,
+            secp256k1_mul: 121810 * STEP + 10739 * RANGE_CHECK,
+            secp256k1_get_point_from_x: 260 * STEP + 29 * RANGE_CHECK,
+            secp256k1_get_xy: 24 * STEP + 9 * RANGE_CHECK,
+            secp256r1_new: 340 * STEP + 36 * RANGE_CHECK,
+            secp256r1_add: 254 * STEP + 29 * RANGE_CHECK,
+            secp256r1_mul: 121810 * STEP + 10739 * RANGE_CHECK,
+            secp256r1_get_point_from_x: 260 * STEP + 29 * RANGE_CHECK,
+            secp256r1_get_xy: 24 * STEP + 9 * RANGE_CHECK,
+        }
+    }
+}
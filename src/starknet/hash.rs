@@ -0,0 +1,146 @@
+//! # Class hashes and contract addresses
+//!
+//! Starknet identifies classes by their hash and contracts by an address derived from their class
+//! hash and deployment parameters. The functions in this module compute them exactly like the
+//! network does, so handlers implementing `deploy` can assign the same addresses.
+
+use cairo_lang_starknet::{
+    casm_contract_class::CasmContractClass,
+    contract::starknet_keccak,
+    contract_class::{ContractClass, ContractEntryPoint},
+};
+use num_bigint::BigUint;
+use starknet_types_core::{
+    felt::Felt,
+    hash::{Pedersen, Poseidon, StarkHash},
+};
+
+/// The prefix of the contract address hash, `'STARKNET_CONTRACT_ADDRESS'`.
+const CONTRACT_ADDRESS_PREFIX: &[u8] = b"STARKNET_CONTRACT_ADDRESS";
+
+/// The prefix of the Sierra contract class version, which is hashed as `'CONTRACT_CLASS_V0.1.0'`.
+const CONTRACT_CLASS_VERSION_PREFIX: &str = "CONTRACT_CLASS_V";
+
+/// Compute the address of a contract deployed with the given parameters, like Starknet does.
+pub fn calculate_contract_address(
+    contract_address_salt: Felt,
+    class_hash: Felt,
+    constructor_calldata: &[Felt],
+    deployer_address: Felt,
+) -> Felt {
+    let hash = Pedersen::hash_array(&[
+        Felt::from_bytes_be_slice(CONTRACT_ADDRESS_PREFIX),
+        deployer_address,
+        contract_address_salt,
+        class_hash,
+        Pedersen::hash_array(constructor_calldata),
+    ]);
+
+    // Addresses are reduced to the L2 address space, `[0, 2**251 - 256)`.
+    let upper_bound = (BigUint::from(1u8) << 251u32) - 256u32;
+    let address = BigUint::from_bytes_be(&hash.to_bytes_be()) % upper_bound;
+    Felt::from_bytes_be_slice(&address.to_bytes_be())
+}
+
+/// Compute the hash of a Sierra contract class, which identifies it once declared.
+///
+/// The ABI is hashed exactly as declared (the `abi` string of the declared class), since its
+/// serialization depends on the tooling that declared it. The class's own `abi` is ignored.
+pub fn calculate_class_hash(contract_class: &ContractClass, abi: &str) -> Felt {
+    let version = format!(
+        "{CONTRACT_CLASS_VERSION_PREFIX}{}",
+        contract_class.contract_class_version
+    );
+
+    let entry_points = &contract_class.entry_points_by_type;
+    let sierra_program = contract_class
+        .sierra_program
+        .iter()
+        .map(|x| Felt::from_bytes_be_slice(&x.value.to_bytes_be()))
+        .collect::<Vec<_>>();
+
+    Poseidon::hash_array(&[
+        Felt::from_bytes_be_slice(version.as_bytes()),
+        hash_entry_points(&entry_points.external),
+        hash_entry_points(&entry_points.l1_handler),
+        hash_entry_points(&entry_points.constructor),
+        Felt::from_bytes_be_slice(&starknet_keccak(abi.as_bytes()).to_bytes_be()),
+        Poseidon::hash_array(&sierra_program),
+    ])
+}
+
+/// Compute the hash of a compiled (CASM) contract class, which is declared along with the hash of
+/// its Sierra class.
+pub fn calculate_compiled_class_hash(casm_contract_class: &CasmContractClass) -> Felt {
+    Felt::from_bytes_be_slice(&casm_contract_class.compiled_class_hash().to_bytes_be())
+}
+
+/// Hash the selectors and function indices of a class's entry points with poseidon.
+fn hash_entry_points(entry_points: &[ContractEntryPoint]) -> Felt {
+    Poseidon::hash_array(
+        &entry_points
+            .iter()
+            .flat_map(|entry_point| {
+                [
+                    Felt::from_bytes_be_slice(&entry_point.selector.to_bytes_be()),
+                    Felt::from(entry_point.function_idx),
+                ]
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn contract_address() {
+        let class_hash = Felt::from(0x1234);
+        let calldata = [Felt::from(1), Felt::from(2)];
+
+        let address = calculate_contract_address(Felt::ZERO, class_hash, &calldata, Felt::ZERO);
+
+        // The address depends on every parameter.
+        assert_ne!(
+            address,
+            calculate_contract_address(Felt::ONE, class_hash, &calldata, Felt::ZERO)
+        );
+        assert_ne!(
+            address,
+            calculate_contract_address(Felt::ZERO, class_hash, &calldata[..1], Felt::ZERO)
+        );
+        assert_ne!(
+            address,
+            calculate_contract_address(Felt::ZERO, class_hash, &calldata, Felt::ONE)
+        );
+
+        let upper_bound = (BigUint::from(1u8) << 251u32) - 256u32;
+        assert!(BigUint::from_bytes_be(&address.to_bytes_be()) < upper_bound);
+    }
+
+    #[test]
+    fn contract_address_known_answer() {
+        // The deployment checked by the tests of `cairo-lang-runner`, which computes the addresses
+        // of the contracts deployed on the VM.
+        let salt = Felt::from_dec_str("122660764594045088044512115").unwrap();
+        let deployer_address = Felt::ONE;
+        let class_hash = Felt::from_dec_str(
+            "1779576919126046589190499439779938629977579841313883525093195577363779864274",
+        )
+        .unwrap();
+
+        assert_eq!(
+            calculate_contract_address(
+                salt,
+                class_hash,
+                &[deployer_address, salt],
+                deployer_address
+            ),
+            Felt::from_dec_str(
+                "2288343933438457476985546536845198482236255384896285993520343604559094835567"
+            )
+            .unwrap()
+        );
+    }
+}
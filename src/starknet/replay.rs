@@ -0,0 +1,1296 @@
+//! # Syscall recording and replay
+//!
+//! The [`RecordingSyscallHandler`] wraps another handler and logs every syscall along with its
+//! result and the gas it consumed, as well as the handler's syscall base costs. The
+//! [`ReplaySyscallHandler`] feeds a recording back, so that an invocation can be rerun offline
+//! (without the original state) and its result compared with the recorded one.
+//!
+//! Only the syscalls made by the invoked entry point are recorded: the nested calls it makes are
+//! recorded as a whole, with their results. Cheatcodes aren't syscalls, so they are neither
+//! recorded nor replayed: the recording handler forwards them to the wrapped handler, while the
+//! replay handler ignores them.
+
+use super::{
+    ExecutionInfo, ExecutionInfoV2, Secp256k1Point, Secp256r1Point, StarknetSyscallHandler,
+    StarknetTestingHandler, SyscallGasCosts, SyscallResult, U256,
+};
+use crate::error::Error;
+use starknet_types_core::felt::Felt;
+use std::collections::VecDeque;
+
+/// A syscall, with its arguments and its result.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Syscall {
+    GetBlockHash {
+        block_number: u64,
+        result: SyscallResult<Felt>,
+    },
+    GetExecutionInfo {
+        result: SyscallResult<ExecutionInfo>,
+    },
+    GetExecutionInfoV2 {
+        result: SyscallResult<ExecutionInfoV2>,
+    },
+    Deploy {
+        class_hash: Felt,
+        contract_address_salt: Felt,
+        calldata: Vec<Felt>,
+        deploy_from_zero: bool,
+        result: SyscallResult<(Felt, Vec<Felt>)>,
+    },
+    ReplaceClass {
+        class_hash: Felt,
+        result: SyscallResult<()>,
+    },
+    LibraryCall {
+        class_hash: Felt,
+        function_selector: Felt,
+        calldata: Vec<Felt>,
+        result: SyscallResult<Vec<Felt>>,
+    },
+    CallContract {
+        address: Felt,
+        entry_point_selector: Felt,
+        calldata: Vec<Felt>,
+        result: SyscallResult<Vec<Felt>>,
+    },
+    StorageRead {
+        address_domain: u32,
+        address: Felt,
+        result: SyscallResult<Felt>,
+    },
+    StorageWrite {
+        address_domain: u32,
+        address: Felt,
+        value: Felt,
+        result: SyscallResult<()>,
+    },
+    EmitEvent {
+        keys: Vec<Felt>,
+        data: Vec<Felt>,
+        result: SyscallResult<()>,
+    },
+    SendMessageToL1 {
+        to_address: Felt,
+        payload: Vec<Felt>,
+        result: SyscallResult<()>,
+    },
+    Keccak {
+        input: Vec<u64>,
+        result: SyscallResult<U256>,
+    },
+    Secp256k1New {
+        x: U256,
+        y: U256,
+        result: SyscallResult<Option<Secp256k1Point>>,
+    },
+    Secp256k1Add {
+        p0: Secp256k1Point,
+        p1: Secp256k1Point,
+        result: SyscallResult<Secp256k1Point>,
+    },
+    Secp256k1Mul {
+        p: Secp256k1Point,
+        m: U256,
+        result: SyscallResult<Secp256k1Point>,
+    },
+    Secp256k1GetPointFromX {
+        x: U256,
+        y_parity: bool,
+        result: SyscallResult<Option<Secp256k1Point>>,
+    },
+    Secp256k1GetXy {
+        p: Secp256k1Point,
+        result: SyscallResult<(U256, U256)>,
+    },
+    Secp256r1New {
+        x: U256,
+        y: U256,
+        result: SyscallResult<Option<Secp256r1Point>>,
+    },
+    Secp256r1Add {
+        p0: Secp256r1Point,
+        p1: Secp256r1Point,
+        result: SyscallResult<Secp256r1Point>,
+    },
+    Secp256r1Mul {
+        p: Secp256r1Point,
+        m: U256,
+        result: SyscallResult<Secp256r1Point>,
+    },
+    Secp256r1GetPointFromX {
+        x: U256,
+        y_parity: bool,
+        result: SyscallResult<Option<Secp256r1Point>>,
+    },
+    Secp256r1GetXy {
+        p: Secp256r1Point,
+        result: SyscallResult<(U256, U256)>,
+    },
+    GetClassHashAt {
+        contract_address: Felt,
+        result: SyscallResult<Felt>,
+    },
+    Sha256ProcessBlock {
+        state: [u32; 8],
+        block: [u32; 16],
+        /// The state after processing the block.
+        result: SyscallResult<[u32; 8]>,
+    },
+    MetaTxV0 {
+        address: Felt,
+        entry_point_selector: Felt,
+        calldata: Vec<Felt>,
+        signature: Vec<Felt>,
+        result: SyscallResult<Vec<Felt>>,
+    },
+}
+
+/// A recorded syscall and the gas it consumed.
+///
+/// The consumed gas doesn't include the syscall's base cost, which is charged before calling the
+/// handler.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordedSyscall {
+    pub syscall: Syscall,
+    pub gas_consumed: u128,
+}
+
+/// The syscalls of an invocation, along with the syscall base costs of the handler which served
+/// them.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyscallRecording {
+    pub syscall_gas_costs: SyscallGasCosts,
+    pub syscalls: Vec<RecordedSyscall>,
+}
+
+/// A syscall handler which records every syscall handled by the wrapped handler.
+///
+/// It's lent to the executor by mutable reference, so the recording can be retrieved after the run.
+#[derive(Debug)]
+pub struct RecordingSyscallHandler<H> {
+    handler: H,
+    records: Vec<RecordedSyscall>,
+}
+
+impl<H> RecordingSyscallHandler<H>
+where
+    H: StarknetSyscallHandler,
+{
+    pub fn new(handler: H) -> Self {
+        Self {
+            handler,
+            records: Vec::new(),
+        }
+    }
+
+    /// Return the syscalls recorded so far, in order.
+    pub fn records(&self) -> &[RecordedSyscall] {
+        &self.records
+    }
+
+    /// Return the wrapped handler and the recording.
+    pub fn into_parts(self) -> (H, SyscallRecording) {
+        let recording = SyscallRecording {
+            syscall_gas_costs: self.handler.syscall_gas_costs(),
+            syscalls: self.records,
+        };
+
+        (self.handler, recording)
+    }
+
+    fn record<T>(
+        &mut self,
+        remaining_gas: &mut u128,
+        call: impl FnOnce(&mut H, &mut u128) -> SyscallResult<T>,
+        syscall: impl FnOnce(SyscallResult<T>) -> Syscall,
+    ) -> SyscallResult<T>
+    where
+        T: Clone,
+    {
+        let initial_gas = *remaining_gas;
+        let result = call(&mut self.handler, remaining_gas);
+
+        self.records.push(RecordedSyscall {
+            syscall: syscall(result.clone()),
+            gas_consumed: initial_gas.saturating_sub(*remaining_gas),
+        });
+        result
+    }
+}
+
+impl<H> StarknetSyscallHandler for RecordingSyscallHandler<H>
+where
+    H: StarknetSyscallHandler,
+{
+    fn get_block_hash(
+        &mut self,
+        block_number: u64,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Felt> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.get_block_hash(block_number, gas),
+            |result| Syscall::GetBlockHash {
+                block_number,
+                result,
+            },
+        )
+    }
+
+    fn get_execution_info(&mut self, remaining_gas: &mut u128) -> SyscallResult<ExecutionInfo> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.get_execution_info(gas),
+            |result| Syscall::GetExecutionInfo { result },
+        )
+    }
+
+    fn get_execution_info_v2(
+        &mut self,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<ExecutionInfoV2> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.get_execution_info_v2(gas),
+            |result| Syscall::GetExecutionInfoV2 { result },
+        )
+    }
+
+    fn deploy(
+        &mut self,
+        class_hash: Felt,
+        contract_address_salt: Felt,
+        calldata: &[Felt],
+        deploy_from_zero: bool,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<(Felt, Vec<Felt>)> {
+        self.record(
+            remaining_gas,
+            |handler, gas| {
+                handler.deploy(
+                    class_hash,
+                    contract_address_salt,
+                    calldata,
+                    deploy_from_zero,
+                    gas,
+                )
+            },
+            |result| Syscall::Deploy {
+                class_hash,
+                contract_address_salt,
+                calldata: calldata.to_vec(),
+                deploy_from_zero,
+                result,
+            },
+        )
+    }
+
+    fn replace_class(&mut self, class_hash: Felt, remaining_gas: &mut u128) -> SyscallResult<()> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.replace_class(class_hash, gas),
+            |result| Syscall::ReplaceClass { class_hash, result },
+        )
+    }
+
+    fn library_call(
+        &mut self,
+        class_hash: Felt,
+        function_selector: Felt,
+        calldata: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.library_call(class_hash, function_selector, calldata, gas),
+            |result| Syscall::LibraryCall {
+                class_hash,
+                function_selector,
+                calldata: calldata.to_vec(),
+                result,
+            },
+        )
+    }
+
+    fn call_contract(
+        &mut self,
+        address: Felt,
+        entry_point_selector: Felt,
+        calldata: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.call_contract(address, entry_point_selector, calldata, gas),
+            |result| Syscall::CallContract {
+                address,
+                entry_point_selector,
+                calldata: calldata.to_vec(),
+                result,
+            },
+        )
+    }
+
+    fn storage_read(
+        &mut self,
+        address_domain: u32,
+        address: Felt,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Felt> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.storage_read(address_domain, address, gas),
+            |result| Syscall::StorageRead {
+                address_domain,
+                address,
+                result,
+            },
+        )
+    }
+
+    fn storage_write(
+        &mut self,
+        address_domain: u32,
+        address: Felt,
+        value: Felt,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.storage_write(address_domain, address, value, gas),
+            |result| Syscall::StorageWrite {
+                address_domain,
+                address,
+                value,
+                result,
+            },
+        )
+    }
+
+    fn emit_event(
+        &mut self,
+        keys: &[Felt],
+        data: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.emit_event(keys, data, gas),
+            |result| Syscall::EmitEvent {
+                keys: keys.to_vec(),
+                data: data.to_vec(),
+                result,
+            },
+        )
+    }
+
+    fn send_message_to_l1(
+        &mut self,
+        to_address: Felt,
+        payload: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.send_message_to_l1(to_address, payload, gas),
+            |result| Syscall::SendMessageToL1 {
+                to_address,
+                payload: payload.to_vec(),
+                result,
+            },
+        )
+    }
+
+    fn keccak(&mut self, input: &[u64], remaining_gas: &mut u128) -> SyscallResult<U256> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.keccak(input, gas),
+            |result| Syscall::Keccak {
+                input: input.to_vec(),
+                result,
+            },
+        )
+    }
+
+    fn secp256k1_new(
+        &mut self,
+        x: U256,
+        y: U256,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256k1Point>> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.secp256k1_new(x, y, gas),
+            |result| Syscall::Secp256k1New { x, y, result },
+        )
+    }
+
+    fn secp256k1_add(
+        &mut self,
+        p0: Secp256k1Point,
+        p1: Secp256k1Point,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256k1Point> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.secp256k1_add(p0, p1, gas),
+            |result| Syscall::Secp256k1Add { p0, p1, result },
+        )
+    }
+
+    fn secp256k1_mul(
+        &mut self,
+        p: Secp256k1Point,
+        m: U256,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256k1Point> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.secp256k1_mul(p, m, gas),
+            |result| Syscall::Secp256k1Mul { p, m, result },
+        )
+    }
+
+    fn secp256k1_get_point_from_x(
+        &mut self,
+        x: U256,
+        y_parity: bool,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256k1Point>> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.secp256k1_get_point_from_x(x, y_parity, gas),
+            |result| Syscall::Secp256k1GetPointFromX {
+                x,
+                y_parity,
+                result,
+            },
+        )
+    }
+
+    fn secp256k1_get_xy(
+        &mut self,
+        p: Secp256k1Point,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<(U256, U256)> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.secp256k1_get_xy(p, gas),
+            |result| Syscall::Secp256k1GetXy { p, result },
+        )
+    }
+
+    fn secp256r1_new(
+        &mut self,
+        x: U256,
+        y: U256,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256r1Point>> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.secp256r1_new(x, y, gas),
+            |result| Syscall::Secp256r1New { x, y, result },
+        )
+    }
+
+    fn secp256r1_add(
+        &mut self,
+        p0: Secp256r1Point,
+        p1: Secp256r1Point,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256r1Point> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.secp256r1_add(p0, p1, gas),
+            |result| Syscall::Secp256r1Add { p0, p1, result },
+        )
+    }
+
+    fn secp256r1_mul(
+        &mut self,
+        p: Secp256r1Point,
+        m: U256,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256r1Point> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.secp256r1_mul(p, m, gas),
+            |result| Syscall::Secp256r1Mul { p, m, result },
+        )
+    }
+
+    fn secp256r1_get_point_from_x(
+        &mut self,
+        x: U256,
+        y_parity: bool,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256r1Point>> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.secp256r1_get_point_from_x(x, y_parity, gas),
+            |result| Syscall::Secp256r1GetPointFromX {
+                x,
+                y_parity,
+                result,
+            },
+        )
+    }
+
+    fn secp256r1_get_xy(
+        &mut self,
+        p: Secp256r1Point,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<(U256, U256)> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.secp256r1_get_xy(p, gas),
+            |result| Syscall::Secp256r1GetXy { p, result },
+        )
+    }
+
+    fn get_class_hash_at(
+        &mut self,
+        contract_address: Felt,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Felt> {
+        self.record(
+            remaining_gas,
+            |handler, gas| handler.get_class_hash_at(contract_address, gas),
+            |result| Syscall::GetClassHashAt {
+                contract_address,
+                result,
+            },
+        )
+    }
+
+    fn sha256_process_block(
+        &mut self,
+        state: &mut [u32; 8],
+        block: &[u32; 16],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        let initial_state = *state;
+        self.record(
+            remaining_gas,
+            |handler, gas| {
+                handler
+                    .sha256_process_block(state, block, gas)
+                    .map(|()| *state)
+            },
+            |result| Syscall::Sha256ProcessBlock {
+                state: initial_state,
+                block: *block,
+                result,
+            },
+        )
+        .map(|_| ())
+    }
+
+    fn meta_tx_v0(
+        &mut self,
+        address: Felt,
+        entry_point_selector: Felt,
+        calldata: &[Felt],
+        signature: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        self.record(
+            remaining_gas,
+            |handler, gas| {
+                handler.meta_tx_v0(address, entry_point_selector, calldata, signature, gas)
+            },
+            |result| Syscall::MetaTxV0 {
+                address,
+                entry_point_selector,
+                calldata: calldata.to_vec(),
+                signature: signature.to_vec(),
+                result,
+            },
+        )
+    }
+
+    fn syscall_gas_costs(&self) -> SyscallGasCosts {
+        self.handler.syscall_gas_costs()
+    }
+
+    fn begin_call_frame(&mut self) {
+        self.handler.begin_call_frame();
+    }
+
+    fn commit_call_frame(&mut self) {
+        self.handler.commit_call_frame();
+    }
+
+    fn revert_call_frame(&mut self) {
+        self.handler.revert_call_frame();
+    }
+
+    fn testing_handler(&mut self) -> Option<&mut dyn StarknetTestingHandler> {
+        self.handler.testing_handler()
+    }
+}
+
+/// A syscall handler which answers with the results of a recording.
+///
+/// Every syscall must match the next recorded one, arguments included. After the first mismatch
+/// every syscall fails, and the mismatch is returned by [`finish`](Self::finish).
+#[derive(Debug)]
+pub struct ReplaySyscallHandler {
+    records: VecDeque<RecordedSyscall>,
+    syscall_gas_costs: SyscallGasCosts,
+    mismatch: Option<String>,
+}
+
+impl ReplaySyscallHandler {
+    pub fn new(recording: SyscallRecording) -> Self {
+        Self {
+            records: recording.syscalls.into(),
+            syscall_gas_costs: recording.syscall_gas_costs,
+            mismatch: None,
+        }
+    }
+
+    /// Check that the replay followed the recording until its end.
+    pub fn finish(self) -> Result<(), Error> {
+        if let Some(mismatch) = self.mismatch {
+            return Err(Error::ReplayMismatch(mismatch));
+        }
+        if !self.records.is_empty() {
+            return Err(Error::ReplayMismatch(format!(
+                "{} recorded syscalls were not replayed",
+                self.records.len()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Return the result of the next recorded syscall if it matches, charging its gas.
+    fn replay<T>(
+        &mut self,
+        remaining_gas: &mut u128,
+        name: &str,
+        matches: impl FnOnce(&Syscall) -> Option<&SyscallResult<T>>,
+    ) -> SyscallResult<T>
+    where
+        T: Clone,
+    {
+        let Some(record) = self.next_record(name) else {
+            return Err(vec![Felt::from_bytes_be_slice(b"REPLAY_MISMATCH")]);
+        };
+
+        match matches(&record.syscall) {
+            Some(result) => {
+                *remaining_gas = remaining_gas.saturating_sub(record.gas_consumed);
+                result.clone()
+            }
+            None => {
+                self.mismatch = Some(format!("expected {:?}, got `{name}`", record.syscall));
+                Err(vec![Felt::from_bytes_be_slice(b"REPLAY_MISMATCH")])
+            }
+        }
+    }
+
+    fn next_record(&mut self, name: &str) -> Option<RecordedSyscall> {
+        if self.mismatch.is_some() {
+            return None;
+        }
+
+        let record = self.records.pop_front();
+        if record.is_none() {
+            self.mismatch = Some(format!(
+                "`{name}` was called after the end of the recording"
+            ));
+        }
+
+        record
+    }
+}
+
+impl StarknetSyscallHandler for ReplaySyscallHandler {
+    fn get_block_hash(
+        &mut self,
+        block_number: u64,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Felt> {
+        self.replay(remaining_gas, "get_block_hash", |syscall| match syscall {
+            Syscall::GetBlockHash {
+                block_number: recorded_block_number,
+                result,
+            } if *recorded_block_number == block_number => Some(result),
+            _ => None,
+        })
+    }
+
+    fn get_execution_info(&mut self, remaining_gas: &mut u128) -> SyscallResult<ExecutionInfo> {
+        self.replay(
+            remaining_gas,
+            "get_execution_info",
+            |syscall| match syscall {
+                Syscall::GetExecutionInfo { result } => Some(result),
+                _ => None,
+            },
+        )
+    }
+
+    fn get_execution_info_v2(
+        &mut self,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<ExecutionInfoV2> {
+        self.replay(
+            remaining_gas,
+            "get_execution_info_v2",
+            |syscall| match syscall {
+                Syscall::GetExecutionInfoV2 { result } => Some(result),
+                _ => None,
+            },
+        )
+    }
+
+    fn deploy(
+        &mut self,
+        class_hash: Felt,
+        contract_address_salt: Felt,
+        calldata: &[Felt],
+        deploy_from_zero: bool,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<(Felt, Vec<Felt>)> {
+        self.replay(remaining_gas, "deploy", |syscall| match syscall {
+            Syscall::Deploy {
+                class_hash: recorded_class_hash,
+                contract_address_salt: recorded_contract_address_salt,
+                calldata: recorded_calldata,
+                deploy_from_zero: recorded_deploy_from_zero,
+                result,
+            } if *recorded_class_hash == class_hash
+                && *recorded_contract_address_salt == contract_address_salt
+                && recorded_calldata == calldata
+                && *recorded_deploy_from_zero == deploy_from_zero =>
+            {
+                Some(result)
+            }
+            _ => None,
+        })
+    }
+
+    fn replace_class(&mut self, class_hash: Felt, remaining_gas: &mut u128) -> SyscallResult<()> {
+        self.replay(remaining_gas, "replace_class", |syscall| match syscall {
+            Syscall::ReplaceClass {
+                class_hash: recorded_class_hash,
+                result,
+            } if *recorded_class_hash == class_hash => Some(result),
+            _ => None,
+        })
+    }
+
+    fn library_call(
+        &mut self,
+        class_hash: Felt,
+        function_selector: Felt,
+        calldata: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        self.replay(remaining_gas, "library_call", |syscall| match syscall {
+            Syscall::LibraryCall {
+                class_hash: recorded_class_hash,
+                function_selector: recorded_function_selector,
+                calldata: recorded_calldata,
+                result,
+            } if *recorded_class_hash == class_hash
+                && *recorded_function_selector == function_selector
+                && recorded_calldata == calldata =>
+            {
+                Some(result)
+            }
+            _ => None,
+        })
+    }
+
+    fn call_contract(
+        &mut self,
+        address: Felt,
+        entry_point_selector: Felt,
+        calldata: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        self.replay(remaining_gas, "call_contract", |syscall| match syscall {
+            Syscall::CallContract {
+                address: recorded_address,
+                entry_point_selector: recorded_entry_point_selector,
+                calldata: recorded_calldata,
+                result,
+            } if *recorded_address == address
+                && *recorded_entry_point_selector == entry_point_selector
+                && recorded_calldata == calldata =>
+            {
+                Some(result)
+            }
+            _ => None,
+        })
+    }
+
+    fn storage_read(
+        &mut self,
+        address_domain: u32,
+        address: Felt,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Felt> {
+        self.replay(remaining_gas, "storage_read", |syscall| match syscall {
+            Syscall::StorageRead {
+                address_domain: recorded_address_domain,
+                address: recorded_address,
+                result,
+            } if *recorded_address_domain == address_domain && *recorded_address == address => {
+                Some(result)
+            }
+            _ => None,
+        })
+    }
+
+    fn storage_write(
+        &mut self,
+        address_domain: u32,
+        address: Felt,
+        value: Felt,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        self.replay(remaining_gas, "storage_write", |syscall| match syscall {
+            Syscall::StorageWrite {
+                address_domain: recorded_address_domain,
+                address: recorded_address,
+                value: recorded_value,
+                result,
+            } if *recorded_address_domain == address_domain
+                && *recorded_address == address
+                && *recorded_value == value =>
+            {
+                Some(result)
+            }
+            _ => None,
+        })
+    }
+
+    fn emit_event(
+        &mut self,
+        keys: &[Felt],
+        data: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        self.replay(remaining_gas, "emit_event", |syscall| match syscall {
+            Syscall::EmitEvent {
+                keys: recorded_keys,
+                data: recorded_data,
+                result,
+            } if recorded_keys == keys && recorded_data == data => Some(result),
+            _ => None,
+        })
+    }
+
+    fn send_message_to_l1(
+        &mut self,
+        to_address: Felt,
+        payload: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        self.replay(
+            remaining_gas,
+            "send_message_to_l1",
+            |syscall| match syscall {
+                Syscall::SendMessageToL1 {
+                    to_address: recorded_to_address,
+                    payload: recorded_payload,
+                    result,
+                } if *recorded_to_address == to_address && recorded_payload == payload => {
+                    Some(result)
+                }
+                _ => None,
+            },
+        )
+    }
+
+    fn keccak(&mut self, input: &[u64], remaining_gas: &mut u128) -> SyscallResult<U256> {
+        self.replay(remaining_gas, "keccak", |syscall| match syscall {
+            Syscall::Keccak {
+                input: recorded_input,
+                result,
+            } if recorded_input == input => Some(result),
+            _ => None,
+        })
+    }
+
+    fn secp256k1_new(
+        &mut self,
+        x: U256,
+        y: U256,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256k1Point>> {
+        self.replay(remaining_gas, "secp256k1_new", |syscall| match syscall {
+            Syscall::Secp256k1New {
+                x: recorded_x,
+                y: recorded_y,
+                result,
+            } if *recorded_x == x && *recorded_y == y => Some(result),
+            _ => None,
+        })
+    }
+
+    fn secp256k1_add(
+        &mut self,
+        p0: Secp256k1Point,
+        p1: Secp256k1Point,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256k1Point> {
+        self.replay(remaining_gas, "secp256k1_add", |syscall| match syscall {
+            Syscall::Secp256k1Add {
+                p0: recorded_p0,
+                p1: recorded_p1,
+                result,
+            } if *recorded_p0 == p0 && *recorded_p1 == p1 => Some(result),
+            _ => None,
+        })
+    }
+
+    fn secp256k1_mul(
+        &mut self,
+        p: Secp256k1Point,
+        m: U256,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256k1Point> {
+        self.replay(remaining_gas, "secp256k1_mul", |syscall| match syscall {
+            Syscall::Secp256k1Mul {
+                p: recorded_p,
+                m: recorded_m,
+                result,
+            } if *recorded_p == p && *recorded_m == m => Some(result),
+            _ => None,
+        })
+    }
+
+    fn secp256k1_get_point_from_x(
+        &mut self,
+        x: U256,
+        y_parity: bool,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256k1Point>> {
+        self.replay(
+            remaining_gas,
+            "secp256k1_get_point_from_x",
+            |syscall| match syscall {
+                Syscall::Secp256k1GetPointFromX {
+                    x: recorded_x,
+                    y_parity: recorded_y_parity,
+                    result,
+                } if *recorded_x == x && *recorded_y_parity == y_parity => Some(result),
+                _ => None,
+            },
+        )
+    }
+
+    fn secp256k1_get_xy(
+        &mut self,
+        p: Secp256k1Point,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<(U256, U256)> {
+        self.replay(remaining_gas, "secp256k1_get_xy", |syscall| match syscall {
+            Syscall::Secp256k1GetXy {
+                p: recorded_p,
+                result,
+            } if *recorded_p == p => Some(result),
+            _ => None,
+        })
+    }
+
+    fn secp256r1_new(
+        &mut self,
+        x: U256,
+        y: U256,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256r1Point>> {
+        self.replay(remaining_gas, "secp256r1_new", |syscall| match syscall {
+            Syscall::Secp256r1New {
+                x: recorded_x,
+                y: recorded_y,
+                result,
+            } if *recorded_x == x && *recorded_y == y => Some(result),
+            _ => None,
+        })
+    }
+
+    fn secp256r1_add(
+        &mut self,
+        p0: Secp256r1Point,
+        p1: Secp256r1Point,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256r1Point> {
+        self.replay(remaining_gas, "secp256r1_add", |syscall| match syscall {
+            Syscall::Secp256r1Add {
+                p0: recorded_p0,
+                p1: recorded_p1,
+                result,
+            } if *recorded_p0 == p0 && *recorded_p1 == p1 => Some(result),
+            _ => None,
+        })
+    }
+
+    fn secp256r1_mul(
+        &mut self,
+        p: Secp256r1Point,
+        m: U256,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256r1Point> {
+        self.replay(remaining_gas, "secp256r1_mul", |syscall| match syscall {
+            Syscall::Secp256r1Mul {
+                p: recorded_p,
+                m: recorded_m,
+                result,
+            } if *recorded_p == p && *recorded_m == m => Some(result),
+            _ => None,
+        })
+    }
+
+    fn secp256r1_get_point_from_x(
+        &mut self,
+        x: U256,
+        y_parity: bool,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256r1Point>> {
+        self.replay(
+            remaining_gas,
+            "secp256r1_get_point_from_x",
+            |syscall| match syscall {
+                Syscall::Secp256r1GetPointFromX {
+                    x: recorded_x,
+                    y_parity: recorded_y_parity,
+                    result,
+                } if *recorded_x == x && *recorded_y_parity == y_parity => Some(result),
+                _ => None,
+            },
+        )
+    }
+
+    fn secp256r1_get_xy(
+        &mut self,
+        p: Secp256r1Point,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<(U256, U256)> {
+        self.replay(remaining_gas, "secp256r1_get_xy", |syscall| match syscall {
+            Syscall::Secp256r1GetXy {
+                p: recorded_p,
+                result,
+            } if *recorded_p == p => Some(result),
+            _ => None,
+        })
+    }
+
+    fn get_class_hash_at(
+        &mut self,
+        contract_address: Felt,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Felt> {
+        self.replay(
+            remaining_gas,
+            "get_class_hash_at",
+            |syscall| match syscall {
+                Syscall::GetClassHashAt {
+                    contract_address: recorded_contract_address,
+                    result,
+                } if *recorded_contract_address == contract_address => Some(result),
+                _ => None,
+            },
+        )
+    }
+
+    fn sha256_process_block(
+        &mut self,
+        state: &mut [u32; 8],
+        block: &[u32; 16],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        let initial_state = *state;
+        *state = self.replay(
+            remaining_gas,
+            "sha256_process_block",
+            |syscall| match syscall {
+                Syscall::Sha256ProcessBlock {
+                    state: recorded_state,
+                    block: recorded_block,
+                    result,
+                } if *recorded_state == initial_state && recorded_block == block => Some(result),
+                _ => None,
+            },
+        )?;
+
+        Ok(())
+    }
+
+    fn meta_tx_v0(
+        &mut self,
+        address: Felt,
+        entry_point_selector: Felt,
+        calldata: &[Felt],
+        signature: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        self.replay(remaining_gas, "meta_tx_v0", |syscall| match syscall {
+            Syscall::MetaTxV0 {
+                address: recorded_address,
+                entry_point_selector: recorded_entry_point_selector,
+                calldata: recorded_calldata,
+                signature: recorded_signature,
+                result,
+            } if *recorded_address == address
+                && *recorded_entry_point_selector == entry_point_selector
+                && recorded_calldata == calldata
+                && recorded_signature == signature =>
+            {
+                Some(result)
+            }
+            _ => None,
+        })
+    }
+
+    fn syscall_gas_costs(&self) -> SyscallGasCosts {
+        self.syscall_gas_costs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn replay_handler(records: &[RecordedSyscall]) -> ReplaySyscallHandler {
+        ReplaySyscallHandler::new(SyscallRecording {
+            syscalls: records.to_vec(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn replay_recorded_syscalls() {
+        let records = vec![
+            RecordedSyscall {
+                syscall: Syscall::StorageRead {
+                    address_domain: 0,
+                    address: Felt::from(1),
+                    result: Ok(Felt::from(2)),
+                },
+                gas_consumed: 100,
+            },
+            RecordedSyscall {
+                syscall: Syscall::CallContract {
+                    address: Felt::from(3),
+                    entry_point_selector: Felt::from(4),
+                    calldata: vec![Felt::from(5)],
+                    result: Err(vec![Felt::from(6)]),
+                },
+                gas_consumed: 1000,
+            },
+        ];
+
+        let mut replay = replay_handler(&records);
+        let mut gas = 10_000;
+
+        assert_eq!(
+            replay.storage_read(0, Felt::from(1), &mut gas),
+            Ok(Felt::from(2))
+        );
+        assert_eq!(gas, 9_900);
+        assert_eq!(
+            replay.call_contract(Felt::from(3), Felt::from(4), &[Felt::from(5)], &mut gas),
+            Err(vec![Felt::from(6)])
+        );
+        assert_eq!(gas, 8_900);
+
+        assert!(replay.finish().is_ok());
+    }
+
+    #[test]
+    fn replay_mismatch() {
+        let records = vec![RecordedSyscall {
+            syscall: Syscall::StorageRead {
+                address_domain: 0,
+                address: Felt::from(1),
+                result: Ok(Felt::from(2)),
+            },
+            gas_consumed: 100,
+        }];
+
+        let mut replay = replay_handler(&records);
+        let mut gas = 10_000;
+        assert!(replay.storage_read(0, Felt::from(2), &mut gas).is_err());
+        assert!(matches!(replay.finish(), Err(Error::ReplayMismatch(_))));
+
+        let replay = replay_handler(&records);
+        assert!(matches!(replay.finish(), Err(Error::ReplayMismatch(_))));
+    }
+
+    #[test]
+    fn record_replayed_syscalls() {
+        let records = vec![RecordedSyscall {
+            syscall: Syscall::EmitEvent {
+                keys: vec![Felt::from(1)],
+                data: vec![Felt::from(2), Felt::from(3)],
+                result: Ok(()),
+            },
+            gas_consumed: 50,
+        }];
+
+        let syscall_gas_costs = SyscallGasCosts {
+            emit_event: 1234,
+            ..Default::default()
+        };
+        let mut replay = ReplaySyscallHandler::new(SyscallRecording {
+            syscall_gas_costs,
+            syscalls: records.clone(),
+        });
+        assert_eq!(replay.syscall_gas_costs(), syscall_gas_costs);
+        let mut recording = RecordingSyscallHandler::new(&mut replay);
+        let mut gas = 10_000;
+
+        recording
+            .emit_event(&[Felt::from(1)], &[Felt::from(2), Felt::from(3)], &mut gas)
+            .unwrap();
+
+        let (_, recorded) = recording.into_parts();
+        assert_eq!(recorded.syscalls, records);
+        assert_eq!(recorded.syscall_gas_costs, syscall_gas_costs);
+        assert!(replay.finish().is_ok());
+    }
+
+    #[test]
+    fn replay_sha256_process_block() {
+        let records = vec![RecordedSyscall {
+            syscall: Syscall::Sha256ProcessBlock {
+                state: [1; 8],
+                block: [2; 16],
+                result: Ok([3; 8]),
+            },
+            gas_consumed: 0,
+        }];
+
+        let mut replay = replay_handler(&records);
+        let mut recording = RecordingSyscallHandler::new(&mut replay);
+        let mut gas = 10_000;
+
+        let mut state = [1; 8];
+        recording
+            .sha256_process_block(&mut state, &[2; 16], &mut gas)
+            .unwrap();
+        assert_eq!(state, [3; 8]);
+
+        let (_, recorded) = recording.into_parts();
+        assert_eq!(recorded.syscalls, records);
+        assert!(replay.finish().is_ok());
+
+        // A different initial state doesn't match the recording.
+        let mut replay = replay_handler(&records);
+        assert!(replay
+            .sha256_process_block(&mut [0; 8], &[2; 16], &mut gas)
+            .is_err());
+        assert!(matches!(replay.finish(), Err(Error::ReplayMismatch(_))));
+    }
+}
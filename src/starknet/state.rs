@@ -0,0 +1,924 @@
+//! # In-memory Starknet state
+//!
+//! A reference [`StarknetSyscallHandler`] which keeps the whole Starknet state in memory: the
+//! declared classes, the deployed contracts and their storage, the nonces and the emitted events
+//! and messages. Classes are compiled natively when declared, so the contracts deployed from them
+//! can call each other through the `call_contract` and `library_call` syscalls.
+
+use super::{
+    calculate_contract_address, ContractClassProgram, EntryPoints, ExecutionInfo, ExecutionInfoV2,
+    StarknetSyscallHandler, StarknetTestingHandler, SyscallGasCosts, SyscallResult, TxInfo,
+};
+#[cfg(feature = "with-serde")]
+use super::{AbiValue, ContractAbi};
+use crate::{cache::ProgramCache, error::Error, executor::NativeExecutor, OptLevel};
+use cairo_lang_sierra::ids::FunctionId;
+use cairo_lang_starknet::contract_class::ContractClass;
+use starknet_types_core::felt::Felt;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{self, Debug},
+};
+
+/// An event emitted by a contract.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StarknetEvent {
+    pub keys: Vec<Felt>,
+    pub data: Vec<Felt>,
+}
+
+/// A message sent by a contract to L1.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct L2ToL1Message {
+    pub to_address: Felt,
+    pub payload: Vec<Felt>,
+}
+
+/// A natively compiled class and its entry points, by selector.
+struct DeclaredClass<'m> {
+    executor: NativeExecutor<'m>,
+    constructor: Option<(Felt, FunctionId)>,
+    external: HashMap<Felt, FunctionId>,
+    l1_handler: HashMap<Felt, FunctionId>,
+    #[cfg(feature = "with-serde")]
+    abi: ContractAbi,
+}
+
+/// A change to the state, recorded to be undone if its call frame is reverted. Overwritten values
+/// are kept as they were before the change.
+#[derive(Debug)]
+enum JournalEntry {
+    Deploy {
+        address: Felt,
+    },
+    ReplaceClass {
+        address: Felt,
+        class_hash: Option<Felt>,
+    },
+    StorageWrite {
+        address: Felt,
+        key: Felt,
+        value: Option<Felt>,
+    },
+    NonceIncrement {
+        address: Felt,
+    },
+    EmitEvent {
+        address: Felt,
+    },
+    PopLog {
+        address: Felt,
+        event: StarknetEvent,
+    },
+    SendMessageToL1 {
+        address: Felt,
+    },
+    PopL2ToL1Message {
+        address: Felt,
+        message: L2ToL1Message,
+    },
+}
+
+/// The events and messages emitted by a contract.
+#[derive(Debug, Default)]
+struct ContractLogs {
+    events: VecDeque<StarknetEvent>,
+    l2_to_l1_messages: Vec<L2ToL1Message>,
+}
+
+/// A Starknet state kept in memory.
+///
+/// Classes must be declared (which compiles them into the executor cache) before contracts can be
+/// deployed from them. Contract addresses are computed like Starknet does, from the deployer
+/// address, the salt, the class hash and the constructor calldata.
+///
+/// The state is lent to the executor as a syscall handler, so it can be inspected after the run.
+/// Outside of any entry point the contract and caller addresses of the execution info are zero.
+/// It also runs the cheatcodes of the test plugin, which overwrite that execution info and pop the
+/// events and messages emitted by the contracts.
+///
+/// Every change is journaled while a call frame is open, so that failed calls (including the
+/// top-level ones made through this type's methods) leave no trace in the state.
+pub struct InMemoryStarknetState<'m> {
+    cache: ProgramCache<'m, Felt>,
+
+    /// The declared classes, by class hash.
+    classes: HashMap<Felt, DeclaredClass<'m>>,
+    /// The class hash of every deployed contract, by address.
+    contracts: HashMap<Felt, Felt>,
+    /// The storage of every contract, by address.
+    storage: HashMap<Felt, HashMap<Felt, Felt>>,
+    /// The nonce of every contract, by address.
+    nonces: HashMap<Felt, Felt>,
+    /// The events and messages emitted by every contract, by address.
+    logs: HashMap<Felt, ContractLogs>,
+    /// The hashes returned by the `get_block_hash` syscall, by block number.
+    block_hashes: HashMap<u64, Felt>,
+    /// The execution info of the running entry point.
+    exec_info: ExecutionInfoV2,
+    /// The base gas cost of every syscall.
+    gas_costs: SyscallGasCosts,
+
+    /// The changes made within the open call frames, oldest first.
+    journal: Vec<JournalEntry>,
+    /// The journal length at the start of every open call frame.
+    call_frames: Vec<usize>,
+}
+
+impl<'m> InMemoryStarknetState<'m> {
+    /// Create an empty state whose classes will be compiled into the given cache.
+    pub fn new(cache: ProgramCache<'m, Felt>) -> Self {
+        Self {
+            cache,
+            classes: HashMap::new(),
+            contracts: HashMap::new(),
+            storage: HashMap::new(),
+            nonces: HashMap::new(),
+            logs: HashMap::new(),
+            block_hashes: HashMap::new(),
+            exec_info: ExecutionInfoV2::default(),
+            gas_costs: SyscallGasCosts::default(),
+            journal: Vec::new(),
+            call_frames: Vec::new(),
+        }
+    }
+
+    /// Declare a contract class under the given class hash.
+    ///
+    /// The class is compiled unless the cache already holds an executor for that class hash.
+    pub fn declare(
+        &mut self,
+        class_hash: Felt,
+        contract_class: &ContractClass,
+        opt_level: OptLevel,
+    ) -> Result<(), Error> {
+        let ContractClassProgram {
+            program,
+            entry_points,
+            ..
+        } = ContractClassProgram::extract(contract_class)?;
+
+        let executor = match self.cache.get(&class_hash) {
+            Some(executor) => executor,
+            None => self
+                .cache
+                .compile_and_insert(class_hash, &program, opt_level),
+        };
+
+        #[cfg(feature = "with-serde")]
+        let abi = ContractAbi::from_contract_class(contract_class)?.unwrap_or_default();
+
+        self.insert_class(
+            class_hash,
+            executor,
+            entry_points,
+            #[cfg(feature = "with-serde")]
+            abi,
+        );
+        Ok(())
+    }
+
+    /// Declare a class whose entry points are functions of an already compiled program, like the
+    /// contracts compiled along with the tests of a crate.
+    ///
+    /// Such classes have no ABI, so their events can't be decoded.
+    pub fn declare_compiled(
+        &mut self,
+        class_hash: Felt,
+        executor: NativeExecutor<'m>,
+        entry_points: EntryPoints,
+    ) {
+        self.insert_class(
+            class_hash,
+            executor,
+            entry_points,
+            #[cfg(feature = "with-serde")]
+            ContractAbi::default(),
+        );
+    }
+
+    fn insert_class(
+        &mut self,
+        class_hash: Felt,
+        executor: NativeExecutor<'m>,
+        entry_points: EntryPoints,
+        #[cfg(feature = "with-serde")] abi: ContractAbi,
+    ) {
+        let constructor = entry_points
+            .constructor
+            .map(|entry_point| (entry_point.selector, entry_point.function_id));
+        let external = entry_points
+            .external
+            .into_iter()
+            .map(|entry_point| (entry_point.selector, entry_point.function_id))
+            .collect();
+        let l1_handler = entry_points
+            .l1_handler
+            .into_iter()
+            .map(|entry_point| (entry_point.selector, entry_point.function_id))
+            .collect();
+
+        self.classes.insert(
+            class_hash,
+            DeclaredClass {
+                executor,
+                constructor,
+                external,
+                l1_handler,
+                #[cfg(feature = "with-serde")]
+                abi,
+            },
+        );
+    }
+
+    /// Whether a class has been declared.
+    pub fn is_declared(&self, class_hash: Felt) -> bool {
+        self.classes.contains_key(&class_hash)
+    }
+
+    /// Return the class hash of a deployed contract.
+    pub fn class_hash_at(&self, address: Felt) -> Option<Felt> {
+        self.contracts.get(&address).copied()
+    }
+
+    /// Read a storage value of a contract. Unset values are zero.
+    pub fn storage_at(&self, address: Felt, key: Felt) -> Felt {
+        self.storage
+            .get(&address)
+            .and_then(|storage| storage.get(&key))
+            .copied()
+            .unwrap_or(Felt::ZERO)
+    }
+
+    /// Write a storage value of a contract.
+    pub fn set_storage_at(&mut self, address: Felt, key: Felt, value: Felt) {
+        let value = self.storage.entry(address).or_default().insert(key, value);
+        self.record(|| JournalEntry::StorageWrite {
+            address,
+            key,
+            value,
+        });
+    }
+
+    /// Return the nonce of a contract.
+    pub fn nonce_of(&self, address: Felt) -> Felt {
+        self.nonces.get(&address).copied().unwrap_or(Felt::ZERO)
+    }
+
+    /// Increment the nonce of a contract, returning the previous one.
+    pub fn increment_nonce(&mut self, address: Felt) -> Felt {
+        let nonce = self.nonces.entry(address).or_insert(Felt::ZERO);
+        let previous = *nonce;
+        *nonce += Felt::ONE;
+
+        self.record(|| JournalEntry::NonceIncrement { address });
+        previous
+    }
+
+    /// Return the events emitted by a contract which haven't been popped yet, oldest first.
+    pub fn events(&self, address: Felt) -> impl Iterator<Item = &StarknetEvent> + '_ {
+        self.logs
+            .get(&address)
+            .into_iter()
+            .flat_map(|logs| logs.events.iter())
+    }
+
+    /// Decode the events emitted by a contract which haven't been popped yet with the ABI of its
+    /// class, oldest first.
+    #[cfg(feature = "with-serde")]
+    pub fn decoded_events(&self, address: Felt) -> Result<Vec<AbiValue>, Error> {
+        let abi = self
+            .contracts
+            .get(&address)
+            .and_then(|class_hash| self.classes.get(class_hash))
+            .map(|class| &class.abi);
+
+        self.events(address)
+            .map(|event| match abi {
+                Some(abi) => abi.decode_event(&event.keys, &event.data),
+                None => Err(Error::UnknownAbiItem("Event".to_string())),
+            })
+            .collect()
+    }
+
+    /// Return the messages sent to L1 by a contract, oldest first.
+    pub fn l2_to_l1_messages(&self, address: Felt) -> &[L2ToL1Message] {
+        self.logs
+            .get(&address)
+            .map(|logs| logs.l2_to_l1_messages.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Set the hash returned by the `get_block_hash` syscall for a block.
+    pub fn set_block_hash(&mut self, block_number: u64, block_hash: Felt) {
+        self.block_hashes.insert(block_number, block_hash);
+    }
+
+    /// Return the execution info, which is shared by every entry point except for the contract
+    /// and caller addresses and the entry point selector.
+    pub fn execution_info(&self) -> &ExecutionInfoV2 {
+        &self.exec_info
+    }
+
+    /// Return a mutable reference to the execution info, to set the block and transaction info.
+    pub fn execution_info_mut(&mut self) -> &mut ExecutionInfoV2 {
+        &mut self.exec_info
+    }
+
+    /// Replace the base gas costs charged for every syscall, which default to the VM's.
+    pub fn set_syscall_gas_costs(&mut self, gas_costs: SyscallGasCosts) {
+        self.gas_costs = gas_costs;
+    }
+
+    /// Deploy a contract and run its constructor, returning its address and the constructor's
+    /// return values.
+    ///
+    /// If the constructor fails, the contract isn't deployed and its changes are reverted.
+    pub fn deploy_contract(
+        &mut self,
+        class_hash: Felt,
+        contract_address_salt: Felt,
+        calldata: &[Felt],
+        deployer_address: Felt,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<(Felt, Vec<Felt>)> {
+        let Some(class) = self.classes.get(&class_hash) else {
+            return Err(vec![short_string(b"CLASS_HASH_NOT_FOUND")]);
+        };
+        let constructor = class.constructor.clone();
+
+        let contract_address = calculate_contract_address(
+            contract_address_salt,
+            class_hash,
+            calldata,
+            deployer_address,
+        );
+        if self.contracts.contains_key(&contract_address) {
+            return Err(vec![short_string(b"CONTRACT_ADDRESS_UNAVAILABLE")]);
+        }
+
+        self.in_call_frame(|state| {
+            // The contract is deployed before running its constructor, since it may call itself.
+            state.contracts.insert(contract_address, class_hash);
+            state.record(|| JournalEntry::Deploy {
+                address: contract_address,
+            });
+
+            let return_values = match constructor {
+                Some((selector, function_id)) => state
+                    .run_entry_point(
+                        class_hash,
+                        selector,
+                        &function_id,
+                        contract_address,
+                        deployer_address,
+                        calldata,
+                        remaining_gas,
+                    )
+                    .map_err(|mut revert_reason| {
+                        revert_reason.push(short_string(b"CONSTRUCTOR_FAILED"));
+                        revert_reason
+                    })?,
+                None if calldata.is_empty() => Vec::new(),
+                None => return Err(vec![short_string(b"INVALID_CALLDATA_LEN")]),
+            };
+
+            Ok((contract_address, return_values))
+        })
+    }
+
+    /// Call an external entry point of a deployed contract.
+    ///
+    /// The caller is the running contract, or zero outside of any entry point.
+    pub fn invoke_contract(
+        &mut self,
+        address: Felt,
+        entry_point_selector: Felt,
+        calldata: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        let (class_hash, function_id) = self.find_external(address, entry_point_selector)?;
+
+        let caller_address = self.exec_info.contract_address;
+        self.in_call_frame(|state| {
+            state
+                .run_entry_point(
+                    class_hash,
+                    entry_point_selector,
+                    &function_id,
+                    address,
+                    caller_address,
+                    calldata,
+                    remaining_gas,
+                )
+                .map_err(|mut revert_reason| {
+                    revert_reason.push(short_string(b"ENTRYPOINT_FAILED"));
+                    revert_reason
+                })
+        })
+    }
+
+    /// Run an L1 handler of a deployed contract, as if a message from `from_address` had been
+    /// consumed.
+    pub fn handle_l1_message(
+        &mut self,
+        address: Felt,
+        entry_point_selector: Felt,
+        from_address: Felt,
+        payload: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        let Some(class_hash) = self.contracts.get(&address).copied() else {
+            return Err(vec![short_string(b"CONTRACT_NOT_DEPLOYED")]);
+        };
+        let Some(class) = self.classes.get(&class_hash) else {
+            return Err(vec![short_string(b"CLASS_HASH_NOT_DECLARED")]);
+        };
+        let Some(function_id) = class.l1_handler.get(&entry_point_selector).cloned() else {
+            return Err(vec![short_string(b"ENTRYPOINT_NOT_FOUND")]);
+        };
+
+        let calldata = [&[from_address], payload].concat();
+        self.in_call_frame(|state| {
+            state.run_entry_point(
+                class_hash,
+                entry_point_selector,
+                &function_id,
+                address,
+                Felt::ZERO,
+                &calldata,
+                remaining_gas,
+            )
+        })
+    }
+
+    /// Run a call inside a call frame, which is reverted if the call fails.
+    fn in_call_frame<T>(
+        &mut self,
+        call: impl FnOnce(&mut Self) -> SyscallResult<T>,
+    ) -> SyscallResult<T> {
+        self.begin_frame();
+        let result = call(self);
+        match result {
+            Ok(_) => self.commit_frame(),
+            Err(_) => self.revert_frame(),
+        }
+
+        result
+    }
+
+    fn begin_frame(&mut self) {
+        self.call_frames.push(self.journal.len());
+    }
+
+    fn commit_frame(&mut self) {
+        self.call_frames
+            .pop()
+            .expect("there should be an open call frame");
+
+        // Outside of any frame there is nothing left to revert.
+        if self.call_frames.is_empty() {
+            self.journal.clear();
+        }
+    }
+
+    fn revert_frame(&mut self) {
+        let start = self
+            .call_frames
+            .pop()
+            .expect("there should be an open call frame");
+
+        while self.journal.len() > start {
+            match self.journal.pop().unwrap() {
+                JournalEntry::Deploy { address } => {
+                    self.contracts.remove(&address);
+                }
+                JournalEntry::ReplaceClass {
+                    address,
+                    class_hash,
+                } => {
+                    match class_hash {
+                        Some(class_hash) => self.contracts.insert(address, class_hash),
+                        None => self.contracts.remove(&address),
+                    };
+                }
+                JournalEntry::StorageWrite {
+                    address,
+                    key,
+                    value,
+                } => {
+                    let storage = self.storage.entry(address).or_default();
+                    match value {
+                        Some(value) => storage.insert(key, value),
+                        None => storage.remove(&key),
+                    };
+                }
+                JournalEntry::NonceIncrement { address } => {
+                    if let Some(nonce) = self.nonces.get_mut(&address) {
+                        *nonce -= Felt::ONE;
+                    }
+                }
+                JournalEntry::EmitEvent { address } => {
+                    if let Some(logs) = self.logs.get_mut(&address) {
+                        logs.events.pop_back();
+                    }
+                }
+                JournalEntry::PopLog { address, event } => {
+                    self.logs
+                        .entry(address)
+                        .or_default()
+                        .events
+                        .push_front(event);
+                }
+                JournalEntry::SendMessageToL1 { address } => {
+                    if let Some(logs) = self.logs.get_mut(&address) {
+                        logs.l2_to_l1_messages.pop();
+                    }
+                }
+                JournalEntry::PopL2ToL1Message { address, message } => {
+                    self.logs
+                        .entry(address)
+                        .or_default()
+                        .l2_to_l1_messages
+                        .insert(0, message);
+                }
+            }
+        }
+    }
+
+    /// Record a change to the state, unless there is no call frame to revert.
+    fn record(&mut self, entry: impl FnOnce() -> JournalEntry) {
+        if !self.call_frames.is_empty() {
+            self.journal.push(entry());
+        }
+    }
+
+    /// Find the class hash of a deployed contract and its external entry point.
+    fn find_external(
+        &self,
+        address: Felt,
+        entry_point_selector: Felt,
+    ) -> SyscallResult<(Felt, FunctionId)> {
+        let Some(class_hash) = self.contracts.get(&address).copied() else {
+            return Err(vec![short_string(b"CONTRACT_NOT_DEPLOYED")]);
+        };
+        let Some(class) = self.classes.get(&class_hash) else {
+            return Err(vec![short_string(b"CLASS_HASH_NOT_DECLARED")]);
+        };
+        let Some(function_id) = class.external.get(&entry_point_selector).cloned() else {
+            return Err(vec![short_string(b"ENTRYPOINT_NOT_FOUND")]);
+        };
+
+        Ok((class_hash, function_id))
+    }
+
+    fn logs(&mut self) -> &mut ContractLogs {
+        self.logs
+            .entry(self.exec_info.contract_address)
+            .or_default()
+    }
+
+    /// Run an entry point of a class with the given contract and caller addresses, returning its
+    /// result or its revert reason.
+    #[allow(clippy::too_many_arguments)]
+    fn run_entry_point(
+        &mut self,
+        class_hash: Felt,
+        entry_point_selector: Felt,
+        function_id: &FunctionId,
+        contract_address: Felt,
+        caller_address: Felt,
+        calldata: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        let executor = self.classes[&class_hash].executor.clone();
+
+        let old_contract_address =
+            std::mem::replace(&mut self.exec_info.contract_address, contract_address);
+        let old_caller_address =
+            std::mem::replace(&mut self.exec_info.caller_address, caller_address);
+        let old_entry_point_selector = std::mem::replace(
+            &mut self.exec_info.entry_point_selector,
+            entry_point_selector,
+        );
+
+        let result = executor.invoke_contract_dynamic(
+            function_id,
+            calldata,
+            Some(*remaining_gas),
+            &mut *self,
+        );
+
+        self.exec_info.contract_address = old_contract_address;
+        self.exec_info.caller_address = old_caller_address;
+        self.exec_info.entry_point_selector = old_entry_point_selector;
+
+        match result {
+            Ok(result) => {
+                *remaining_gas = result.remaining_gas;
+                if result.failure_flag {
+                    Err(result.return_values)
+                } else {
+                    Ok(result.return_values)
+                }
+            }
+            Err(Error::InsufficientGasError) => Err(vec![short_string(b"Out of gas")]),
+            Err(e) => {
+                tracing::error!("failed to run a contract entry point: {e}");
+                Err(Vec::new())
+            }
+        }
+    }
+}
+
+impl Debug for InMemoryStarknetState<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("InMemoryStarknetState")
+            .field("classes", &self.classes.keys().collect::<Vec<_>>())
+            .field("contracts", &self.contracts)
+            .field("storage", &self.storage)
+            .field("nonces", &self.nonces)
+            .field("logs", &self.logs)
+            .field("exec_info", &self.exec_info)
+            .field("call_frames", &self.call_frames.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl StarknetSyscallHandler for InMemoryStarknetState<'_> {
+    fn get_block_hash(
+        &mut self,
+        block_number: u64,
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Felt> {
+        self.block_hashes
+            .get(&block_number)
+            .copied()
+            .ok_or_else(|| vec![short_string(b"Block number out of range")])
+    }
+
+    fn get_execution_info(&mut self, _remaining_gas: &mut u128) -> SyscallResult<ExecutionInfo> {
+        let exec_info = &self.exec_info;
+        Ok(ExecutionInfo {
+            block_info: exec_info.block_info,
+            tx_info: TxInfo {
+                version: exec_info.tx_info.version,
+                account_contract_address: exec_info.tx_info.account_contract_address,
+                max_fee: exec_info.tx_info.max_fee,
+                signature: exec_info.tx_info.signature.clone(),
+                transaction_hash: exec_info.tx_info.transaction_hash,
+                chain_id: exec_info.tx_info.chain_id,
+                nonce: exec_info.tx_info.nonce,
+            },
+            caller_address: exec_info.caller_address,
+            contract_address: exec_info.contract_address,
+            entry_point_selector: exec_info.entry_point_selector,
+        })
+    }
+
+    fn get_execution_info_v2(
+        &mut self,
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<ExecutionInfoV2> {
+        Ok(self.exec_info.clone())
+    }
+
+    fn deploy(
+        &mut self,
+        class_hash: Felt,
+        contract_address_salt: Felt,
+        calldata: &[Felt],
+        deploy_from_zero: bool,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<(Felt, Vec<Felt>)> {
+        let deployer_address = if deploy_from_zero {
+            Felt::ZERO
+        } else {
+            self.exec_info.contract_address
+        };
+
+        self.deploy_contract(
+            class_hash,
+            contract_address_salt,
+            calldata,
+            deployer_address,
+            remaining_gas,
+        )
+    }
+
+    fn replace_class(&mut self, class_hash: Felt, _remaining_gas: &mut u128) -> SyscallResult<()> {
+        if !self.is_declared(class_hash) {
+            return Err(vec![short_string(b"CLASS_HASH_NOT_FOUND")]);
+        }
+
+        let address = self.exec_info.contract_address;
+        let class_hash = self.contracts.insert(address, class_hash);
+        self.record(|| JournalEntry::ReplaceClass {
+            address,
+            class_hash,
+        });
+        Ok(())
+    }
+
+    fn library_call(
+        &mut self,
+        class_hash: Felt,
+        function_selector: Felt,
+        calldata: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        let Some(class) = self.classes.get(&class_hash) else {
+            return Err(vec![short_string(b"CLASS_HASH_NOT_DECLARED")]);
+        };
+        let Some(function_id) = class.external.get(&function_selector).cloned() else {
+            return Err(vec![short_string(b"ENTRYPOINT_NOT_FOUND")]);
+        };
+
+        let (contract_address, caller_address) = (
+            self.exec_info.contract_address,
+            self.exec_info.caller_address,
+        );
+        self.run_entry_point(
+            class_hash,
+            function_selector,
+            &function_id,
+            contract_address,
+            caller_address,
+            calldata,
+            remaining_gas,
+        )
+        .map_err(|mut revert_reason| {
+            revert_reason.push(short_string(b"ENTRYPOINT_FAILED"));
+            revert_reason
+        })
+    }
+
+    fn call_contract(
+        &mut self,
+        address: Felt,
+        entry_point_selector: Felt,
+        calldata: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        self.invoke_contract(address, entry_point_selector, calldata, remaining_gas)
+    }
+
+    fn get_class_hash_at(
+        &mut self,
+        contract_address: Felt,
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Felt> {
+        Ok(self.class_hash_at(contract_address).unwrap_or(Felt::ZERO))
+    }
+
+    fn meta_tx_v0(
+        &mut self,
+        address: Felt,
+        entry_point_selector: Felt,
+        calldata: &[Felt],
+        signature: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        let (class_hash, function_id) = self.find_external(address, entry_point_selector)?;
+
+        let old_tx_info = self.exec_info.tx_info.clone();
+        self.exec_info.tx_info.version = Felt::ZERO;
+        self.exec_info.tx_info.signature = signature.to_vec();
+
+        let result = self.in_call_frame(|state| {
+            state
+                .run_entry_point(
+                    class_hash,
+                    entry_point_selector,
+                    &function_id,
+                    address,
+                    Felt::ZERO,
+                    calldata,
+                    remaining_gas,
+                )
+                .map_err(|mut revert_reason| {
+                    revert_reason.push(short_string(b"ENTRYPOINT_FAILED"));
+                    revert_reason
+                })
+        });
+
+        self.exec_info.tx_info = old_tx_info;
+        result
+    }
+
+    fn storage_read(
+        &mut self,
+        address_domain: u32,
+        address: Felt,
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Felt> {
+        if address_domain != 0 {
+            return Err(vec![short_string(b"Unsupported address domain")]);
+        }
+
+        Ok(self.storage_at(self.exec_info.contract_address, address))
+    }
+
+    fn storage_write(
+        &mut self,
+        address_domain: u32,
+        address: Felt,
+        value: Felt,
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        if address_domain != 0 {
+            return Err(vec![short_string(b"Unsupported address domain")]);
+        }
+
+        let contract_address = self.exec_info.contract_address;
+        self.set_storage_at(contract_address, address, value);
+        Ok(())
+    }
+
+    fn emit_event(
+        &mut self,
+        keys: &[Felt],
+        data: &[Felt],
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        self.logs().events.push_back(StarknetEvent {
+            keys: keys.to_vec(),
+            data: data.to_vec(),
+        });
+
+        let address = self.exec_info.contract_address;
+        self.record(|| JournalEntry::EmitEvent { address });
+        Ok(())
+    }
+
+    fn send_message_to_l1(
+        &mut self,
+        to_address: Felt,
+        payload: &[Felt],
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        self.logs().l2_to_l1_messages.push(L2ToL1Message {
+            to_address,
+            payload: payload.to_vec(),
+        });
+
+        let address = self.exec_info.contract_address;
+        self.record(|| JournalEntry::SendMessageToL1 { address });
+        Ok(())
+    }
+
+    fn syscall_gas_costs(&self) -> SyscallGasCosts {
+        self.gas_costs
+    }
+
+    fn begin_call_frame(&mut self) {
+        self.begin_frame();
+    }
+
+    fn commit_call_frame(&mut self) {
+        self.commit_frame();
+    }
+
+    fn revert_call_frame(&mut self) {
+        self.revert_frame();
+    }
+
+    fn testing_handler(&mut self) -> Option<&mut dyn StarknetTestingHandler> {
+        Some(self)
+    }
+}
+
+impl<'m> StarknetTestingHandler for InMemoryStarknetState<'m> {
+    fn testing_execution_info(&mut self) -> &mut ExecutionInfoV2 {
+        &mut self.exec_info
+    }
+
+    fn pop_log(&mut self, address: Felt) -> Option<StarknetEvent> {
+        let event = self.logs.get_mut(&address)?.events.pop_front()?;
+        self.record(|| JournalEntry::PopLog {
+            address,
+            event: event.clone(),
+        });
+
+        Some(event)
+    }
+
+    fn pop_l2_to_l1_message(&mut self, address: Felt) -> Option<L2ToL1Message> {
+        let messages = &mut self.logs.get_mut(&address)?.l2_to_l1_messages;
+        if messages.is_empty() {
+            return None;
+        }
+
+        let message = messages.remove(0);
+        self.record(|| JournalEntry::PopL2ToL1Message {
+            address,
+            message: message.clone(),
+        });
+
+        Some(message)
+    }
+}
+
+fn short_string(value: &[u8]) -> Felt {
+    Felt::from_bytes_be_slice(value)
+}
@@ -0,0 +1,247 @@
+//! # Testing cheatcodes
+//!
+//! The `starknet::testing` functions of the corelib (`set_caller_address`, `set_block_number`,
+//! `pop_log`, ...) are compiled into the `cheatcode` libfunc of the test plugin, which forwards its
+//! selector and input to the host (check out [the instrumentation](crate::metadata::cheatcode)).
+//!
+//! Cheatcodes are run by the [`StarknetTestingHandler`] returned by the
+//! [`testing_handler`](StarknetSyscallHandler::testing_handler) method of the handler which is
+//! running the program. Handlers which don't support them ignore every cheatcode, which then
+//! returns an empty span.
+
+use super::{
+    handler::StarknetSyscallHandlerCallbacks, ArrayAbi, ExecutionInfoV2, Felt252Abi, L2ToL1Message,
+    StarknetEvent, StarknetSyscallHandler,
+};
+use num_traits::ToPrimitive;
+use starknet_types_core::felt::Felt;
+use std::{alloc::Layout, cell::Cell, ptr::NonNull};
+
+/// The syscall callbacks of a running program, with their lifetimes erased.
+type CheatcodeTarget =
+    StarknetSyscallHandlerCallbacks<'static, &'static mut dyn StarknetSyscallHandler>;
+
+thread_local! {
+    /// The syscall callbacks of the running program, whose handler's testing handler runs the
+    /// cheatcodes.
+    static CHEATCODE_TARGET: Cell<Option<NonNull<CheatcodeTarget>>> = Cell::new(None);
+}
+
+/// The test-only counterpart of [`StarknetSyscallHandler`], which implements the cheatcodes.
+///
+/// The `set_*` cheatcodes overwrite the fields of the execution info returned by
+/// [`testing_execution_info`](Self::testing_execution_info), which should also be the one returned
+/// by the `get_execution_info` syscalls. The `pop_*` cheatcodes take the oldest event or message
+/// emitted by a contract.
+pub trait StarknetTestingHandler: StarknetSyscallHandler {
+    /// Return the execution info which the `set_*` cheatcodes overwrite.
+    fn testing_execution_info(&mut self) -> &mut ExecutionInfoV2;
+
+    /// Remove and return the oldest event emitted by the contract, if any.
+    fn pop_log(&mut self, address: Felt) -> Option<StarknetEvent>;
+
+    /// Remove and return the oldest message sent to L1 by the contract, if any.
+    fn pop_l2_to_l1_message(&mut self, address: Felt) -> Option<L2ToL1Message>;
+
+    fn set_account_contract_address(&mut self, contract_address: Felt) {
+        self.testing_execution_info()
+            .tx_info
+            .account_contract_address = contract_address;
+    }
+
+    fn set_block_number(&mut self, block_number: u64) {
+        self.testing_execution_info().block_info.block_number = block_number;
+    }
+
+    fn set_block_timestamp(&mut self, block_timestamp: u64) {
+        self.testing_execution_info().block_info.block_timestamp = block_timestamp;
+    }
+
+    fn set_caller_address(&mut self, address: Felt) {
+        self.testing_execution_info().caller_address = address;
+    }
+
+    fn set_chain_id(&mut self, chain_id: Felt) {
+        self.testing_execution_info().tx_info.chain_id = chain_id;
+    }
+
+    fn set_contract_address(&mut self, address: Felt) {
+        self.testing_execution_info().contract_address = address;
+    }
+
+    fn set_max_fee(&mut self, max_fee: u128) {
+        self.testing_execution_info().tx_info.max_fee = max_fee;
+    }
+
+    fn set_nonce(&mut self, nonce: Felt) {
+        self.testing_execution_info().tx_info.nonce = nonce;
+    }
+
+    fn set_sequencer_address(&mut self, address: Felt) {
+        self.testing_execution_info().block_info.sequencer_address = address;
+    }
+
+    fn set_signature(&mut self, signature: &[Felt]) {
+        self.testing_execution_info().tx_info.signature = signature.to_vec();
+    }
+
+    fn set_transaction_hash(&mut self, transaction_hash: Felt) {
+        self.testing_execution_info().tx_info.transaction_hash = transaction_hash;
+    }
+
+    fn set_version(&mut self, version: Felt) {
+        self.testing_execution_info().tx_info.version = version;
+    }
+
+    /// Run a cheatcode, returning its output, or `None` if the selector is unknown or the input is
+    /// invalid.
+    ///
+    /// The selector is the cheatcode's name as a short string. The input and output are encoded
+    /// like the `cairo-lang-runner` does.
+    fn cheatcode(&mut self, selector: Felt, input: &[Felt]) -> Option<Vec<Felt>> {
+        let selector = selector.to_bytes_be();
+        let offset = selector.iter().take_while(|x| **x == 0).count();
+        let selector = std::str::from_utf8(&selector[offset..]).ok()?;
+
+        match (selector, input) {
+            ("set_account_contract_address", &[value]) => self.set_account_contract_address(value),
+            ("set_block_number", &[value]) => self.set_block_number(value.to_biguint().to_u64()?),
+            ("set_block_timestamp", &[value]) => {
+                self.set_block_timestamp(value.to_biguint().to_u64()?)
+            }
+            ("set_caller_address", &[value]) => self.set_caller_address(value),
+            ("set_chain_id", &[value]) => self.set_chain_id(value),
+            ("set_contract_address", &[value]) => self.set_contract_address(value),
+            ("set_max_fee", &[value]) => self.set_max_fee(value.to_biguint().to_u128()?),
+            ("set_nonce", &[value]) => self.set_nonce(value),
+            ("set_sequencer_address", &[value]) => self.set_sequencer_address(value),
+            ("set_signature", signature) => self.set_signature(signature),
+            ("set_transaction_hash", &[value]) => self.set_transaction_hash(value),
+            ("set_version", &[value]) => self.set_version(value),
+            ("pop_log", &[address]) => {
+                return Some(match self.pop_log(address) {
+                    Some(event) => std::iter::once(Felt::from(event.keys.len()))
+                        .chain(event.keys)
+                        .chain([Felt::from(event.data.len())])
+                        .chain(event.data)
+                        .collect(),
+                    None => Vec::new(),
+                });
+            }
+            ("pop_l2_to_l1_message", &[address]) => {
+                return Some(match self.pop_l2_to_l1_message(address) {
+                    Some(message) => [message.to_address, Felt::from(message.payload.len())]
+                        .into_iter()
+                        .chain(message.payload)
+                        .collect(),
+                    None => Vec::new(),
+                });
+            }
+            _ => return None,
+        }
+
+        Some(Vec::new())
+    }
+}
+
+/// Restores the previous cheatcode target when dropped.
+pub(crate) struct CheatcodeTargetGuard(Option<NonNull<CheatcodeTarget>>);
+
+impl Drop for CheatcodeTargetGuard {
+    fn drop(&mut self) {
+        CHEATCODE_TARGET.with(|target| target.set(self.0));
+    }
+}
+
+/// Make the handler behind the syscall callbacks run the cheatcodes of the current thread until
+/// the guard is dropped.
+///
+/// The cheatcodes go through the same callbacks as the program's syscalls, instead of a separate
+/// borrow of the handler, so the handler is only ever reached through the callbacks' pointer.
+///
+/// # Safety
+///
+/// The callbacks must be valid until the guard is dropped, and they must only be accessed through
+/// this same pointer (or pointers derived from it) in the meantime.
+pub(crate) unsafe fn set_cheatcode_target(
+    callbacks: *mut StarknetSyscallHandlerCallbacks<'_, &mut dyn StarknetSyscallHandler>,
+) -> CheatcodeTargetGuard {
+    // Erase the callbacks' lifetimes, which the guard is responsible for.
+    let callbacks = callbacks.cast::<CheatcodeTarget>();
+
+    CheatcodeTargetGuard(CHEATCODE_TARGET.with(|target| target.replace(NonNull::new(callbacks))))
+}
+
+extern "C" fn run_cheatcode(
+    output: &mut ArrayAbi<Felt252Abi>,
+    selector: &Felt252Abi,
+    input: &ArrayAbi<Felt252Abi>,
+) {
+    let selector = Felt::from_bytes_le(&selector.0);
+    let input = if input.ptr.is_null() {
+        Vec::new()
+    } else {
+        let input = unsafe {
+            std::slice::from_raw_parts(
+                input.ptr.add(input.since as usize),
+                (input.until - input.since) as usize,
+            )
+        };
+        input.iter().map(|x| Felt::from_bytes_le(&x.0)).collect()
+    };
+
+    let result = CHEATCODE_TARGET.with(|target| {
+        let mut callbacks = target.get()?;
+        // SAFETY: The target is valid while it's set (see `set_cheatcode_target`), and cheatcodes
+        // never run while the handler is running a syscall.
+        let handler = unsafe { callbacks.as_mut() }.handler().testing_handler()?;
+        Some(handler.cheatcode(selector, &input))
+    });
+
+    let data = match result {
+        Some(Some(data)) => data,
+        Some(None) => {
+            tracing::error!("Invalid cheatcode {selector} with input {input:?}.");
+            Vec::new()
+        }
+        None => {
+            tracing::warn!("Ignoring cheatcode {selector} since the handler doesn't support them.");
+            Vec::new()
+        }
+    };
+
+    *output = match data.len() {
+        0 => ArrayAbi {
+            ptr: std::ptr::null_mut(),
+            since: 0,
+            until: 0,
+            capacity: 0,
+        },
+        len => unsafe {
+            let ptr =
+                libc::malloc(Layout::array::<Felt252Abi>(len).unwrap().size()) as *mut Felt252Abi;
+            for (i, value) in data.iter().enumerate() {
+                ptr.add(i).write(Felt252Abi(value.to_bytes_le()));
+            }
+
+            let len = len.try_into().unwrap();
+            ArrayAbi {
+                ptr,
+                since: 0,
+                until: len,
+                capacity: len,
+            }
+        },
+    };
+}
+
+/// Point the cheatcode hook global of a compiled program to this module's cheatcode runner.
+///
+/// # Safety
+///
+/// The pointer must point to the cheatcode hook global of a program.
+pub(crate) unsafe fn install_cheatcode_hook(hook_ptr: *mut u64) {
+    *hook_ptr = run_cheatcode
+        as extern "C" fn(&mut ArrayAbi<Felt252Abi>, &Felt252Abi, &ArrayAbi<Felt252Abi>)
+        as usize as u64;
+}
@@ -0,0 +1,412 @@
+//! # Statement traces
+//!
+//! Programs compiled with statement tracing enabled (check out
+//! [`NativeContext::with_statement_trace`](crate::context::NativeContext::with_statement_trace))
+//! report every executed Sierra statement (see [the instrumentation](crate::metadata::statement_trace)).
+//! This module collects them into a [`StatementTrace`], which can be compared against the trace of
+//! the same program running on the Cairo VM to find where both executions diverge.
+//!
+//! The Cairo VM executes CASM, not Sierra, therefore its trace is recovered by mapping the program
+//! counters to the statements whose CASM code starts there (see [`StatementTrace::from_casm_pcs`]).
+//! Some statements don't generate any CASM code (ex. `branch_align` or `drop`), so they can't appear
+//! in the VM trace and are ignored when comparing traces.
+//!
+//! When values are traced, every step also records the branch its statement took and the values of
+//! its integer and felt outputs. Branches are reported once the statement has been executed, which
+//! for function calls happens after the callee's steps, so they're matched to their steps using a
+//! stack of the statements still pending.
+//!
+//! The trace is kept per thread and cleared every time a function is invoked.
+
+use crate::metadata::statement_trace::{
+    STATEMENT_TRACE_BRANCH, STATEMENT_TRACE_RETURN, STATEMENT_TRACE_SIGNED_VALUE,
+    STATEMENT_TRACE_STATEMENT, STATEMENT_TRACE_UNSIGNED_VALUE,
+};
+use cairo_lang_sierra::program::StatementIdx;
+use starknet_types_core::felt::Felt;
+use std::{
+    cell::RefCell,
+    fmt::{self, Display},
+};
+
+thread_local! {
+    static STATEMENT_TRACE: RefCell<TraceState> = RefCell::new(TraceState::default());
+}
+
+#[derive(Default)]
+struct TraceState {
+    steps: Vec<TraceStep>,
+    /// Positions of the invocation steps whose branch hasn't been reported yet.
+    pending: Vec<usize>,
+    /// Position of the last step whose branch was reported, which receives the output values.
+    last_branch: Option<usize>,
+}
+
+extern "C" fn record_statement_trace(
+    kind: u64,
+    statement_idx: u64,
+    index: u64,
+    l0: u64,
+    l1: u64,
+    l2: u64,
+    l3: u64,
+) {
+    STATEMENT_TRACE.with(|trace| {
+        let state = &mut *trace.borrow_mut();
+        match kind {
+            STATEMENT_TRACE_STATEMENT | STATEMENT_TRACE_RETURN => {
+                if kind == STATEMENT_TRACE_STATEMENT {
+                    state.pending.push(state.steps.len());
+                }
+                state.steps.push(TraceStep {
+                    statement_idx: StatementIdx(statement_idx as usize),
+                    branch: None,
+                    outputs: Vec::new(),
+                });
+            }
+            STATEMENT_TRACE_BRANCH => {
+                state.last_branch = state.pending.pop();
+                if let Some(position) = state.last_branch {
+                    debug_assert_eq!(
+                        state.steps[position].statement_idx.0,
+                        statement_idx as usize
+                    );
+                    state.steps[position].branch = Some(index as usize);
+                }
+            }
+            STATEMENT_TRACE_UNSIGNED_VALUE | STATEMENT_TRACE_SIGNED_VALUE => {
+                let limbs = [l0, l1, l2, l3];
+                let value = if kind == STATEMENT_TRACE_SIGNED_VALUE && (l3 as i64) < 0 {
+                    // Negate the two's complement to get the magnitude.
+                    let mut carry = true;
+                    let limbs = limbs.map(|limb| {
+                        let (limb, overflow) = (!limb).overflowing_add(carry as u64);
+                        carry = overflow;
+                        limb
+                    });
+                    -felt_from_limbs(limbs)
+                } else {
+                    felt_from_limbs(limbs)
+                };
+
+                if let Some(position) = state.last_branch {
+                    state.steps[position].outputs.push((index as usize, value));
+                }
+            }
+            _ => {}
+        }
+    });
+}
+
+fn felt_from_limbs(limbs: [u64; 4]) -> Felt {
+    let mut bytes = [0u8; 32];
+    for (chunk, limb) in bytes.chunks_exact_mut(8).zip(limbs) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+
+    Felt::from_bytes_le(&bytes)
+}
+
+/// Point the statement trace hook global of a compiled program to this module's trace.
+///
+/// # Safety
+///
+/// The pointer must point to the hook global of a program compiled with statement tracing enabled.
+pub(crate) unsafe fn install_statement_trace_hook(hook_ptr: *mut u64) {
+    *hook_ptr =
+        record_statement_trace as extern "C" fn(u64, u64, u64, u64, u64, u64, u64) as usize as u64;
+}
+
+/// Discard the trace of the current thread.
+pub(crate) fn clear_statement_trace() {
+    STATEMENT_TRACE.with(|trace| *trace.borrow_mut() = TraceState::default());
+}
+
+/// A single executed statement.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TraceStep {
+    pub statement_idx: StatementIdx,
+    /// The branch taken by the statement, if it was traced.
+    pub branch: Option<usize>,
+    /// The values of the statement's integer and felt outputs in the branch taken, by their
+    /// position, if they were traced.
+    pub outputs: Vec<(usize, Felt)>,
+}
+
+impl TraceStep {
+    /// Return whether both steps executed the same statement, took the same branch and produced
+    /// the same outputs. Branches and outputs which are missing on either side aren't compared.
+    pub fn matches(&self, other: &Self) -> bool {
+        self.statement_idx == other.statement_idx
+            && match (self.branch, other.branch) {
+                (Some(lhs), Some(rhs)) => lhs == rhs,
+                _ => true,
+            }
+            && self.outputs.iter().all(|(idx, value)| {
+                other
+                    .outputs
+                    .iter()
+                    .find(|(other_idx, _)| other_idx == idx)
+                    .map_or(true, |(_, other_value)| other_value == value)
+            })
+    }
+}
+
+impl Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.statement_idx)?;
+        if let Some(branch) = self.branch {
+            write!(f, " branch={branch}")?;
+        }
+        for (output_idx, value) in &self.outputs {
+            write!(f, " {output_idx}={value}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The sequence of executed statements.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StatementTrace {
+    pub steps: Vec<TraceStep>,
+}
+
+impl StatementTrace {
+    /// Take the trace recorded by the last invocation in the current thread.
+    pub fn take() -> Self {
+        Self {
+            steps: STATEMENT_TRACE
+                .with(|trace| std::mem::take(&mut *trace.borrow_mut()))
+                .steps,
+        }
+    }
+
+    /// Build a trace from the program counters of a Cairo VM run.
+    ///
+    /// The offsets are the CASM code offsets of every Sierra statement, as found in the CASM
+    /// program's debug info, and the program counters must be relative to the start of the
+    /// program's code. Program counters which don't point to the start of a statement are skipped.
+    pub fn from_casm_pcs(code_offsets: &[usize], pcs: impl IntoIterator<Item = usize>) -> Self {
+        Self {
+            steps: pcs
+                .into_iter()
+                .filter_map(|pc| {
+                    Some(TraceStep {
+                        statement_idx: Self::statement_at_pc(code_offsets, pc)?,
+                        branch: None,
+                        outputs: Vec::new(),
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Return the statement whose CASM code starts at the program counter, if any (see
+    /// [`Self::from_casm_pcs`]).
+    pub fn statement_at_pc(code_offsets: &[usize], pc: usize) -> Option<StatementIdx> {
+        // Statements without code share their offset with the next one, so the statement starting
+        // at `pc` is the last one with that offset.
+        let statement_idx = code_offsets.partition_point(|offset| *offset <= pc);
+        (statement_idx > 0 && code_offsets[statement_idx - 1] == pc)
+            .then(|| StatementIdx(statement_idx - 1))
+    }
+
+    /// Return the statements of the trace which generate CASM code, according to the offsets (see
+    /// [`Self::from_casm_pcs`]), along with their positions in the trace.
+    fn steps_with_code<'a>(
+        &'a self,
+        code_offsets: &'a [usize],
+    ) -> impl Iterator<Item = (usize, &'a TraceStep)> + 'a {
+        self.steps
+            .iter()
+            .enumerate()
+            .filter(|(_, TraceStep { statement_idx, .. })| {
+                match (
+                    code_offsets.get(statement_idx.0),
+                    code_offsets.get(statement_idx.0 + 1),
+                ) {
+                    (Some(offset), Some(next_offset)) => next_offset > offset,
+                    (Some(_), None) => true,
+                    _ => false,
+                }
+            })
+    }
+
+    /// Find the first statement where this (native) trace and a trace built using
+    /// [`Self::from_casm_pcs`] diverge, if any. Steps diverge when they don't
+    /// [match](TraceStep::matches).
+    pub fn find_divergence(&self, vm_trace: &Self, code_offsets: &[usize]) -> Option<Divergence> {
+        let mut native_steps = self.steps_with_code(code_offsets);
+        let mut vm_steps = vm_trace.steps_with_code(code_offsets);
+
+        let mut last_common = None;
+        loop {
+            match (native_steps.next(), vm_steps.next()) {
+                (None, None) => return None,
+                (Some((native_position, native)), Some((vm_position, vm)))
+                    if native.matches(vm) =>
+                {
+                    last_common = Some((native_position, vm_position));
+                }
+                (native, vm) => {
+                    return Some(Divergence {
+                        last_common,
+                        native: native.map(|(position, _)| position),
+                        vm: vm.map(|(position, _)| position),
+                    })
+                }
+            }
+        }
+    }
+}
+
+impl Display for StatementTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            writeln!(f, "{step}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Where two traces diverge, as positions within their steps.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Divergence {
+    /// The positions of the last statement which both traces had in common, in the native and VM
+    /// traces respectively.
+    pub last_common: Option<(usize, usize)>,
+    /// The position of the first divergent step in the native trace, or `None` if it ended.
+    pub native: Option<usize>,
+    /// The position of the first divergent step in the VM trace, or `None` if it ended.
+    pub vm: Option<usize>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::NativeContext, executor::JitNativeExecutor,
+        metadata::statement_trace::StatementTraceMode, utils::test::load_cairo, OptLevel,
+    };
+
+    fn trace(statements: &[usize]) -> StatementTrace {
+        StatementTrace {
+            steps: statements
+                .iter()
+                .map(|&statement_idx| TraceStep {
+                    statement_idx: StatementIdx(statement_idx),
+                    branch: None,
+                    outputs: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn casm_pcs() {
+        // Statement 1 doesn't generate any code.
+        let code_offsets = [0, 2, 2, 5];
+
+        assert_eq!(
+            StatementTrace::from_casm_pcs(&code_offsets, [0, 1, 2, 3, 4, 5, 2]),
+            trace(&[0, 2, 3, 2])
+        );
+    }
+
+    #[test]
+    fn divergence() {
+        let code_offsets = [0, 2, 2, 5, 6];
+
+        let native_trace = trace(&[0, 1, 2, 3, 4]);
+        assert_eq!(
+            native_trace.find_divergence(&trace(&[0, 2, 3, 4]), &code_offsets),
+            None
+        );
+        assert_eq!(
+            native_trace.find_divergence(&trace(&[0, 2, 4]), &code_offsets),
+            Some(Divergence {
+                last_common: Some((2, 1)),
+                native: Some(3),
+                vm: Some(2),
+            })
+        );
+        assert_eq!(
+            native_trace.find_divergence(&trace(&[0, 2]), &code_offsets),
+            Some(Divergence {
+                last_common: Some((2, 1)),
+                native: Some(3),
+                vm: None,
+            })
+        );
+    }
+
+    #[test]
+    fn divergent_outputs() {
+        let code_offsets = [0, 2, 5];
+
+        let mut native_trace = trace(&[0, 1, 2]);
+        native_trace.steps[1].branch = Some(0);
+        native_trace.steps[1].outputs = vec![(0, Felt::from(7)), (1, Felt::from(1))];
+
+        // Missing branches and outputs aren't compared.
+        let mut vm_trace = trace(&[0, 1, 2]);
+        vm_trace.steps[1].outputs = vec![(0, Felt::from(7))];
+        assert_eq!(native_trace.find_divergence(&vm_trace, &code_offsets), None);
+
+        vm_trace.steps[1].outputs = vec![(0, Felt::from(8))];
+        assert_eq!(
+            native_trace.find_divergence(&vm_trace, &code_offsets),
+            Some(Divergence {
+                last_common: Some((0, 0)),
+                native: Some(1),
+                vm: Some(1),
+            })
+        );
+
+        vm_trace.steps[1].outputs = Vec::new();
+        vm_trace.steps[1].branch = Some(1);
+        assert!(native_trace
+            .find_divergence(&vm_trace, &code_offsets)
+            .is_some());
+    }
+
+    #[test]
+    fn trace_program() {
+        let (module_name, program) = load_cairo! {
+            fn run_test() -> u32 {
+                let x: u32 = 3;
+                x + 4
+            }
+        };
+
+        let native_context = NativeContext::new()
+            .with_statement_trace(Some(StatementTraceMode::StatementsAndValues));
+        let native_module = native_context.compile(&program, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(native_module, OptLevel::None);
+
+        let entry_point = program
+            .funcs
+            .iter()
+            .find(|x| {
+                x.id.debug_name.as_deref()
+                    == Some(format!("{0}::{0}::run_test", module_name).as_str())
+            })
+            .unwrap();
+        executor.invoke_dynamic(&entry_point.id, &[], None).unwrap();
+
+        let trace = StatementTrace::take();
+        assert_eq!(trace.steps[0].statement_idx, entry_point.entry_point);
+        // The constant and the result of the addition are reported as outputs.
+        assert!(trace
+            .steps
+            .iter()
+            .any(|step| step.outputs.contains(&(0, Felt::from(3)))));
+        assert!(trace.steps.iter().any(|step| step.branch == Some(0)
+            && step
+                .outputs
+                .iter()
+                .any(|(_, value)| *value == Felt::from(7))));
+    }
+}
@@ -2,8 +2,9 @@
 //!
 //! A key value storage for values whose type implement Copy. The key is always a felt.
 //!
-//! This type is represented as a pointer to a heap allocated Rust hashmap, interacted through the runtime functions to
-//! insert and get elements.
+//! This type is represented as a pointer to a heap allocated `FeltDict` from the runtime library,
+//! interacted through the runtime functions to insert and get elements. Values are stored inline in
+//! an arena owned by the dictionary and are all freed at once when the dictionary is dropped.
 
 use super::WithSelf;
 use crate::{error::Result, metadata::MetadataStorage};
@@ -2,6 +2,8 @@
 
 use crate::{
     debug_info::{DebugInfo, DebugLocations},
+    executor::JitDebugOptions,
+    ffi::create_execution_engine,
     metadata::MetadataStorage,
     types::{felt252::PRIME, TypeBuilder},
     OptLevel,
@@ -28,7 +30,6 @@ use std::{
     fmt::{self, Display},
     ops::Neg,
     path::Path,
-    ptr::NonNull,
     sync::Arc,
 };
 use thiserror::Error;
@@ -165,6 +166,20 @@ pub fn find_entry_point_by_idx(
         .find(|x| x.id.id == entry_point_idx as u64)
 }
 
+/// Returns the function which contains the given statement, if any.
+pub(crate) fn find_statement_function(
+    program: &Program,
+    statement_idx: StatementIdx,
+) -> Option<&GenFunction<StatementIdx>> {
+    // Functions are laid out contiguously, therefore a statement belongs to the last function whose
+    // entry point precedes it.
+    program
+        .funcs
+        .iter()
+        .filter(|function| function.entry_point.0 <= statement_idx.0)
+        .max_by_key(|function| function.entry_point.0)
+}
+
 /// Given a string representing a function name, searches in the program for the id corresponding to said function, and returns a reference to it.
 #[track_caller]
 pub fn find_function_id<'a>(program: &'a Program, function_name: &str) -> &'a FunctionId {
@@ -248,6 +263,59 @@ pub fn create_engine(
     engine
 }
 
+/// Same as [`create_engine`], but choosing the debugger and profiler integrations of the generated
+/// code.
+pub fn create_engine_with_debug_options(
+    module: &Module,
+    _metadata: &MetadataStorage,
+    opt_level: OptLevel,
+    debug_options: &JitDebugOptions,
+) -> Result<crate::executor::ExecutionEngine, crate::error::Error> {
+    // Create the JIT engine.
+    let engine = create_execution_engine(
+        module,
+        match opt_level {
+            OptLevel::None => 0,
+            OptLevel::Less => 1,
+            OptLevel::Default => 2,
+            OptLevel::Aggressive => 3,
+        },
+        debug_options.gdb_jit_interface,
+        debug_options.perf_jitdump,
+    )?;
+
+    #[cfg(feature = "with-runtime")]
+    register_runtime_symbols_in(&engine);
+
+    #[cfg(feature = "with-debug-utils")]
+    _metadata
+        .get::<crate::metadata::debug_utils::DebugUtils>()
+        .unwrap()
+        .register_impls_in(&engine);
+
+    Ok(engine)
+}
+
+/// An execution engine which the compiled code's external symbols can be registered to.
+pub(crate) trait RegisterSymbol {
+    /// # Safety
+    ///
+    /// The pointer must be valid for every use the compiled code makes of the symbol.
+    unsafe fn register_symbol(&self, name: &str, ptr: *mut ());
+}
+
+impl RegisterSymbol for ExecutionEngine {
+    unsafe fn register_symbol(&self, name: &str, ptr: *mut ()) {
+        ExecutionEngine::register_symbol(self, name, ptr)
+    }
+}
+
+impl RegisterSymbol for crate::executor::ExecutionEngine {
+    unsafe fn register_symbol(&self, name: &str, ptr: *mut ()) {
+        crate::executor::ExecutionEngine::register_symbol(self, name, ptr)
+    }
+}
+
 pub fn run_pass_manager(context: &Context, module: &mut Module) -> Result<(), Error> {
     let pass_manager = PassManager::new(context);
     pass_manager.enable_verifier(true);
@@ -262,8 +330,22 @@ pub fn run_pass_manager(context: &Context, module: &mut Module) -> Result<(), Er
     pass_manager.run(module)
 }
 
+/// Attach debug info scopes to the (already lowered) functions, so that their source locations are
+/// translated into DWARF line tables and function DIEs.
+pub fn run_debug_info_pass(context: &Context, module: &mut Module) -> Result<(), Error> {
+    let pass_manager = PassManager::new(context);
+    pass_manager.enable_verifier(true);
+    pass_manager.add_pass(crate::ffi::create_di_scope_for_llvm_func_op());
+    pass_manager.run(module)
+}
+
 #[cfg(feature = "with-runtime")]
 pub fn register_runtime_symbols(engine: &ExecutionEngine) {
+    register_runtime_symbols_in(engine)
+}
+
+#[cfg(feature = "with-runtime")]
+pub(crate) fn register_runtime_symbols_in(engine: &impl RegisterSymbol) {
     unsafe {
         engine.register_symbol(
             "cairo_native__libfunc__debug__print",
@@ -318,8 +400,8 @@ pub fn register_runtime_symbols(engine: &ExecutionEngine) {
 
         engine.register_symbol(
             "cairo_native__alloc_dict",
-            cairo_native_runtime::cairo_native__alloc_dict as *const fn() -> *mut std::ffi::c_void
-                as *mut (),
+            cairo_native_runtime::cairo_native__alloc_dict
+                as *const fn(u64, u64) -> *mut std::ffi::c_void as *mut (),
         );
 
         engine.register_symbol(
@@ -338,11 +420,20 @@ pub fn register_runtime_symbols(engine: &ExecutionEngine) {
         engine.register_symbol(
             "cairo_native__dict_insert",
             cairo_native_runtime::cairo_native__dict_insert
-                as *const fn(
-                    *mut std::ffi::c_void,
-                    &[u8; 32],
-                    NonNull<std::ffi::c_void>,
-                ) -> *mut std::ffi::c_void as *mut (),
+                as *const fn(*mut std::ffi::c_void, &[u8; 32]) -> *mut std::ffi::c_void
+                as *mut (),
+        );
+
+        engine.register_symbol(
+            "cairo_native__dict_finalize_access",
+            cairo_native_runtime::cairo_native__dict_finalize_access
+                as *const fn(*mut std::ffi::c_void) as *mut (),
+        );
+
+        engine.register_symbol(
+            "cairo_native__dict_repeated_accesses",
+            cairo_native_runtime::cairo_native__dict_repeated_accesses
+                as *const fn(*const std::ffi::c_void) -> u64 as *mut (),
         );
     }
 }
@@ -825,7 +916,7 @@ pub mod test {
                 entry_point_id,
                 args,
                 Some(u128::MAX),
-                TestSyscallHandler,
+                &mut TestSyscallHandler,
             )
             .unwrap()
     }
@@ -52,6 +52,12 @@ pub enum JitValue {
         #[educe(PartialEq(ignore))]
         debug_name: Option<String>,
     },
+    /// A squashed dictionary: the final value and access log of every key, ordered by key.
+    SquashedFelt252Dict {
+        value: Vec<SquashedDictEntry>,
+        #[educe(PartialEq(ignore))]
+        debug_name: Option<String>,
+    },
     Uint8(u8),
     Uint16(u16),
     Uint32(u32),
@@ -76,6 +82,28 @@ pub enum JitValue {
     Null,
 }
 
+/// A single key of a squashed dictionary.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SquashedDictEntry {
+    pub key: Felt,
+    /// Every access to the key made by the program, in order.
+    pub accesses: Vec<DictAccess>,
+    /// The value after the last access.
+    pub value: JitValue,
+}
+
+/// An access to a dictionary's key: its value before and after it.
+///
+/// Values of `Nullable<T>` dictionaries are `None`: they point to memory which the program may
+/// have freed after overwriting them, so only the entry's final value can be read back.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DictAccess {
+    pub prev_value: Option<JitValue>,
+    pub new_value: Option<JitValue>,
+}
+
 // Conversions
 
 impl From<Felt> for JitValue {
@@ -363,30 +391,30 @@ impl JitValue {
                 }
                 Self::Felt252Dict { value: map, .. } => {
                     if let CoreTypeConcrete::Felt252Dict(info) = Self::resolve_type(ty, registry) {
-                        let elem_ty = registry.get_type(&info.ty).unwrap();
-                        let elem_layout = elem_ty.layout(registry).unwrap().pad_to_align();
+                        #[cfg(not(feature = "with-runtime"))]
+                        unimplemented!("enable the `with-runtime` feature to use felt252 dicts");
 
-                        let mut value_map = HashMap::<[u8; 32], NonNull<std::ffi::c_void>>::new();
+                        #[cfg(feature = "with-runtime")]
+                        {
+                            let elem_ty = registry.get_type(&info.ty).unwrap();
+                            let elem_layout = elem_ty.layout(registry).unwrap();
 
-                        // next key must be called before next_value
+                            let mut value_map = cairo_native_runtime::FeltDict::new(elem_layout);
 
-                        for (key, value) in map.iter() {
-                            let key = key.to_bytes_le();
-                            let value = value.to_jit(arena, registry, &info.ty)?;
+                            for (key, value) in map.iter() {
+                                let key = key.to_bytes_le();
+                                let value = value.to_jit(arena, registry, &info.ty)?;
 
-                            let value_malloc_ptr =
-                                NonNull::new(libc::malloc(elem_layout.size())).unwrap();
+                                let value_ptr = value_map.insert(&key);
+                                std::ptr::copy_nonoverlapping(
+                                    value.cast::<u8>().as_ptr(),
+                                    value_ptr.cast().as_ptr(),
+                                    value_map.layout().size(),
+                                );
+                            }
 
-                            std::ptr::copy_nonoverlapping(
-                                value.cast::<u8>().as_ptr(),
-                                value_malloc_ptr.cast().as_ptr(),
-                                elem_layout.size(),
-                            );
-
-                            value_map.insert(key, value_malloc_ptr);
+                            NonNull::new_unchecked(Box::into_raw(Box::new(value_map))).cast()
                         }
-
-                        NonNull::new_unchecked(Box::into_raw(Box::new(value_map))).cast()
                     } else {
                         Err(Error::UnexpectedValue(format!(
                             "expected value of type {:?} but got a felt dict",
@@ -482,6 +510,63 @@ impl JitValue {
 
                     ptr
                 }
+                Self::SquashedFelt252Dict { value: entries, .. } => {
+                    if let CoreTypeConcrete::SquashedFelt252Dict(info) =
+                        Self::resolve_type(ty, registry)
+                    {
+                        #[cfg(not(feature = "with-runtime"))]
+                        unimplemented!("enable the `with-runtime` feature to use felt252 dicts");
+
+                        #[cfg(feature = "with-runtime")]
+                        {
+                            let elem_ty = registry.get_type(&info.ty).unwrap();
+                            let elem_layout = elem_ty.layout(registry).unwrap();
+
+                            let mut value_map = cairo_native_runtime::FeltDict::new(elem_layout);
+                            let size = value_map.layout().size();
+
+                            // See `DictAccess` for why `Nullable` values aren't logged.
+                            let is_nullable = matches!(elem_ty, CoreTypeConcrete::Nullable(_));
+                            let log_value = |value: &Option<Self>| -> Result<Vec<u8>, Error> {
+                                Ok(match value {
+                                    Some(value) if !is_nullable => {
+                                        let ptr = value.to_jit(arena, registry, &info.ty)?;
+                                        std::slice::from_raw_parts(ptr.cast::<u8>().as_ptr(), size)
+                                            .to_vec()
+                                    }
+                                    _ => vec![0; size],
+                                })
+                            };
+
+                            for entry in entries {
+                                let key = entry.key.to_bytes_le();
+                                let value = entry.value.to_jit(arena, registry, &info.ty)?;
+
+                                let value_ptr = value_map.insert(&key);
+                                std::ptr::copy_nonoverlapping(
+                                    value.cast::<u8>().as_ptr(),
+                                    value_ptr.cast().as_ptr(),
+                                    size,
+                                );
+
+                                for access in &entry.accesses {
+                                    value_map.record_access(
+                                        &key,
+                                        &log_value(&access.prev_value)?,
+                                        &log_value(&access.new_value)?,
+                                    );
+                                }
+                            }
+
+                            NonNull::new_unchecked(Box::into_raw(Box::new(value_map))).cast()
+                        }
+                    } else {
+                        Err(Error::UnexpectedValue(format!(
+                            "expected value of type {:?} but got a squashed felt dict",
+                            type_id.debug_name
+                        )))?
+                    }
+                }
                 Self::Secp256K1Point { .. } => todo!(),
                 Self::Secp256R1Point { .. } => todo!(),
                 Self::Null => {
@@ -666,23 +751,79 @@ impl JitValue {
                         debug_name: type_id.debug_name.as_ref().map(|x| x.to_string()),
                     }
                 }
-                CoreTypeConcrete::Felt252Dict(info)
-                | CoreTypeConcrete::SquashedFelt252Dict(info) => {
-                    let map = Box::from_raw(
-                        ptr.cast::<HashMap<[u8; 32], NonNull<std::ffi::c_void>>>()
-                            .as_ptr(),
-                    );
+                CoreTypeConcrete::Felt252Dict(info) => {
+                    #[cfg(not(feature = "with-runtime"))]
+                    unimplemented!("enable the `with-runtime` feature to use felt252 dicts");
+
+                    #[cfg(feature = "with-runtime")]
+                    {
+                        let map =
+                            Box::from_raw(ptr.cast::<cairo_native_runtime::FeltDict>().as_ptr());
+
+                        let mut output_map = HashMap::with_capacity(map.len());
 
-                    let mut output_map = HashMap::with_capacity(map.len());
+                        for (key, val_ptr) in map.iter() {
+                            let key = Felt::from_bytes_le(key);
+                            output_map
+                                .insert(key, Self::from_jit(val_ptr.cast(), &info.ty, registry));
+                        }
 
-                    for (key, val_ptr) in map.iter() {
-                        let key = Felt::from_bytes_le(key);
-                        output_map.insert(key, Self::from_jit(val_ptr.cast(), &info.ty, registry));
+                        JitValue::Felt252Dict {
+                            value: output_map,
+                            debug_name: type_id.debug_name.as_ref().map(|x| x.to_string()),
+                        }
                     }
+                }
+                CoreTypeConcrete::SquashedFelt252Dict(info) => {
+                    #[cfg(not(feature = "with-runtime"))]
+                    unimplemented!("enable the `with-runtime` feature to use felt252 dicts");
+
+                    #[cfg(feature = "with-runtime")]
+                    {
+                        let map =
+                            Box::from_raw(ptr.cast::<cairo_native_runtime::FeltDict>().as_ptr());
+
+                        // See `DictAccess` for why `Nullable` values aren't logged.
+                        let is_nullable = matches!(
+                            registry.get_type(&info.ty).unwrap(),
+                            CoreTypeConcrete::Nullable(_)
+                        );
+                        // The log isn't aligned, so values are copied before reading them.
+                        let scratch = Bump::new();
+                        let read_logged = |value: &[u8]| {
+                            (!is_nullable).then(|| {
+                                let ptr = scratch.alloc_layout(map.layout());
+                                std::ptr::copy_nonoverlapping(
+                                    value.as_ptr(),
+                                    ptr.as_ptr(),
+                                    value.len(),
+                                );
+                                Self::from_jit(ptr.cast(), &info.ty, registry)
+                            })
+                        };
 
-                    JitValue::Felt252Dict {
-                        value: output_map,
-                        debug_name: type_id.debug_name.as_ref().map(|x| x.to_string()),
+                        let mut accesses = HashMap::<[u8; 32], Vec<DictAccess>>::new();
+                        for (key, prev_value, new_value) in map.access_log() {
+                            accesses.entry(*key).or_default().push(DictAccess {
+                                prev_value: read_logged(prev_value),
+                                new_value: read_logged(new_value),
+                            });
+                        }
+
+                        let mut entries = map
+                            .iter()
+                            .map(|(key, val_ptr)| SquashedDictEntry {
+                                key: Felt::from_bytes_le(key),
+                                accesses: accesses.remove(key).unwrap_or_default(),
+                                value: Self::from_jit(val_ptr.cast(), &info.ty, registry),
+                            })
+                            .collect::<Vec<_>>();
+                        entries.sort_by(|a, b| a.key.cmp(&b.key));
+
+                        JitValue::SquashedFelt252Dict {
+                            value: entries,
+                            debug_name: type_id.debug_name.as_ref().map(|x| x.to_string()),
+                        }
                     }
                 }
                 CoreTypeConcrete::Felt252DictEntry(_) => {
@@ -0,0 +1,620 @@
+//! # Cairo VM comparison
+//!
+//! The Cairo VM (as run by `cairo-lang-runner`) is the reference implementation of Cairo. This
+//! module decodes the results of a function run on the VM into the [`JitValue`]s the native
+//! execution of the same function returns, so that both executions can be compared.
+//!
+//! The VM returns values as a flat list of felts, where every type has a fixed size (ex. arrays
+//! are a pair of pointers into the VM's memory and enums are a tag followed by the variant's data,
+//! which is aligned to the end).
+
+use crate::{
+    error::{Error, Result},
+    execution_result::ExecutionResult,
+    types::{
+        felt252::{HALF_PRIME, PRIME},
+        TypeBuilder,
+    },
+    values::JitValue,
+};
+use cairo_felt::Felt252;
+use cairo_lang_runner::{Arg, RunResultStarknet, RunResultValue};
+use cairo_lang_sierra::{
+    extensions::core::{CoreLibfunc, CoreType, CoreTypeConcrete},
+    ids::{ConcreteTypeId, FunctionId},
+    program_registry::ProgramRegistry,
+};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use starknet_types_core::felt::Felt;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+};
+
+/// A difference between the native and the VM executions of a function.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VmMismatch {
+    /// The functions returned different values. When a function panics, its return value contains
+    /// the panic data.
+    ReturnValue { native: JitValue, vm: JitValue },
+    /// The executions finished with different amounts of gas.
+    RemainingGas { native: u128, vm: u128 },
+}
+
+impl Display for VmMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VmMismatch::ReturnValue { native, vm } => {
+                write!(
+                    f,
+                    "the return values differ (native: {native:?}, VM: {vm:?})"
+                )
+            }
+            VmMismatch::RemainingGas { native, vm } => {
+                write!(f, "the remaining gas differs (native: {native}, VM: {vm})")
+            }
+        }
+    }
+}
+
+/// Compare the native execution of a function with its execution on the Cairo VM.
+///
+/// The remaining gas is only compared when both executions report it. An empty list means both
+/// executions match.
+pub fn compare_with_vm(
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    function_id: &FunctionId,
+    native_result: &ExecutionResult,
+    vm_result: &RunResultStarknet,
+) -> Result<Vec<VmMismatch>> {
+    let mut mismatches = Vec::new();
+
+    let vm_value = jit_value_from_vm(registry, function_id, &vm_result.value, &vm_result.memory)?;
+    if native_result.return_value != vm_value {
+        mismatches.push(VmMismatch::ReturnValue {
+            native: native_result.return_value.clone(),
+            vm: vm_value,
+        });
+    }
+
+    if let (Some(native), Some(vm)) = (native_result.remaining_gas, &vm_result.gas_counter) {
+        let vm = vm
+            .to_biguint()
+            .to_u128()
+            .ok_or_else(|| Error::UnexpectedValue("u128".to_string()))?;
+        if native != vm {
+            mismatches.push(VmMismatch::RemainingGas { native, vm });
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// Decode the value a function returned when run on the Cairo VM into the [`JitValue`] its native
+/// execution would return.
+///
+/// The memory is the VM's relocated memory after the run, which holds the data behind pointers.
+pub fn jit_value_from_vm(
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    function_id: &FunctionId,
+    value: &RunResultValue,
+    memory: &[Option<Felt252>],
+) -> Result<JitValue> {
+    let function = registry.get_function(function_id)?;
+    let ty = function
+        .signature
+        .ret_types
+        .last()
+        .ok_or_else(|| Error::SierraAssert("the function has no return types".to_string()))?;
+    let returns_panic = ty
+        .debug_name
+        .as_deref()
+        .is_some_and(|name| name.starts_with("core::panics::PanicResult"));
+
+    let mut decoder = VmValueMapper::new(registry, memory);
+
+    Ok(match value {
+        RunResultValue::Success(values) if returns_panic => {
+            let inner_ty = match registry.get_type(ty)? {
+                CoreTypeConcrete::Enum(info) => &info.variants[0],
+                _ => return Err(Error::UnexpectedValue(ty.to_string())),
+            };
+
+            JitValue::Enum {
+                tag: 0,
+                value: Box::new(decoder.decode(values, inner_ty)?),
+                debug_name: None,
+            }
+        }
+        RunResultValue::Success(values) => decoder.decode(values, ty)?,
+        RunResultValue::Panic(values) => JitValue::Enum {
+            tag: 1,
+            value: Box::new(JitValue::Struct {
+                fields: vec![
+                    JitValue::Struct {
+                        fields: Vec::new(),
+                        debug_name: None,
+                    },
+                    JitValue::Array(
+                        values
+                            .iter()
+                            .map(felt_from_vm)
+                            .map(JitValue::Felt252)
+                            .collect(),
+                    ),
+                ],
+                debug_name: None,
+            }),
+            debug_name: None,
+        },
+    })
+}
+
+/// Encode the arguments of a function into the [`Arg`]s the Cairo VM runner expects.
+///
+/// The arguments are the same ones the native executors take, that is, one for every parameter
+/// which is neither a builtin nor zero-sized.
+pub fn vm_args_from_jit(
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    function_id: &FunctionId,
+    args: &[JitValue],
+) -> Result<Vec<Arg>> {
+    let function = registry.get_function(function_id)?;
+    let mut encoder = VmValueMapper::new(registry, &[]);
+
+    let mut vm_args = Vec::new();
+    let mut args = args.iter();
+    for type_id in &function.signature.param_types {
+        let type_info = registry.get_type(type_id)?;
+        if type_info.is_builtin() || type_info.is_zst(registry) {
+            continue;
+        }
+
+        let arg = args
+            .next()
+            .ok_or_else(|| Error::MissingParameter(type_id.to_string()))?;
+        encoder.encode(arg, type_id, &mut vm_args)?;
+    }
+
+    Ok(vm_args)
+}
+
+fn felt_to_vm(value: &Felt) -> Felt252 {
+    Felt252::from_bytes_be(&value.to_bytes_be())
+}
+
+fn felt_from_vm(value: &Felt252) -> Felt {
+    Felt::from_bytes_le(&value.to_le_bytes())
+}
+
+/// Interpret a felt as a signed integer, where the upper half of the field holds the negatives.
+fn signed_from_vm(value: &Felt252) -> BigInt {
+    let value = value.to_bigint();
+    if value >= *HALF_PRIME {
+        value - BigInt::from(PRIME.clone())
+    } else {
+        value
+    }
+}
+
+struct VmValueMapper<'a> {
+    registry: &'a ProgramRegistry<CoreType, CoreLibfunc>,
+    memory: &'a [Option<Felt252>],
+    size_cache: HashMap<ConcreteTypeId, usize>,
+}
+
+impl<'a> VmValueMapper<'a> {
+    fn new(
+        registry: &'a ProgramRegistry<CoreType, CoreLibfunc>,
+        memory: &'a [Option<Felt252>],
+    ) -> Self {
+        Self {
+            registry,
+            memory,
+            size_cache: HashMap::new(),
+        }
+    }
+
+    /// Return the number of felts the VM uses to represent a value of the given type.
+    fn size(&mut self, ty: &ConcreteTypeId) -> Result<usize> {
+        if let Some(&size) = self.size_cache.get(ty) {
+            return Ok(size);
+        }
+
+        let registry = self.registry;
+        let size = match registry.get_type(ty)? {
+            CoreTypeConcrete::Array(_) => 2,
+            CoreTypeConcrete::Felt252(_)
+            | CoreTypeConcrete::Bytes31(_)
+            | CoreTypeConcrete::Uint128(_)
+            | CoreTypeConcrete::Uint64(_)
+            | CoreTypeConcrete::Uint32(_)
+            | CoreTypeConcrete::Uint16(_)
+            | CoreTypeConcrete::Uint8(_)
+            | CoreTypeConcrete::Sint128(_)
+            | CoreTypeConcrete::Sint64(_)
+            | CoreTypeConcrete::Sint32(_)
+            | CoreTypeConcrete::Sint16(_)
+            | CoreTypeConcrete::Sint8(_)
+            | CoreTypeConcrete::Box(_)
+            | CoreTypeConcrete::Nullable(_) => 1,
+            CoreTypeConcrete::Enum(info) => {
+                let mut max_size = 0;
+                for variant_ty in &info.variants {
+                    max_size = max_size.max(self.size(variant_ty)?);
+                }
+                1 + max_size
+            }
+            CoreTypeConcrete::Struct(info) => {
+                let mut size = 0;
+                for member_ty in &info.members {
+                    size += self.size(member_ty)?;
+                }
+                size
+            }
+            CoreTypeConcrete::NonZero(info) => self.size(&info.ty)?,
+            CoreTypeConcrete::Snapshot(info) => self.size(&info.ty)?,
+            CoreTypeConcrete::EcPoint(_) => 2,
+            CoreTypeConcrete::EcState(_) => 4,
+            _ => return Err(Error::UnexpectedValue(ty.to_string())),
+        };
+        self.size_cache.insert(ty.clone(), size);
+
+        Ok(size)
+    }
+
+    /// Read a range of the VM's memory.
+    fn load(&self, since: usize, until: usize) -> Result<Vec<Felt252>> {
+        self.memory
+            .get(since..until)
+            .and_then(|data| data.iter().cloned().collect())
+            .ok_or_else(|| Error::SierraAssert("invalid VM memory range".to_string()))
+    }
+
+    /// Decode the felts representing a value of the given type.
+    fn decode(&mut self, values: &[Felt252], ty: &ConcreteTypeId) -> Result<JitValue> {
+        let size = self.size(ty)?;
+        if values.len() != size {
+            return Err(Error::UnexpectedValue(ty.to_string()));
+        }
+
+        let unexpected = || Error::UnexpectedValue(ty.to_string());
+        let to_usize = |value: &Felt252| value.to_biguint().to_usize().ok_or_else(unexpected);
+
+        let registry = self.registry;
+        Ok(match registry.get_type(ty)? {
+            CoreTypeConcrete::Array(info) => {
+                let since_ptr = to_usize(&values[0])?;
+                let until_ptr = to_usize(&values[1])?;
+
+                let elem_size = self.size(&info.ty)?;
+                if until_ptr < since_ptr || (elem_size == 0 && until_ptr != since_ptr) {
+                    return Err(unexpected());
+                }
+
+                let data = self.load(since_ptr, until_ptr)?;
+                let mut elems = Vec::new();
+                for elem in data.chunks(elem_size.max(1)) {
+                    elems.push(self.decode(elem, &info.ty)?);
+                }
+
+                JitValue::Array(elems)
+            }
+            CoreTypeConcrete::Felt252(_) => JitValue::Felt252(felt_from_vm(&values[0])),
+            CoreTypeConcrete::Bytes31(_) => {
+                JitValue::Bytes31(values[0].to_be_bytes()[1..].try_into().unwrap())
+            }
+            CoreTypeConcrete::Uint128(_) => {
+                JitValue::Uint128(values[0].to_biguint().to_u128().ok_or_else(unexpected)?)
+            }
+            CoreTypeConcrete::Uint64(_) => {
+                JitValue::Uint64(values[0].to_biguint().to_u64().ok_or_else(unexpected)?)
+            }
+            CoreTypeConcrete::Uint32(_) => {
+                JitValue::Uint32(values[0].to_biguint().to_u32().ok_or_else(unexpected)?)
+            }
+            CoreTypeConcrete::Uint16(_) => {
+                JitValue::Uint16(values[0].to_biguint().to_u16().ok_or_else(unexpected)?)
+            }
+            CoreTypeConcrete::Uint8(_) => {
+                JitValue::Uint8(values[0].to_biguint().to_u8().ok_or_else(unexpected)?)
+            }
+            CoreTypeConcrete::Sint128(_) => JitValue::Sint128(
+                signed_from_vm(&values[0])
+                    .to_i128()
+                    .ok_or_else(unexpected)?,
+            ),
+            CoreTypeConcrete::Sint64(_) => {
+                JitValue::Sint64(signed_from_vm(&values[0]).to_i64().ok_or_else(unexpected)?)
+            }
+            CoreTypeConcrete::Sint32(_) => {
+                JitValue::Sint32(signed_from_vm(&values[0]).to_i32().ok_or_else(unexpected)?)
+            }
+            CoreTypeConcrete::Sint16(_) => {
+                JitValue::Sint16(signed_from_vm(&values[0]).to_i16().ok_or_else(unexpected)?)
+            }
+            CoreTypeConcrete::Sint8(_) => {
+                JitValue::Sint8(signed_from_vm(&values[0]).to_i8().ok_or_else(unexpected)?)
+            }
+            CoreTypeConcrete::Enum(info) => {
+                // Enums with more than two variants are encoded using the jump offsets of the
+                // match's CASM code.
+                let mut tag = to_usize(&values[0])?;
+                if info.variants.len() > 2 {
+                    tag = info
+                        .variants
+                        .len()
+                        .checked_sub((tag + 1) >> 1)
+                        .ok_or_else(unexpected)?;
+                }
+                let variant_ty = info.variants.get(tag).ok_or_else(unexpected)?;
+
+                // The variant's data is aligned to the end of the enum.
+                let variant_size = self.size(variant_ty)?;
+                JitValue::Enum {
+                    tag,
+                    value: Box::new(self.decode(&values[size - variant_size..], variant_ty)?),
+                    debug_name: ty.debug_name.as_deref().map(String::from),
+                }
+            }
+            CoreTypeConcrete::Struct(info) => {
+                let mut values = values;
+                let mut fields = Vec::with_capacity(info.members.len());
+                for member_ty in &info.members {
+                    let data;
+                    (data, values) = values.split_at(self.size(member_ty)?);
+                    fields.push(self.decode(data, member_ty)?);
+                }
+
+                JitValue::Struct {
+                    fields,
+                    debug_name: ty.debug_name.as_deref().map(String::from),
+                }
+            }
+            CoreTypeConcrete::Box(info) => {
+                let ptr = to_usize(&values[0])?;
+                let inner_size = self.size(&info.ty)?;
+                let data = self.load(ptr, ptr + inner_size)?;
+                self.decode(&data, &info.ty)?
+            }
+            CoreTypeConcrete::Nullable(info) => match to_usize(&values[0])? {
+                0 => JitValue::Null,
+                ptr => {
+                    // Zero-sized values are represented by a dummy non-null pointer.
+                    let inner_size = self.size(&info.ty)?;
+                    let data = if inner_size == 0 {
+                        Vec::new()
+                    } else {
+                        self.load(ptr, ptr + inner_size)?
+                    };
+                    self.decode(&data, &info.ty)?
+                }
+            },
+            CoreTypeConcrete::NonZero(info) => self.decode(values, &info.ty)?,
+            CoreTypeConcrete::Snapshot(info) => self.decode(values, &info.ty)?,
+            CoreTypeConcrete::EcPoint(_) => {
+                JitValue::EcPoint(felt_from_vm(&values[0]), felt_from_vm(&values[1]))
+            }
+            CoreTypeConcrete::EcState(_) => JitValue::EcState(
+                felt_from_vm(&values[0]),
+                felt_from_vm(&values[1]),
+                felt_from_vm(&values[2]),
+                felt_from_vm(&values[3]),
+            ),
+            _ => return Err(unexpected()),
+        })
+    }
+
+    /// Encode a value of the given type into the VM's arguments. Arrays are passed as a single
+    /// argument, which is expanded into a pair of pointers by the runner.
+    fn encode(&mut self, value: &JitValue, ty: &ConcreteTypeId, args: &mut Vec<Arg>) -> Result<()> {
+        let unexpected = || Error::UnexpectedValue(ty.to_string());
+
+        let registry = self.registry;
+        match (registry.get_type(ty)?, value) {
+            (CoreTypeConcrete::Array(info), JitValue::Array(values)) => {
+                let mut elems = Vec::new();
+                for value in values {
+                    self.encode(value, &info.ty, &mut elems)?;
+                }
+
+                // The runner only supports arrays of felts (or values made of felts).
+                let elems = elems
+                    .into_iter()
+                    .map(|elem| match elem {
+                        Arg::Value(value) => Ok(value),
+                        Arg::Array(_) => Err(unexpected()),
+                    })
+                    .collect::<Result<_>>()?;
+                args.push(Arg::Array(elems));
+            }
+            (CoreTypeConcrete::Felt252(_), JitValue::Felt252(value)) => {
+                args.push(Arg::Value(felt_to_vm(value)));
+            }
+            (CoreTypeConcrete::Bytes31(_), JitValue::Bytes31(value)) => {
+                args.push(Arg::Value(Felt252::from_bytes_be(value)));
+            }
+            (CoreTypeConcrete::Uint128(_), JitValue::Uint128(value)) => {
+                args.push(Arg::Value((*value).into()));
+            }
+            (CoreTypeConcrete::Uint64(_), JitValue::Uint64(value)) => {
+                args.push(Arg::Value((*value).into()));
+            }
+            (CoreTypeConcrete::Uint32(_), JitValue::Uint32(value)) => {
+                args.push(Arg::Value((*value).into()));
+            }
+            (CoreTypeConcrete::Uint16(_), JitValue::Uint16(value)) => {
+                args.push(Arg::Value((*value).into()));
+            }
+            (CoreTypeConcrete::Uint8(_), JitValue::Uint8(value)) => {
+                args.push(Arg::Value((*value).into()));
+            }
+            (CoreTypeConcrete::Sint128(_), JitValue::Sint128(value)) => {
+                args.push(Arg::Value((*value).into()));
+            }
+            (CoreTypeConcrete::Sint64(_), JitValue::Sint64(value)) => {
+                args.push(Arg::Value((*value).into()));
+            }
+            (CoreTypeConcrete::Sint32(_), JitValue::Sint32(value)) => {
+                args.push(Arg::Value((*value).into()));
+            }
+            (CoreTypeConcrete::Sint16(_), JitValue::Sint16(value)) => {
+                args.push(Arg::Value((*value).into()));
+            }
+            (CoreTypeConcrete::Sint8(_), JitValue::Sint8(value)) => {
+                args.push(Arg::Value((*value).into()));
+            }
+            (CoreTypeConcrete::Enum(info), JitValue::Enum { tag, value, .. }) => {
+                let variant_ty = info.variants.get(*tag).ok_or_else(unexpected)?;
+
+                // Enums with more than two variants are encoded using the jump offsets of the
+                // match's CASM code (see `decode`).
+                let vm_tag = if info.variants.len() > 2 {
+                    2 * (info.variants.len() - tag) - 1
+                } else {
+                    *tag
+                };
+                args.push(Arg::Value(vm_tag.into()));
+
+                // The variant's data is aligned to the end of the enum.
+                let padding = self.size(ty)? - 1 - self.size(variant_ty)?;
+                args.extend((0..padding).map(|_| Arg::Value(Felt252::from(0u8))));
+                self.encode(value, variant_ty, args)?;
+            }
+            (CoreTypeConcrete::Struct(info), JitValue::Struct { fields, .. }) => {
+                if info.members.len() != fields.len() {
+                    return Err(unexpected());
+                }
+                for (member_ty, field) in info.members.iter().zip(fields) {
+                    self.encode(field, member_ty, args)?;
+                }
+            }
+            (CoreTypeConcrete::NonZero(info), _) => self.encode(value, &info.ty, args)?,
+            (CoreTypeConcrete::Snapshot(info), _) => self.encode(value, &info.ty, args)?,
+            (CoreTypeConcrete::EcPoint(_), JitValue::EcPoint(x, y)) => {
+                args.extend([x, y].map(|value| Arg::Value(felt_to_vm(value))));
+            }
+            (CoreTypeConcrete::EcState(_), JitValue::EcState(x0, y0, x1, y1)) => {
+                args.extend([x0, y0, x1, y1].map(|value| Arg::Value(felt_to_vm(value))));
+            }
+            _ => return Err(unexpected()),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{context::NativeContext, executor::JitNativeExecutor, utils::test::load_cairo};
+    use cairo_lang_runner::{SierraCasmRunner, StarknetState};
+    use cairo_lang_sierra::program::Program;
+
+    fn compare(
+        module_name: &str,
+        program: &Program,
+        name: &str,
+        args: &[JitValue],
+    ) -> Vec<VmMismatch> {
+        let function_name = format!("{0}::{0}::{name}", module_name);
+        let function = program
+            .funcs
+            .iter()
+            .find(|x| x.id.debug_name.as_deref() == Some(function_name.as_str()))
+            .unwrap();
+
+        let native_module = NativeContext::new().compile(program, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(native_module, Default::default());
+        let native_result = executor.invoke_dynamic(&function.id, args, None).unwrap();
+
+        let runner = SierraCasmRunner::new(
+            program.clone(),
+            Some(Default::default()),
+            Default::default(),
+            false,
+        )
+        .unwrap();
+        let registry = ProgramRegistry::new(program).unwrap();
+        let vm_result = runner
+            .run_function_with_starknet_context(
+                runner.find_function(&function_name).unwrap(),
+                &vm_args_from_jit(&registry, &function.id, args).unwrap(),
+                None,
+                StarknetState::default(),
+            )
+            .unwrap();
+
+        compare_with_vm(&registry, &function.id, &native_result, &vm_result).unwrap()
+    }
+
+    #[test]
+    fn matching_values() {
+        let (module_name, program) = load_cairo! {
+            enum Shape {
+                Point: (),
+                Circle: u32,
+                Rectangle: (u16, u16),
+            }
+
+            fn run_test() -> (Array<i8>, Shape, Option<felt252>) {
+                let mut values = ArrayTrait::new();
+                values.append(-3);
+                values.append(7);
+                (values, Shape::Rectangle((2, 5)), Option::Some(42))
+            }
+        };
+
+        assert_eq!(compare(&module_name, &program, "run_test", &[]), Vec::new());
+    }
+
+    #[test]
+    fn matching_panic() {
+        let (module_name, program) = load_cairo! {
+            fn run_test() -> u32 {
+                panic_with_felt252(42);
+                1
+            }
+        };
+
+        assert_eq!(compare(&module_name, &program, "run_test", &[]), Vec::new());
+    }
+
+    #[test]
+    fn matching_arguments() {
+        let (module_name, program) = load_cairo! {
+            enum Shape {
+                Point: (),
+                Circle: u32,
+                Rectangle: (u16, u16),
+            }
+
+            fn run_test(a: i16, values: Array<felt252>, shape: Shape) -> (i16, u32, Shape) {
+                (a, values.len(), shape)
+            }
+        };
+
+        let shape = JitValue::Enum {
+            tag: 2,
+            value: Box::new(JitValue::Struct {
+                fields: vec![JitValue::Uint16(2), JitValue::Uint16(5)],
+                debug_name: None,
+            }),
+            debug_name: None,
+        };
+        assert_eq!(
+            compare(
+                &module_name,
+                &program,
+                "run_test",
+                &[
+                    JitValue::Sint16(-4),
+                    JitValue::Array(vec![JitValue::Felt252(1.into()); 3]),
+                    shape,
+                ]
+            ),
+            Vec::new()
+        );
+    }
+}
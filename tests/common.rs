@@ -11,11 +11,8 @@ use cairo_lang_runner::{
     Arg, RunResultStarknet, RunResultValue, RunnerError, SierraCasmRunner, StarknetState,
 };
 use cairo_lang_sierra::{
-    extensions::{
-        core::{CoreLibfunc, CoreType, CoreTypeConcrete},
-        ConcreteType,
-    },
-    ids::{ConcreteTypeId, FunctionId},
+    extensions::core::{CoreLibfunc, CoreType},
+    ids::FunctionId,
     program::Program,
     program_registry::ProgramRegistry,
 };
@@ -32,9 +29,10 @@ use cairo_native::{
     },
     module::NativeModule,
     starknet::{DummySyscallHandler, StarknetSyscallHandler},
-    types::felt252::{HALF_PRIME, PRIME},
+    types::felt252::PRIME,
     utils::{find_entry_point_by_idx, run_pass_manager},
     values::JitValue,
+    vm::jit_value_from_vm,
     OptLevel,
 };
 use lambdaworks_math::{
@@ -52,7 +50,7 @@ use melior::{
 use num_bigint::{BigInt, Sign};
 use proptest::{strategy::Strategy, test_runner::TestCaseError};
 use starknet_types_core::felt::Felt;
-use std::{collections::HashMap, env::var, fs, ops::Neg, path::Path};
+use std::{env::var, fs, ops::Neg, path::Path};
 
 #[allow(unused_macros)]
 macro_rules! load_cairo {
@@ -61,10 +59,8 @@ macro_rules! load_cairo {
     };
 }
 
-use cairo_felt::Felt252;
 #[allow(unused_imports)]
 pub(crate) use load_cairo;
-use num_traits::ToPrimitive;
 
 pub const DEFAULT_GAS: u64 = u64::MAX;
 
@@ -252,8 +248,8 @@ pub fn run_native_program(
     // FIXME: There are some bugs with non-zero LLVM optimization levels.
     let executor = JitNativeExecutor::from_native_module(native_module, OptLevel::None);
     match syscall_handler {
-        Some(syscall_handler) => executor
-            .invoke_dynamic_with_syscall_handler(entry_point_id, args, gas, syscall_handler)
+        Some(mut syscall_handler) => executor
+            .invoke_dynamic_with_syscall_handler(entry_point_id, args, gas, &mut syscall_handler)
             .unwrap(),
         None => executor.invoke_dynamic(entry_point_id, args, gas).unwrap(),
     }
@@ -303,7 +299,7 @@ pub fn run_native_starknet_contract(
     sierra_program: &Program,
     entry_point_function_idx: usize,
     args: &[Felt],
-    handler: impl StarknetSyscallHandler,
+    mut handler: impl StarknetSyscallHandler,
 ) -> ContractExecutionResult {
     let native_context = NativeContext::new();
 
@@ -314,7 +310,7 @@ pub fn run_native_starknet_contract(
 
     let native_executor = JitNativeExecutor::from_native_module(native_program, Default::default());
     native_executor
-        .invoke_contract_dynamic(entry_point_id, args, u128::MAX.into(), handler)
+        .invoke_contract_dynamic(entry_point_id, args, u128::MAX.into(), &mut handler)
         .expect("failed to execute the given contract")
 }
 
@@ -332,264 +328,8 @@ pub fn compare_outputs(
     native_result: &ExecutionResult,
 ) -> Result<(), TestCaseError> {
     let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(program).unwrap();
-    let function = registry.get_function(entry_point).unwrap();
-
-    fn map_vm_sizes(
-        size_cache: &mut HashMap<ConcreteTypeId, usize>,
-        registry: &ProgramRegistry<CoreType, CoreLibfunc>,
-        ty: &ConcreteTypeId,
-    ) -> usize {
-        match size_cache.get(ty) {
-            Some(&type_size) => type_size,
-            None => {
-                let type_size = match registry.get_type(ty).unwrap() {
-                    CoreTypeConcrete::Array(_info) => 2,
-                    CoreTypeConcrete::Felt252(_)
-                    | CoreTypeConcrete::Uint128(_)
-                    | CoreTypeConcrete::Uint64(_)
-                    | CoreTypeConcrete::Uint32(_)
-                    | CoreTypeConcrete::Uint16(_)
-                    | CoreTypeConcrete::Uint8(_)
-                    | CoreTypeConcrete::Sint128(_)
-                    | CoreTypeConcrete::Sint64(_)
-                    | CoreTypeConcrete::Sint32(_)
-                    | CoreTypeConcrete::Sint16(_)
-                    | CoreTypeConcrete::Sint8(_) => 1,
-                    CoreTypeConcrete::Enum(info) => {
-                        1 + info
-                            .variants
-                            .iter()
-                            .map(|variant_ty| map_vm_sizes(size_cache, registry, variant_ty))
-                            .max()
-                            .unwrap_or_default()
-                    }
-                    CoreTypeConcrete::Struct(info) => info
-                        .members
-                        .iter()
-                        .map(|member_ty| map_vm_sizes(size_cache, registry, member_ty))
-                        .sum(),
-                    CoreTypeConcrete::Nullable(_) => 1,
-                    CoreTypeConcrete::NonZero(info) => map_vm_sizes(size_cache, registry, &info.ty),
-                    CoreTypeConcrete::EcPoint(_) => 2,
-                    CoreTypeConcrete::EcState(_) => 4,
-                    x => todo!("vm size not yet implemented: {:?}", x.info()),
-                };
-                size_cache.insert(ty.clone(), type_size);
-
-                type_size
-            }
-        }
-    }
-
-    fn map_vm_values(
-        size_cache: &mut HashMap<ConcreteTypeId, usize>,
-        registry: &ProgramRegistry<CoreType, CoreLibfunc>,
-        memory: &[Option<Felt252>],
-        mut values: &[Felt252],
-        ty: &ConcreteTypeId,
-    ) -> JitValue {
-        match registry.get_type(ty).unwrap() {
-            CoreTypeConcrete::Array(info) => {
-                assert_eq!(values.len(), 2);
-                let since_ptr = values[0].to_usize().unwrap();
-                let until_ptr = values[1].to_usize().unwrap();
-
-                let total_len = until_ptr - since_ptr;
-                let elem_size = map_vm_sizes(size_cache, registry, &info.ty);
-                assert_eq!(total_len % elem_size, 0);
-
-                JitValue::Array(
-                    memory[since_ptr..until_ptr]
-                        .chunks(elem_size)
-                        .map(|data| data.iter().cloned().map(Option::unwrap).collect::<Vec<_>>())
-                        .map(|data| map_vm_values(size_cache, registry, memory, &data, &info.ty))
-                        .collect(),
-                )
-            }
-            CoreTypeConcrete::Felt252(_) => {
-                JitValue::Felt252(Felt::from_bytes_le(&values[0].to_le_bytes()))
-            }
-            CoreTypeConcrete::Uint128(_) => JitValue::Uint128(values[0].to_u128().unwrap()),
-            CoreTypeConcrete::Uint64(_) => JitValue::Uint64(values[0].to_u64().unwrap()),
-            CoreTypeConcrete::Uint32(_) => JitValue::Uint32(values[0].to_u32().unwrap()),
-            CoreTypeConcrete::Uint16(_) => JitValue::Uint16(values[0].to_u16().unwrap()),
-            CoreTypeConcrete::Uint8(_) => JitValue::Uint8(values[0].to_u8().unwrap()),
-            CoreTypeConcrete::Sint128(_) => {
-                JitValue::Sint128(if values[0].to_bigint() >= *HALF_PRIME {
-                    -(&*PRIME - &values[0].to_biguint()).to_i128().unwrap()
-                } else {
-                    values[0].to_biguint().to_i128().unwrap()
-                })
-            }
-            CoreTypeConcrete::Sint64(_) => {
-                JitValue::Sint64(if values[0].to_bigint() >= *HALF_PRIME {
-                    -(&*PRIME - &values[0].to_biguint()).to_i64().unwrap()
-                } else {
-                    values[0].to_biguint().to_i64().unwrap()
-                })
-            }
-            CoreTypeConcrete::Sint32(_) => {
-                JitValue::Sint32(if values[0].to_bigint() >= *HALF_PRIME {
-                    -(&*PRIME - &values[0].to_biguint()).to_i32().unwrap()
-                } else {
-                    values[0].to_biguint().to_i32().unwrap()
-                })
-            }
-            CoreTypeConcrete::Sint16(_) => {
-                JitValue::Sint16(if values[0].to_bigint() >= *HALF_PRIME {
-                    -(&*PRIME - &values[0].to_biguint()).to_i16().unwrap()
-                } else {
-                    values[0].to_biguint().to_i16().unwrap()
-                })
-            }
-            CoreTypeConcrete::Sint8(_) => {
-                JitValue::Sint8(if values[0].to_bigint() >= *HALF_PRIME {
-                    -(&*PRIME - &values[0].to_biguint()).to_i8().unwrap()
-                } else {
-                    values[0].to_biguint().to_i8().unwrap()
-                })
-            }
-            CoreTypeConcrete::Enum(info) => {
-                let enum_size = map_vm_sizes(size_cache, registry, ty);
-                assert_eq!(values.len(), enum_size);
-
-                let (tag, data);
-                (tag, values) = values.split_first().unwrap();
-
-                let mut tag = tag.to_usize().unwrap();
-                if info.variants.len() > 2 {
-                    tag = info.variants.len() - ((tag + 1) >> 1);
-                }
-                assert!(tag <= info.variants.len());
-                data = &values[enum_size - size_cache[&info.variants[tag]] - 1..];
-
-                JitValue::Enum {
-                    tag,
-                    value: Box::new(map_vm_values(
-                        size_cache,
-                        registry,
-                        memory,
-                        data,
-                        &info.variants[tag],
-                    )),
-                    debug_name: ty.debug_name.as_deref().map(String::from),
-                }
-            }
-            CoreTypeConcrete::Struct(info) => JitValue::Struct {
-                fields: info
-                    .members
-                    .iter()
-                    .map(|member_ty| {
-                        let data;
-                        (data, values) =
-                            values.split_at(map_vm_sizes(size_cache, registry, member_ty));
-
-                        map_vm_values(size_cache, registry, memory, data, member_ty)
-                    })
-                    .collect(),
-                debug_name: ty.debug_name.as_deref().map(String::from),
-            },
-            CoreTypeConcrete::Nullable(info) => {
-                assert_eq!(values.len(), 1);
-
-                let ty_size = map_vm_sizes(size_cache, registry, &info.ty);
-                match values[0].to_usize().unwrap() {
-                    0 => JitValue::Null,
-                    ptr if ty_size == 0 => {
-                        assert_eq!(ptr, 1);
-                        map_vm_values(size_cache, registry, memory, &[], &info.ty)
-                    }
-                    ptr => map_vm_values(
-                        size_cache,
-                        registry,
-                        memory,
-                        &memory[ptr..ptr + ty_size]
-                            .iter()
-                            .cloned()
-                            .map(Option::unwrap)
-                            .collect::<Vec<_>>(),
-                        &info.ty,
-                    ),
-                }
-            }
-            CoreTypeConcrete::NonZero(info) => {
-                map_vm_values(size_cache, registry, memory, values, &info.ty)
-            }
-            CoreTypeConcrete::EcPoint(_) => {
-                assert_eq!(values.len(), 2);
-
-                JitValue::EcPoint(
-                    Felt::from_bytes_le(&values[0].to_le_bytes()),
-                    Felt::from_bytes_le(&values[1].to_le_bytes()),
-                )
-            }
-            CoreTypeConcrete::EcState(_) => {
-                assert_eq!(values.len(), 4);
-
-                JitValue::EcState(
-                    Felt::from_bytes_le(&values[0].to_le_bytes()),
-                    Felt::from_bytes_le(&values[1].to_le_bytes()),
-                    Felt::from_bytes_le(&values[2].to_le_bytes()),
-                    Felt::from_bytes_le(&values[3].to_le_bytes()),
-                )
-            }
-            x => {
-                todo!("vm value not yet implemented: {:?}", x.info())
-            }
-        }
-    }
-
-    let mut size_cache = HashMap::new();
-    let ty = function.signature.ret_types.last().unwrap();
-    let returns_panic = ty
-        .debug_name
-        .as_ref()
-        .map(|x| x.starts_with("core::panics::PanicResult"))
-        .unwrap_or(false);
-
-    let vm_result = match &vm_result.value {
-        RunResultValue::Success(values) => {
-            if returns_panic {
-                let inner_ty = match registry.get_type(ty)? {
-                    CoreTypeConcrete::Enum(info) => &info.variants[0],
-                    _ => unreachable!(),
-                };
-                JitValue::Enum {
-                    tag: 0,
-                    value: Box::new(map_vm_values(
-                        &mut size_cache,
-                        &registry,
-                        &vm_result.memory,
-                        values,
-                        inner_ty,
-                    )),
-                    debug_name: None,
-                }
-            } else {
-                map_vm_values(&mut size_cache, &registry, &vm_result.memory, values, ty)
-            }
-        }
-        RunResultValue::Panic(values) => JitValue::Enum {
-            tag: 1,
-            value: Box::new(JitValue::Struct {
-                fields: vec![
-                    JitValue::Struct {
-                        fields: Vec::new(),
-                        debug_name: None,
-                    },
-                    JitValue::Array(
-                        values
-                            .iter()
-                            .map(|value| Felt::from_bytes_le(&value.to_le_bytes()))
-                            .map(JitValue::Felt252)
-                            .collect(),
-                    ),
-                ],
-                debug_name: None,
-            }),
-            debug_name: None,
-        },
-    };
+    let vm_result =
+        jit_value_from_vm(&registry, entry_point, &vm_result.value, &vm_result.memory).unwrap();
 
     pretty_assertions_sorted::assert_eq!(native_result.return_value, vm_result);
     Ok(())
@@ -3,7 +3,11 @@ use cairo_lang_sierra::{
     extensions::core::{CoreLibfunc, CoreType},
     program_registry::ProgramRegistry,
 };
-use cairo_native::metadata::{runtime_bindings::RuntimeBindingsMeta, MetadataStorage};
+use cairo_native::{
+    context::NativeContext,
+    metadata::{runtime_bindings::RuntimeBindingsMeta, MetadataStorage},
+    utils::cairo_to_sierra_with_debug_info,
+};
 use melior::{
     dialect::DialectRegistry,
     ir::{Location, Module},
@@ -11,7 +15,7 @@ use melior::{
     utility::{register_all_dialects, register_all_llvm_translations},
     Context,
 };
-use std::error::Error;
+use std::{error::Error, path::Path};
 use tempfile::NamedTempFile;
 
 #[test]
@@ -71,3 +75,22 @@ pub fn compile_library() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+pub fn compile_library_with_debug_info() -> Result<(), Box<dyn Error>> {
+    let native_context = NativeContext::new();
+    let (program, debug_locations) = cairo_to_sierra_with_debug_info(
+        native_context.context(),
+        Path::new("programs/examples/hello.cairo"),
+    )?;
+
+    let native_module = native_context.compile(&program, Some(debug_locations))?;
+    let object = cairo_native::module_to_object(native_module.module(), Default::default())?;
+
+    // The object should contain line tables pointing at the Cairo source file.
+    let contains = |needle: &[u8]| object.windows(needle.len()).any(|x| x == needle);
+    assert!(contains(b"debug_line"));
+    assert!(contains(b"hello.cairo"));
+
+    Ok(())
+}
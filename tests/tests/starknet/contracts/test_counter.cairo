@@ -0,0 +1,52 @@
+#[starknet::interface]
+trait ICounter<TContractState> {
+    fn get(self: @TContractState) -> felt252;
+    fn increase(ref self: TContractState, amount: felt252) -> felt252;
+    fn increase_and_fail(ref self: TContractState, amount: felt252);
+}
+
+#[starknet::contract]
+mod Counter {
+    #[storage]
+    struct Storage {
+        value: felt252,
+    }
+
+    #[event]
+    #[derive(Drop, starknet::Event)]
+    enum Event {
+        Increased: Increased,
+    }
+
+    #[derive(Drop, starknet::Event)]
+    struct Increased {
+        amount: felt252,
+    }
+
+    #[constructor]
+    fn constructor(ref self: ContractState, initial_value: felt252) {
+        self.value.write(initial_value);
+    }
+
+    #[abi(embed_v0)]
+    impl Counter of super::ICounter<ContractState> {
+        fn get(self: @ContractState) -> felt252 {
+            self.value.read()
+        }
+
+        fn increase(ref self: ContractState, amount: felt252) -> felt252 {
+            assert(amount != 0, 'zero amount');
+
+            let value = self.value.read() + amount;
+            self.value.write(value);
+            self.emit(Increased { amount });
+            value
+        }
+
+        fn increase_and_fail(ref self: ContractState, amount: felt252) {
+            self.value.write(self.value.read() + amount);
+            self.emit(Increased { amount });
+            panic_with_felt252('increase failed');
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use starknet::{ClassHash, ContractAddress};
+
+#[starknet::interface]
+trait ICounter<TContractState> {
+    fn get(self: @TContractState) -> felt252;
+    fn increase(ref self: TContractState, amount: felt252) -> felt252;
+    fn increase_and_fail(ref self: TContractState, amount: felt252);
+}
+
+#[starknet::interface]
+trait ICounterFactory<TContractState> {
+    fn deploy_counter(
+        ref self: TContractState, class_hash: ClassHash, initial_value: felt252
+    ) -> ContractAddress;
+    fn increase_counter(
+        ref self: TContractState, counter: ContractAddress, amount: felt252
+    ) -> felt252;
+    fn increase_with_library(
+        ref self: TContractState, class_hash: ClassHash, amount: felt252
+    ) -> felt252;
+    fn try_increase_and_fail(
+        ref self: TContractState, counter: ContractAddress, amount: felt252
+    ) -> bool;
+}
+
+#[starknet::contract]
+mod CounterFactory {
+    use starknet::{
+        ClassHash, ContractAddress, SyscallResultTrait, call_contract_syscall, deploy_syscall
+    };
+    use super::{
+        ICounterDispatcher, ICounterDispatcherTrait, ICounterLibraryDispatcher,
+        ICounterLibraryDispatcherTrait
+    };
+
+    #[storage]
+    struct Storage {}
+
+    #[abi(embed_v0)]
+    impl CounterFactory of super::ICounterFactory<ContractState> {
+        fn deploy_counter(
+            ref self: ContractState, class_hash: ClassHash, initial_value: felt252
+        ) -> ContractAddress {
+            let (address, _) = deploy_syscall(class_hash, 0, array![initial_value].span(), false)
+                .unwrap_syscall();
+            address
+        }
+
+        fn increase_counter(
+            ref self: ContractState, counter: ContractAddress, amount: felt252
+        ) -> felt252 {
+            ICounterDispatcher { contract_address: counter }.increase(amount)
+        }
+
+        fn increase_with_library(
+            ref self: ContractState, class_hash: ClassHash, amount: felt252
+        ) -> felt252 {
+            ICounterLibraryDispatcher { class_hash }.increase(amount)
+        }
+
+        fn try_increase_and_fail(
+            ref self: ContractState, counter: ContractAddress, amount: felt252
+        ) -> bool {
+            // The failure is caught instead of propagated, so the caller keeps running.
+            call_contract_syscall(counter, selector!("increase_and_fail"), array![amount].span())
+                .is_ok()
+        }
+    }
+}
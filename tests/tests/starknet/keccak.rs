@@ -2,8 +2,8 @@ use crate::common::run_native_starknet_contract;
 use cairo_lang_compiler::CompilerConfig;
 use cairo_lang_starknet::contract_class::{compile_path, ContractClass};
 use cairo_native::starknet::{
-    BlockInfo, ExecutionInfo, ExecutionInfoV2, ResourceBounds, Secp256k1Point, Secp256r1Point,
-    StarknetSyscallHandler, SyscallResult, TxInfo, TxV2Info, U256,
+    BlockInfo, DummySyscallHandler, ExecutionInfo, ExecutionInfoV2, ResourceBounds, Secp256k1Point,
+    Secp256r1Point, StarknetSyscallHandler, SyscallResult, TxInfo, TxV2Info, U256,
 };
 use lazy_static::lazy_static;
 use starknet_types_core::felt::Felt;
@@ -294,3 +294,19 @@ fn keccak_test() {
     assert_eq!(result.remaining_gas, 0xFFFFFFFFFFFFFFFFFFFFFFFFFFFE5F6F);
     assert_eq!(result.return_values, vec![1.into()]);
 }
+
+#[test]
+fn keccak_default_impl_test() {
+    let contract = &KECCAK_CONTRACT;
+
+    let entry_point = contract.entry_points_by_type.external.first().unwrap();
+
+    let program = contract.extract_sierra_program().unwrap();
+    let result =
+        run_native_starknet_contract(&program, entry_point.function_idx, &[], DummySyscallHandler);
+
+    assert!(!result.failure_flag);
+    // A single round, charged `KECCAK_ROUND_COST` instead of the 1000 gas of `keccak_test`.
+    assert_eq!(result.remaining_gas, 0xFFFFFFFFFFFFFFFFFFFFFFFFFFFBA437);
+    assert_eq!(result.return_values, vec![1.into()]);
+}
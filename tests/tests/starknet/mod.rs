@@ -1,3 +1,5 @@
 mod keccak;
+mod replay;
 mod secp256;
+mod state;
 mod syscalls;
@@ -13,6 +13,13 @@ fn secp256k1_new(x: u256, y: u256) -> Result<Option<Secp256k1Point>, Array<felt2
     secp256k1_new_syscall(x, y)
 }
 
+fn secp256k1_new_is_some(x: u256, y: u256) -> Result<bool, Array<felt252>> {
+    match secp256k1_new_syscall(x, y) {
+        Result::Ok(p) => Result::Ok(p.is_some()),
+        Result::Err(e) => Result::Err(e),
+    }
+}
+
 fn secp256k1_add(p0: Secp256k1Point, p1: Secp256k1Point) -> Result<Secp256k1Point, Array<felt252>> {
     secp256k1_add_syscall(p0, p1)
 }
@@ -35,6 +42,13 @@ fn secp256r1_new(x: u256, y: u256) -> Result<Option<Secp256r1Point>, Array<felt2
     secp256r1_new_syscall(x, y)
 }
 
+fn secp256r1_new_is_some(x: u256, y: u256) -> Result<bool, Array<felt252>> {
+    match secp256r1_new_syscall(x, y) {
+        Result::Ok(p) => Result::Ok(p.is_some()),
+        Result::Err(e) => Result::Err(e),
+    }
+}
+
 fn secp256r1_add(p0: Secp256r1Point, p1: Secp256r1Point) -> Result<Secp256r1Point, Array<felt252>> {
     secp256r1_add_syscall(p0, p1)
 }
@@ -52,3 +66,59 @@ fn secp256r1_get_point_from_x(
 fn secp256r1_get_xy(p: Secp256r1Point) -> Result<(u256, u256), Array<felt252>> {
     secp256r1_get_xy_syscall(p)
 }
+
+fn secp256k1_add_xy(
+    x0: u256, y0: u256, x1: u256, y1: u256
+) -> Result<(u256, u256), Array<felt252>> {
+    let p0 = match secp256k1_new_syscall(x0, y0) {
+        Result::Ok(p) => p.unwrap(),
+        Result::Err(e) => { return Result::Err(e); },
+    };
+    let p1 = match secp256k1_new_syscall(x1, y1) {
+        Result::Ok(p) => p.unwrap(),
+        Result::Err(e) => { return Result::Err(e); },
+    };
+    match secp256k1_add_syscall(p0, p1) {
+        Result::Ok(p) => secp256k1_get_xy_syscall(p),
+        Result::Err(e) => Result::Err(e),
+    }
+}
+
+fn secp256k1_mul_xy(x: u256, y: u256, scalar: u256) -> Result<(u256, u256), Array<felt252>> {
+    let p = match secp256k1_new_syscall(x, y) {
+        Result::Ok(p) => p.unwrap(),
+        Result::Err(e) => { return Result::Err(e); },
+    };
+    match secp256k1_mul_syscall(p, scalar) {
+        Result::Ok(p) => secp256k1_get_xy_syscall(p),
+        Result::Err(e) => Result::Err(e),
+    }
+}
+
+fn secp256r1_add_xy(
+    x0: u256, y0: u256, x1: u256, y1: u256
+) -> Result<(u256, u256), Array<felt252>> {
+    let p0 = match secp256r1_new_syscall(x0, y0) {
+        Result::Ok(p) => p.unwrap(),
+        Result::Err(e) => { return Result::Err(e); },
+    };
+    let p1 = match secp256r1_new_syscall(x1, y1) {
+        Result::Ok(p) => p.unwrap(),
+        Result::Err(e) => { return Result::Err(e); },
+    };
+    match secp256r1_add_syscall(p0, p1) {
+        Result::Ok(p) => secp256r1_get_xy_syscall(p),
+        Result::Err(e) => Result::Err(e),
+    }
+}
+
+fn secp256r1_mul_xy(x: u256, y: u256, scalar: u256) -> Result<(u256, u256), Array<felt252>> {
+    let p = match secp256r1_new_syscall(x, y) {
+        Result::Ok(p) => p.unwrap(),
+        Result::Err(e) => { return Result::Err(e); },
+    };
+    match secp256r1_mul_syscall(p, scalar) {
+        Result::Ok(p) => secp256r1_get_xy_syscall(p),
+        Result::Err(e) => Result::Err(e),
+    }
+}
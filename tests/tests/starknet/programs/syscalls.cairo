@@ -60,3 +60,8 @@ fn send_message_to_l1() -> SyscallResult<()> {
 fn keccak() -> SyscallResult<u256> {
     keccak_syscall(array![].span())
 }
+
+fn set_block_number() -> u64 {
+    core::starknet::testing::set_block_number(7);
+    core::starknet::get_block_info().unbox().block_number
+}
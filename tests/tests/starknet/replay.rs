@@ -0,0 +1,105 @@
+use crate::common::run_native_starknet_contract;
+use cairo_lang_compiler::CompilerConfig;
+use cairo_lang_starknet::{
+    contract::starknet_keccak,
+    contract_class::{compile_path, ContractClass},
+};
+#[cfg(feature = "with-serde")]
+use cairo_native::starknet::SyscallRecording;
+use cairo_native::{
+    cache::JitProgramCache,
+    context::NativeContext,
+    starknet::{InMemoryStarknetState, RecordingSyscallHandler, ReplaySyscallHandler, Syscall},
+};
+use lazy_static::lazy_static;
+use starknet_types_core::felt::Felt;
+use std::path::Path;
+
+lazy_static! {
+    static ref COUNTER_CONTRACT: ContractClass = compile_path(
+        Path::new("tests/tests/starknet/contracts/test_counter.cairo"),
+        None,
+        CompilerConfig {
+            replace_ids: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+}
+
+fn find_external(contract: &ContractClass, name: &str) -> usize {
+    let selector = starknet_keccak(name.as_bytes());
+    contract
+        .entry_points_by_type
+        .external
+        .iter()
+        .find(|entry_point| entry_point.selector == selector)
+        .unwrap()
+        .function_idx
+}
+
+#[test]
+fn record_and_replay() {
+    let program = COUNTER_CONTRACT.extract_sierra_program().unwrap();
+    let increase = find_external(&COUNTER_CONTRACT, "increase");
+
+    let context = NativeContext::new();
+    let mut state = InMemoryStarknetState::new(JitProgramCache::new(&context).into());
+    state.set_storage_at(
+        Felt::ZERO,
+        Felt::from_bytes_be_slice(&starknet_keccak(b"value").to_bytes_be()),
+        10.into(),
+    );
+
+    let mut recording = RecordingSyscallHandler::new(&mut state);
+    let recorded_result =
+        run_native_starknet_contract(&program, increase, &[5.into()], &mut recording);
+    assert_eq!(recorded_result.return_values, vec![15.into()]);
+
+    let (_, recording) = recording.into_parts();
+    assert!(matches!(
+        recording.syscalls[0].syscall,
+        Syscall::StorageRead {
+            result: Ok(value),
+            ..
+        } if value == 10.into()
+    ));
+
+    // The replay doesn't need the state.
+    let mut replay = ReplaySyscallHandler::new(recording.clone());
+    let replayed_result =
+        run_native_starknet_contract(&program, increase, &[5.into()], &mut replay);
+    assert_eq!(replayed_result, recorded_result);
+    replay.finish().unwrap();
+
+    // A different input diverges from the recording.
+    let mut replay = ReplaySyscallHandler::new(recording);
+    let replayed_result =
+        run_native_starknet_contract(&program, increase, &[6.into()], &mut replay);
+    assert!(replayed_result.failure_flag);
+    assert!(replay.finish().is_err());
+}
+
+#[cfg(feature = "with-serde")]
+#[test]
+fn serialize_and_replay() {
+    let program = COUNTER_CONTRACT.extract_sierra_program().unwrap();
+    let increase = find_external(&COUNTER_CONTRACT, "increase");
+
+    let context = NativeContext::new();
+    let mut state = InMemoryStarknetState::new(JitProgramCache::new(&context).into());
+    let mut recording = RecordingSyscallHandler::new(&mut state);
+    let recorded_result =
+        run_native_starknet_contract(&program, increase, &[5.into()], &mut recording);
+
+    let (_, recording) = recording.into_parts();
+    let serialized = serde_json::to_string(&recording).unwrap();
+    let deserialized: SyscallRecording = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, recording);
+
+    let mut replay = ReplaySyscallHandler::new(deserialized);
+    let replayed_result =
+        run_native_starknet_contract(&program, increase, &[5.into()], &mut replay);
+    assert_eq!(replayed_result, recorded_result);
+    replay.finish().unwrap();
+}
@@ -1,8 +1,14 @@
-use crate::common::{load_cairo_path, run_native_program};
-use cairo_lang_runner::SierraCasmRunner;
+use crate::common::{
+    compare_outputs, load_cairo_path, run_native_program, run_vm_program, DEFAULT_GAS,
+};
+use cairo_felt::Felt252 as DeprecatedFelt;
+use cairo_lang_runner::{Arg, SierraCasmRunner};
 use cairo_lang_sierra::program::Program;
 use cairo_native::{
-    starknet::{Secp256k1Point, Secp256r1Point, StarknetSyscallHandler, SyscallResult, U256},
+    starknet::{
+        DummySyscallHandler, Secp256k1Point, Secp256r1Point, StarknetSyscallHandler, SyscallResult,
+        U256,
+    },
     values::JitValue,
 };
 use lazy_static::lazy_static;
@@ -31,7 +37,7 @@ struct SyscallHandler {
     secp256r1_get_xy: (VecDeque<Secp256r1Point>, VecDeque<(U256, U256)>),
 }
 
-impl StarknetSyscallHandler for &mut SyscallHandler {
+impl StarknetSyscallHandler for SyscallHandler {
     fn get_block_hash(
         &mut self,
         _block_number: u64,
@@ -262,17 +268,17 @@ fn secp256k1_new() {
             VecDeque::from([
                 None,
                 Some(Secp256k1Point {
-                    x: U256 { hi: 0, lo: 0 },
-                    y: U256 { hi: 0, lo: 0 },
+                    x: U256 { lo: 0, hi: 0 },
+                    y: U256 { lo: 0, hi: 0 },
                 }),
                 Some(Secp256k1Point {
                     x: U256 {
-                        hi: u128::MAX,
                         lo: u128::MAX,
+                        hi: u128::MAX,
                     },
                     y: U256 {
-                        hi: u128::MAX,
                         lo: u128::MAX,
+                        hi: u128::MAX,
                     },
                 }),
             ]),
@@ -379,25 +385,25 @@ fn secp256k1_new() {
     assert_eq!(
         syscall_handler.secp256k1_new.0,
         [
-            (U256 { hi: 0, lo: 0 }, U256 { hi: 0, lo: 0 }),
+            (U256 { lo: 0, hi: 0 }, U256 { lo: 0, hi: 0 }),
             (
                 U256 {
-                    hi: u128::MAX,
-                    lo: 0
+                    lo: u128::MAX,
+                    hi: 0
                 },
                 U256 {
-                    hi: 0,
-                    lo: u128::MAX
+                    lo: 0,
+                    hi: u128::MAX
                 }
             ),
             (
                 U256 {
-                    hi: u128::MAX,
-                    lo: u128::MAX
+                    lo: u128::MAX,
+                    hi: u128::MAX
                 },
                 U256 {
-                    hi: u128::MAX,
-                    lo: u128::MAX
+                    lo: u128::MAX,
+                    hi: u128::MAX
                 }
             ),
         ],
@@ -412,27 +418,27 @@ fn secp256k1_add() {
             VecDeque::from([]),
             VecDeque::from([
                 Secp256k1Point {
-                    x: U256 { hi: 0, lo: 0 },
-                    y: U256 { hi: 0, lo: 0 },
+                    x: U256 { lo: 0, hi: 0 },
+                    y: U256 { lo: 0, hi: 0 },
                 },
                 Secp256k1Point {
                     x: U256 {
-                        hi: u128::MAX,
-                        lo: 0,
+                        lo: u128::MAX,
+                        hi: 0,
                     },
                     y: U256 {
-                        hi: 0,
-                        lo: u128::MAX,
+                        lo: 0,
+                        hi: u128::MAX,
                     },
                 },
                 Secp256k1Point {
                     x: U256 {
-                        hi: u128::MAX,
                         lo: u128::MAX,
+                        hi: u128::MAX,
                     },
                     y: U256 {
-                        hi: u128::MAX,
                         lo: u128::MAX,
+                        hi: u128::MAX,
                     },
                 },
             ]),
@@ -529,55 +535,55 @@ fn secp256k1_add() {
         [
             (
                 Secp256k1Point {
-                    x: U256 { hi: 0, lo: 0 },
-                    y: U256 { hi: 0, lo: 0 },
+                    x: U256 { lo: 0, hi: 0 },
+                    y: U256 { lo: 0, hi: 0 },
                 },
                 Secp256k1Point {
-                    x: U256 { hi: 0, lo: 0 },
-                    y: U256 { hi: 0, lo: 0 },
+                    x: U256 { lo: 0, hi: 0 },
+                    y: U256 { lo: 0, hi: 0 },
                 },
             ),
             (
                 Secp256k1Point {
                     x: U256 {
-                        hi: u128::MAX,
-                        lo: 0
+                        lo: u128::MAX,
+                        hi: 0
                     },
                     y: U256 {
-                        hi: 0,
-                        lo: u128::MAX
+                        lo: 0,
+                        hi: u128::MAX
                     },
                 },
                 Secp256k1Point {
                     x: U256 {
-                        hi: 0,
-                        lo: u128::MAX
+                        lo: 0,
+                        hi: u128::MAX
                     },
                     y: U256 {
-                        hi: u128::MAX,
-                        lo: 0
+                        lo: u128::MAX,
+                        hi: 0
                     },
                 },
             ),
             (
                 Secp256k1Point {
                     x: U256 {
-                        hi: u128::MAX,
-                        lo: u128::MAX
+                        lo: u128::MAX,
+                        hi: u128::MAX
                     },
                     y: U256 {
-                        hi: u128::MAX,
-                        lo: u128::MAX
+                        lo: u128::MAX,
+                        hi: u128::MAX
                     },
                 },
                 Secp256k1Point {
                     x: U256 {
-                        hi: u128::MAX,
-                        lo: u128::MAX
+                        lo: u128::MAX,
+                        hi: u128::MAX
                     },
                     y: U256 {
-                        hi: u128::MAX,
-                        lo: u128::MAX
+                        lo: u128::MAX,
+                        hi: u128::MAX
                     },
                 },
             ),
@@ -593,27 +599,27 @@ fn secp256k1_mul() {
             VecDeque::from([]),
             VecDeque::from([
                 Secp256k1Point {
-                    x: U256 { hi: 0, lo: 0 },
-                    y: U256 { hi: 0, lo: 0 },
+                    x: U256 { lo: 0, hi: 0 },
+                    y: U256 { lo: 0, hi: 0 },
                 },
                 Secp256k1Point {
                     x: U256 {
-                        hi: u128::MAX,
-                        lo: 0,
+                        lo: u128::MAX,
+                        hi: 0,
                     },
                     y: U256 {
-                        hi: 0,
-                        lo: u128::MAX,
+                        lo: 0,
+                        hi: u128::MAX,
                     },
                 },
                 Secp256k1Point {
                     x: U256 {
-                        hi: u128::MAX,
                         lo: u128::MAX,
+                        hi: u128::MAX,
                     },
                     y: U256 {
-                        hi: u128::MAX,
                         lo: u128::MAX,
+                        hi: u128::MAX,
                     },
                 },
             ]),
@@ -710,41 +716,41 @@ fn secp256k1_mul() {
         [
             (
                 Secp256k1Point {
-                    x: U256 { hi: 0, lo: 0 },
-                    y: U256 { hi: 0, lo: 0 },
+                    x: U256 { lo: 0, hi: 0 },
+                    y: U256 { lo: 0, hi: 0 },
                 },
-                U256 { hi: 0, lo: 0 },
+                U256 { lo: 0, hi: 0 },
             ),
             (
                 Secp256k1Point {
                     x: U256 {
-                        hi: u128::MAX,
-                        lo: 0
+                        lo: u128::MAX,
+                        hi: 0
                     },
                     y: U256 {
-                        hi: 0,
-                        lo: u128::MAX,
+                        lo: 0,
+                        hi: u128::MAX,
                     },
                 },
                 U256 {
-                    hi: u128::MAX,
-                    lo: 0,
+                    lo: u128::MAX,
+                    hi: 0,
                 },
             ),
             (
                 Secp256k1Point {
                     x: U256 {
-                        hi: 0,
-                        lo: u128::MAX,
+                        lo: 0,
+                        hi: u128::MAX,
                     },
                     y: U256 {
-                        hi: u128::MAX,
-                        lo: 0,
+                        lo: u128::MAX,
+                        hi: 0,
                     },
                 },
                 U256 {
-                    hi: 0,
-                    lo: u128::MAX,
+                    lo: 0,
+                    hi: u128::MAX,
                 },
             ),
         ],
@@ -760,27 +766,27 @@ fn secp256k1_get_point_from_x() {
             VecDeque::from([
                 None,
                 Some(Secp256k1Point {
-                    x: U256 { hi: 0, lo: 0 },
-                    y: U256 { hi: 0, lo: 0 },
+                    x: U256 { lo: 0, hi: 0 },
+                    y: U256 { lo: 0, hi: 0 },
                 }),
                 Some(Secp256k1Point {
                     x: U256 {
-                        hi: 0,
-                        lo: u128::MAX,
+                        lo: 0,
+                        hi: u128::MAX,
                     },
                     y: U256 {
-                        hi: u128::MAX,
-                        lo: 0,
+                        lo: u128::MAX,
+                        hi: 0,
                     },
                 }),
                 Some(Secp256k1Point {
                     x: U256 {
-                        hi: u128::MAX,
-                        lo: 0,
+                        lo: u128::MAX,
+                        hi: 0,
                     },
                     y: U256 {
-                        hi: 0,
-                        lo: u128::MAX,
+                        lo: 0,
+                        hi: u128::MAX,
                     },
                 }),
             ]),
@@ -935,25 +941,25 @@ fn secp256k1_get_point_from_x() {
     assert_eq!(
         syscall_handler.secp256k1_get_point_from_x.0,
         [
-            (U256 { hi: 0, lo: 0 }, false),
+            (U256 { lo: 0, hi: 0 }, false),
             (
                 U256 {
-                    hi: 0,
-                    lo: u128::MAX,
+                    lo: 0,
+                    hi: u128::MAX,
                 },
                 true,
             ),
             (
                 U256 {
-                    hi: u128::MAX,
-                    lo: 0,
+                    lo: u128::MAX,
+                    hi: 0,
                 },
                 false,
             ),
             (
                 U256 {
-                    hi: u128::MAX,
                     lo: u128::MAX,
+                    hi: u128::MAX,
                 },
                 true,
             ),
@@ -968,35 +974,35 @@ fn secp256k1_get_xy() {
         secp256k1_get_xy: (
             VecDeque::from([]),
             VecDeque::from([
-                (U256 { hi: 0, lo: 0 }, U256 { hi: 0, lo: 0 }),
+                (U256 { lo: 0, hi: 0 }, U256 { lo: 0, hi: 0 }),
                 (
                     U256 {
-                        hi: 0,
-                        lo: u128::MAX,
+                        lo: 0,
+                        hi: u128::MAX,
                     },
                     U256 {
-                        hi: u128::MAX,
-                        lo: 0,
+                        lo: u128::MAX,
+                        hi: 0,
                     },
                 ),
                 (
                     U256 {
-                        hi: u128::MAX,
-                        lo: 0,
+                        lo: u128::MAX,
+                        hi: 0,
                     },
                     U256 {
-                        hi: 0,
-                        lo: u128::MAX,
+                        lo: 0,
+                        hi: u128::MAX,
                     },
                 ),
                 (
                     U256 {
-                        hi: u128::MAX,
                         lo: u128::MAX,
+                        hi: u128::MAX,
                     },
                     U256 {
-                        hi: u128::MAX,
                         lo: u128::MAX,
+                        hi: u128::MAX,
                     },
                 ),
             ]),
@@ -1132,37 +1138,37 @@ fn secp256k1_get_xy() {
         syscall_handler.secp256k1_get_xy.0,
         [
             Secp256k1Point {
-                x: U256 { hi: 0, lo: 0 },
-                y: U256 { hi: 0, lo: 0 },
+                x: U256 { lo: 0, hi: 0 },
+                y: U256 { lo: 0, hi: 0 },
             },
             Secp256k1Point {
                 x: U256 {
-                    hi: 0,
-                    lo: u128::MAX,
+                    lo: 0,
+                    hi: u128::MAX,
                 },
                 y: U256 {
-                    hi: u128::MAX,
-                    lo: 0,
+                    lo: u128::MAX,
+                    hi: 0,
                 },
             },
             Secp256k1Point {
                 x: U256 {
-                    hi: u128::MAX,
-                    lo: 0,
+                    lo: u128::MAX,
+                    hi: 0,
                 },
                 y: U256 {
-                    hi: 0,
-                    lo: u128::MAX,
+                    lo: 0,
+                    hi: u128::MAX,
                 },
             },
             Secp256k1Point {
                 x: U256 {
-                    hi: u128::MAX,
                     lo: u128::MAX,
+                    hi: u128::MAX,
                 },
                 y: U256 {
-                    hi: u128::MAX,
                     lo: u128::MAX,
+                    hi: u128::MAX,
                 },
             },
         ],
@@ -1178,17 +1184,17 @@ fn secp256r1_new() {
             VecDeque::from([
                 None,
                 Some(Secp256r1Point {
-                    x: U256 { hi: 0, lo: 0 },
-                    y: U256 { hi: 0, lo: 0 },
+                    x: U256 { lo: 0, hi: 0 },
+                    y: U256 { lo: 0, hi: 0 },
                 }),
                 Some(Secp256r1Point {
                     x: U256 {
-                        hi: u128::MAX,
                         lo: u128::MAX,
+                        hi: u128::MAX,
                     },
                     y: U256 {
-                        hi: u128::MAX,
                         lo: u128::MAX,
+                        hi: u128::MAX,
                     },
                 }),
             ]),
@@ -1295,25 +1301,25 @@ fn secp256r1_new() {
     assert_eq!(
         syscall_handler.secp256r1_new.0,
         [
-            (U256 { hi: 0, lo: 0 }, U256 { hi: 0, lo: 0 }),
+            (U256 { lo: 0, hi: 0 }, U256 { lo: 0, hi: 0 }),
             (
                 U256 {
-                    hi: u128::MAX,
-                    lo: 0
+                    lo: u128::MAX,
+                    hi: 0
                 },
                 U256 {
-                    hi: 0,
-                    lo: u128::MAX
+                    lo: 0,
+                    hi: u128::MAX
                 }
             ),
             (
                 U256 {
-                    hi: u128::MAX,
-                    lo: u128::MAX
+                    lo: u128::MAX,
+                    hi: u128::MAX
                 },
                 U256 {
-                    hi: u128::MAX,
-                    lo: u128::MAX
+                    lo: u128::MAX,
+                    hi: u128::MAX
                 }
             ),
         ],
@@ -1328,27 +1334,27 @@ fn secp256r1_add() {
             VecDeque::from([]),
             VecDeque::from([
                 Secp256r1Point {
-                    x: U256 { hi: 0, lo: 0 },
-                    y: U256 { hi: 0, lo: 0 },
+                    x: U256 { lo: 0, hi: 0 },
+                    y: U256 { lo: 0, hi: 0 },
                 },
                 Secp256r1Point {
                     x: U256 {
-                        hi: u128::MAX,
-                        lo: 0,
+                        lo: u128::MAX,
+                        hi: 0,
                     },
                     y: U256 {
-                        hi: 0,
-                        lo: u128::MAX,
+                        lo: 0,
+                        hi: u128::MAX,
                     },
                 },
                 Secp256r1Point {
                     x: U256 {
-                        hi: u128::MAX,
                         lo: u128::MAX,
+                        hi: u128::MAX,
                     },
                     y: U256 {
-                        hi: u128::MAX,
                         lo: u128::MAX,
+                        hi: u128::MAX,
                     },
                 },
             ]),
@@ -1445,55 +1451,55 @@ fn secp256r1_add() {
         [
             (
                 Secp256r1Point {
-                    x: U256 { hi: 0, lo: 0 },
-                    y: U256 { hi: 0, lo: 0 },
+                    x: U256 { lo: 0, hi: 0 },
+                    y: U256 { lo: 0, hi: 0 },
                 },
                 Secp256r1Point {
-                    x: U256 { hi: 0, lo: 0 },
-                    y: U256 { hi: 0, lo: 0 },
+                    x: U256 { lo: 0, hi: 0 },
+                    y: U256 { lo: 0, hi: 0 },
                 },
             ),
             (
                 Secp256r1Point {
                     x: U256 {
-                        hi: u128::MAX,
-                        lo: 0
+                        lo: u128::MAX,
+                        hi: 0
                     },
                     y: U256 {
-                        hi: 0,
-                        lo: u128::MAX
+                        lo: 0,
+                        hi: u128::MAX
                     },
                 },
                 Secp256r1Point {
                     x: U256 {
-                        hi: 0,
-                        lo: u128::MAX
+                        lo: 0,
+                        hi: u128::MAX
                     },
                     y: U256 {
-                        hi: u128::MAX,
-                        lo: 0
+                        lo: u128::MAX,
+                        hi: 0
                     },
                 },
             ),
             (
                 Secp256r1Point {
                     x: U256 {
-                        hi: u128::MAX,
-                        lo: u128::MAX
+                        lo: u128::MAX,
+                        hi: u128::MAX
                     },
                     y: U256 {
-                        hi: u128::MAX,
-                        lo: u128::MAX
+                        lo: u128::MAX,
+                        hi: u128::MAX
                     },
                 },
                 Secp256r1Point {
                     x: U256 {
-                        hi: u128::MAX,
-                        lo: u128::MAX
+                        lo: u128::MAX,
+                        hi: u128::MAX
                     },
                     y: U256 {
-                        hi: u128::MAX,
-                        lo: u128::MAX
+                        lo: u128::MAX,
+                        hi: u128::MAX
                     },
                 },
             ),
@@ -1509,27 +1515,27 @@ fn secp256r1_mul() {
             VecDeque::from([]),
             VecDeque::from([
                 Secp256r1Point {
-                    x: U256 { hi: 0, lo: 0 },
-                    y: U256 { hi: 0, lo: 0 },
+                    x: U256 { lo: 0, hi: 0 },
+                    y: U256 { lo: 0, hi: 0 },
                 },
                 Secp256r1Point {
                     x: U256 {
-                        hi: u128::MAX,
-                        lo: 0,
+                        lo: u128::MAX,
+                        hi: 0,
                     },
                     y: U256 {
-                        hi: 0,
-                        lo: u128::MAX,
+                        lo: 0,
+                        hi: u128::MAX,
                     },
                 },
                 Secp256r1Point {
                     x: U256 {
-                        hi: u128::MAX,
                         lo: u128::MAX,
+                        hi: u128::MAX,
                     },
                     y: U256 {
-                        hi: u128::MAX,
                         lo: u128::MAX,
+                        hi: u128::MAX,
                     },
                 },
             ]),
@@ -1626,41 +1632,41 @@ fn secp256r1_mul() {
         [
             (
                 Secp256r1Point {
-                    x: U256 { hi: 0, lo: 0 },
-                    y: U256 { hi: 0, lo: 0 },
+                    x: U256 { lo: 0, hi: 0 },
+                    y: U256 { lo: 0, hi: 0 },
                 },
-                U256 { hi: 0, lo: 0 },
+                U256 { lo: 0, hi: 0 },
             ),
             (
                 Secp256r1Point {
                     x: U256 {
-                        hi: u128::MAX,
-                        lo: 0
+                        lo: u128::MAX,
+                        hi: 0
                     },
                     y: U256 {
-                        hi: 0,
-                        lo: u128::MAX,
+                        lo: 0,
+                        hi: u128::MAX,
                     },
                 },
                 U256 {
-                    hi: u128::MAX,
-                    lo: 0,
+                    lo: u128::MAX,
+                    hi: 0,
                 },
             ),
             (
                 Secp256r1Point {
                     x: U256 {
-                        hi: 0,
-                        lo: u128::MAX,
+                        lo: 0,
+                        hi: u128::MAX,
                     },
                     y: U256 {
-                        hi: u128::MAX,
-                        lo: 0,
+                        lo: u128::MAX,
+                        hi: 0,
                     },
                 },
                 U256 {
-                    hi: 0,
-                    lo: u128::MAX,
+                    lo: 0,
+                    hi: u128::MAX,
                 },
             ),
         ],
@@ -1676,27 +1682,27 @@ fn secp256r1_get_point_from_x() {
             VecDeque::from([
                 None,
                 Some(Secp256r1Point {
-                    x: U256 { hi: 0, lo: 0 },
-                    y: U256 { hi: 0, lo: 0 },
+                    x: U256 { lo: 0, hi: 0 },
+                    y: U256 { lo: 0, hi: 0 },
                 }),
                 Some(Secp256r1Point {
                     x: U256 {
-                        hi: 0,
-                        lo: u128::MAX,
+                        lo: 0,
+                        hi: u128::MAX,
                     },
                     y: U256 {
-                        hi: u128::MAX,
-                        lo: 0,
+                        lo: u128::MAX,
+                        hi: 0,
                     },
                 }),
                 Some(Secp256r1Point {
                     x: U256 {
-                        hi: u128::MAX,
-                        lo: 0,
+                        lo: u128::MAX,
+                        hi: 0,
                     },
                     y: U256 {
-                        hi: 0,
-                        lo: u128::MAX,
+                        lo: 0,
+                        hi: u128::MAX,
                     },
                 }),
             ]),
@@ -1851,25 +1857,25 @@ fn secp256r1_get_point_from_x() {
     assert_eq!(
         syscall_handler.secp256r1_get_point_from_x.0,
         [
-            (U256 { hi: 0, lo: 0 }, false),
+            (U256 { lo: 0, hi: 0 }, false),
             (
                 U256 {
-                    hi: 0,
-                    lo: u128::MAX,
+                    lo: 0,
+                    hi: u128::MAX,
                 },
                 true,
             ),
             (
                 U256 {
-                    hi: u128::MAX,
-                    lo: 0,
+                    lo: u128::MAX,
+                    hi: 0,
                 },
                 false,
             ),
             (
                 U256 {
-                    hi: u128::MAX,
                     lo: u128::MAX,
+                    hi: u128::MAX,
                 },
                 true,
             ),
@@ -1884,35 +1890,35 @@ fn secp256r1_get_xy() {
         secp256r1_get_xy: (
             VecDeque::from([]),
             VecDeque::from([
-                (U256 { hi: 0, lo: 0 }, U256 { hi: 0, lo: 0 }),
+                (U256 { lo: 0, hi: 0 }, U256 { lo: 0, hi: 0 }),
                 (
                     U256 {
-                        hi: 0,
-                        lo: u128::MAX,
-                    },
-                    U256 {
-                        hi: u128::MAX,
                         lo: 0,
-                    },
-                ),
-                (
-                    U256 {
                         hi: u128::MAX,
-                        lo: 0,
                     },
                     U256 {
-                        hi: 0,
                         lo: u128::MAX,
+                        hi: 0,
                     },
                 ),
                 (
                     U256 {
-                        hi: u128::MAX,
                         lo: u128::MAX,
+                        hi: 0,
                     },
                     U256 {
+                        lo: 0,
                         hi: u128::MAX,
+                    },
+                ),
+                (
+                    U256 {
                         lo: u128::MAX,
+                        hi: u128::MAX,
+                    },
+                    U256 {
+                        lo: u128::MAX,
+                        hi: u128::MAX,
                     },
                 ),
             ]),
@@ -2048,40 +2054,493 @@ fn secp256r1_get_xy() {
         syscall_handler.secp256r1_get_xy.0,
         [
             Secp256r1Point {
-                x: U256 { hi: 0, lo: 0 },
-                y: U256 { hi: 0, lo: 0 },
+                x: U256 { lo: 0, hi: 0 },
+                y: U256 { lo: 0, hi: 0 },
             },
             Secp256r1Point {
                 x: U256 {
-                    hi: 0,
-                    lo: u128::MAX,
+                    lo: 0,
+                    hi: u128::MAX,
                 },
                 y: U256 {
-                    hi: u128::MAX,
-                    lo: 0,
+                    lo: u128::MAX,
+                    hi: 0,
                 },
             },
             Secp256r1Point {
                 x: U256 {
-                    hi: u128::MAX,
-                    lo: 0,
+                    lo: u128::MAX,
+                    hi: 0,
                 },
                 y: U256 {
-                    hi: 0,
-                    lo: u128::MAX,
+                    lo: 0,
+                    hi: u128::MAX,
                 },
             },
             Secp256r1Point {
                 x: U256 {
-                    hi: u128::MAX,
                     lo: u128::MAX,
+                    hi: u128::MAX,
                 },
                 y: U256 {
-                    hi: u128::MAX,
                     lo: u128::MAX,
+                    hi: u128::MAX,
                 },
             },
         ],
     );
     assert!(syscall_handler.secp256r1_get_xy.1.is_empty());
 }
+
+#[test]
+fn secp256k1_default_impl() {
+    // The secp256k1 generator point.
+    let x = (
+        0x029BFCDB2DCE28D959F2815B16F81798,
+        0x79BE667EF9DCBBAC55A06295CE870B07,
+    );
+    let y = (
+        0xFD17B448A68554199C47D08FFB10D4B8,
+        0x483ADA7726A3C4655DA4FBFC0E1108A8,
+    );
+
+    let result = run_native_program(
+        &SECP256_PROGRAM,
+        "secp256k1_new",
+        &[
+            JitValue::Struct {
+                fields: vec![JitValue::Uint128(x.0), JitValue::Uint128(x.1)],
+                debug_name: None,
+            },
+            JitValue::Struct {
+                fields: vec![JitValue::Uint128(y.0), JitValue::Uint128(y.1)],
+                debug_name: None,
+            },
+        ],
+        Some(u128::MAX),
+        Some(DummySyscallHandler),
+    );
+    assert_eq!(
+        result.return_value,
+        JitValue::Enum {
+            tag: 0,
+            value: Box::new(JitValue::Enum {
+                tag: 0,
+                value: Box::new(JitValue::Secp256K1Point { x, y }),
+                debug_name: None,
+            }),
+            debug_name: None,
+        },
+    );
+
+    let result = run_native_program(
+        &SECP256_PROGRAM,
+        "secp256k1_get_point_from_x",
+        &[
+            JitValue::Struct {
+                fields: vec![JitValue::Uint128(x.0), JitValue::Uint128(x.1)],
+                debug_name: None,
+            },
+            JitValue::Enum {
+                tag: 0,
+                value: Box::new(JitValue::Struct {
+                    fields: vec![],
+                    debug_name: None,
+                }),
+                debug_name: None,
+            },
+        ],
+        Some(u128::MAX),
+        Some(DummySyscallHandler),
+    );
+    assert_eq!(
+        result.return_value,
+        JitValue::Enum {
+            tag: 0,
+            value: Box::new(JitValue::Enum {
+                tag: 0,
+                value: Box::new(JitValue::Secp256K1Point { x, y }),
+                debug_name: None,
+            }),
+            debug_name: None,
+        },
+    );
+
+    // A point not on the curve.
+    let result = run_native_program(
+        &SECP256_PROGRAM,
+        "secp256k1_new",
+        &[
+            JitValue::Struct {
+                fields: vec![JitValue::Uint128(x.0), JitValue::Uint128(x.1)],
+                debug_name: None,
+            },
+            JitValue::Struct {
+                fields: vec![JitValue::Uint128(x.0), JitValue::Uint128(x.1)],
+                debug_name: None,
+            },
+        ],
+        Some(u128::MAX),
+        Some(DummySyscallHandler),
+    );
+    assert_eq!(
+        result.return_value,
+        JitValue::Enum {
+            tag: 0,
+            value: Box::new(JitValue::Enum {
+                tag: 1,
+                value: Box::new(JitValue::Struct {
+                    fields: vec![],
+                    debug_name: None
+                }),
+                debug_name: None,
+            }),
+            debug_name: None,
+        },
+    );
+}
+
+#[test]
+fn secp256r1_default_impl() {
+    // The secp256r1 generator point.
+    let x = (
+        0x77037D812DEB33A0F4A13945D898C296,
+        0x6B17D1F2E12C4247F8BCE6E563A440F2,
+    );
+    let y = (
+        0x2BCE33576B315ECECBB6406837BF51F5,
+        0x4FE342E2FE1A7F9B8EE7EB4A7C0F9E16,
+    );
+
+    let result = run_native_program(
+        &SECP256_PROGRAM,
+        "secp256r1_new",
+        &[
+            JitValue::Struct {
+                fields: vec![JitValue::Uint128(x.0), JitValue::Uint128(x.1)],
+                debug_name: None,
+            },
+            JitValue::Struct {
+                fields: vec![JitValue::Uint128(y.0), JitValue::Uint128(y.1)],
+                debug_name: None,
+            },
+        ],
+        Some(u128::MAX),
+        Some(DummySyscallHandler),
+    );
+    assert_eq!(
+        result.return_value,
+        JitValue::Enum {
+            tag: 0,
+            value: Box::new(JitValue::Enum {
+                tag: 0,
+                value: Box::new(JitValue::Secp256R1Point { x, y }),
+                debug_name: None,
+            }),
+            debug_name: None,
+        },
+    );
+
+    // The generator's y coordinate is odd.
+    let result = run_native_program(
+        &SECP256_PROGRAM,
+        "secp256r1_get_point_from_x",
+        &[
+            JitValue::Struct {
+                fields: vec![JitValue::Uint128(x.0), JitValue::Uint128(x.1)],
+                debug_name: None,
+            },
+            JitValue::Enum {
+                tag: 1,
+                value: Box::new(JitValue::Struct {
+                    fields: vec![],
+                    debug_name: None,
+                }),
+                debug_name: None,
+            },
+        ],
+        Some(u128::MAX),
+        Some(DummySyscallHandler),
+    );
+    assert_eq!(
+        result.return_value,
+        JitValue::Enum {
+            tag: 0,
+            value: Box::new(JitValue::Enum {
+                tag: 0,
+                value: Box::new(JitValue::Secp256R1Point { x, y }),
+                debug_name: None,
+            }),
+            debug_name: None,
+        },
+    );
+
+    // A point not on the curve.
+    let result = run_native_program(
+        &SECP256_PROGRAM,
+        "secp256r1_new",
+        &[
+            JitValue::Struct {
+                fields: vec![JitValue::Uint128(x.0), JitValue::Uint128(x.1)],
+                debug_name: None,
+            },
+            JitValue::Struct {
+                fields: vec![JitValue::Uint128(x.0), JitValue::Uint128(x.1)],
+                debug_name: None,
+            },
+        ],
+        Some(u128::MAX),
+        Some(DummySyscallHandler),
+    );
+    assert_eq!(
+        result.return_value,
+        JitValue::Enum {
+            tag: 0,
+            value: Box::new(JitValue::Enum {
+                tag: 1,
+                value: Box::new(JitValue::Struct {
+                    fields: vec![],
+                    debug_name: None
+                }),
+                debug_name: None,
+            }),
+            debug_name: None,
+        },
+    );
+}
+
+#[test]
+fn secp256_new_matches_vm() {
+    let points = [
+        // The point at infinity.
+        ((0, 0), (0, 0)),
+        // Not on either curve.
+        ((1, 0), (1, 0)),
+        ((0, 0), (1, 0)),
+        // The secp256k1 generator.
+        (
+            (
+                0x029BFCDB2DCE28D959F2815B16F81798,
+                0x79BE667EF9DCBBAC55A06295CE870B07,
+            ),
+            (
+                0xFD17B448A68554199C47D08FFB10D4B8,
+                0x483ADA7726A3C4655DA4FBFC0E1108A8,
+            ),
+        ),
+        // The secp256r1 generator.
+        (
+            (
+                0x77037D812DEB33A0F4A13945D898C296,
+                0x6B17D1F2E12C4247F8BCE6E563A440F2,
+            ),
+            (
+                0x2BCE33576B315ECECBB6406837BF51F5,
+                0x4FE342E2FE1A7F9B8EE7EB4A7C0F9E16,
+            ),
+        ),
+    ];
+
+    for entry_point in ["secp256k1_new_is_some", "secp256r1_new_is_some"] {
+        for (x, y) in points {
+            let result_vm = run_vm_program(
+                &SECP256_PROGRAM,
+                entry_point,
+                &[
+                    Arg::Value(DeprecatedFelt::from(x.0)),
+                    Arg::Value(DeprecatedFelt::from(x.1)),
+                    Arg::Value(DeprecatedFelt::from(y.0)),
+                    Arg::Value(DeprecatedFelt::from(y.1)),
+                ],
+                Some(DEFAULT_GAS as usize),
+            )
+            .unwrap();
+            let result_native = run_native_program(
+                &SECP256_PROGRAM,
+                entry_point,
+                &[
+                    JitValue::Struct {
+                        fields: vec![JitValue::Uint128(x.0), JitValue::Uint128(x.1)],
+                        debug_name: None,
+                    },
+                    JitValue::Struct {
+                        fields: vec![JitValue::Uint128(y.0), JitValue::Uint128(y.1)],
+                        debug_name: None,
+                    },
+                ],
+                Some(DEFAULT_GAS as u128),
+                Some(DummySyscallHandler),
+            );
+
+            compare_outputs(
+                &SECP256_PROGRAM.1,
+                &SECP256_PROGRAM.2.find_function(entry_point).unwrap().id,
+                &result_vm,
+                &result_native,
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// Run an entry point taking `u256` arguments natively (with the default syscall implementations)
+/// and in the VM, and check that both return the same value.
+fn compare_u256_entry_point_with_vm(entry_point: &str, args: &[(u128, u128)]) {
+    let result_vm = run_vm_program(
+        &SECP256_PROGRAM,
+        entry_point,
+        &args
+            .iter()
+            .flat_map(|(lo, hi)| {
+                [
+                    Arg::Value(DeprecatedFelt::from(*lo)),
+                    Arg::Value(DeprecatedFelt::from(*hi)),
+                ]
+            })
+            .collect::<Vec<_>>(),
+        Some(DEFAULT_GAS as usize),
+    )
+    .unwrap();
+    let result_native = run_native_program(
+        &SECP256_PROGRAM,
+        entry_point,
+        &args
+            .iter()
+            .map(|(lo, hi)| JitValue::Struct {
+                fields: vec![JitValue::Uint128(*lo), JitValue::Uint128(*hi)],
+                debug_name: None,
+            })
+            .collect::<Vec<_>>(),
+        Some(DEFAULT_GAS as u128),
+        Some(DummySyscallHandler),
+    );
+
+    compare_outputs(
+        &SECP256_PROGRAM.1,
+        &SECP256_PROGRAM.2.find_function(entry_point).unwrap().id,
+        &result_vm,
+        &result_native,
+    )
+    .unwrap();
+}
+
+#[test]
+fn secp256_add_mul_get_xy_match_vm() {
+    type Point = ((u128, u128), (u128, u128));
+
+    const INFINITY: Point = ((0, 0), (0, 0));
+    // The generator, its double and its negation on each curve.
+    const K1_POINTS: [Point; 3] = [
+        (
+            (
+                0x029BFCDB2DCE28D959F2815B16F81798,
+                0x79BE667EF9DCBBAC55A06295CE870B07,
+            ),
+            (
+                0xFD17B448A68554199C47D08FFB10D4B8,
+                0x483ADA7726A3C4655DA4FBFC0E1108A8,
+            ),
+        ),
+        (
+            (
+                0x5C778E4B8CEF3CA7ABAC09B95C709EE5,
+                0xC6047F9441ED7D6D3045406E95C07CD8,
+            ),
+            (
+                0xF7F632653266D0E1236431A950CFE52A,
+                0x1AE168FEA63DC339A3C58419466CEAEE,
+            ),
+        ),
+        (
+            (
+                0x029BFCDB2DCE28D959F2815B16F81798,
+                0x79BE667EF9DCBBAC55A06295CE870B07,
+            ),
+            (
+                0x02E84BB7597AABE663B82F6F04EF2777,
+                0xB7C52588D95C3B9AA25B0403F1EEF757,
+            ),
+        ),
+    ];
+    const R1_POINTS: [Point; 3] = [
+        (
+            (
+                0x77037D812DEB33A0F4A13945D898C296,
+                0x6B17D1F2E12C4247F8BCE6E563A440F2,
+            ),
+            (
+                0x2BCE33576B315ECECBB6406837BF51F5,
+                0x4FE342E2FE1A7F9B8EE7EB4A7C0F9E16,
+            ),
+        ),
+        (
+            (
+                0xC08969E277F21B35A60B48FC47669978,
+                0x7CF27B188D034F7E8A52380304B51AC3,
+            ),
+            (
+                0xBA7DADE63CE982299E04B79D227873D1,
+                0x07775510DB8ED040293D9AC69F7430DB,
+            ),
+        ),
+        (
+            (
+                0x77037D812DEB33A0F4A13945D898C296,
+                0x6B17D1F2E12C4247F8BCE6E563A440F2,
+            ),
+            (
+                0xD431CCA994CEA1313449BF97C840AE0A,
+                0xB01CBD1C01E58065711814B583F061E9,
+            ),
+        ),
+    ];
+
+    for (curve, points, order) in [
+        (
+            "secp256k1",
+            K1_POINTS,
+            (
+                0xBAAEDCE6AF48A03BBFD25E8CD0364141,
+                0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFE,
+            ),
+        ),
+        (
+            "secp256r1",
+            R1_POINTS,
+            (
+                0xBCE6FAADA7179E84F3B9CAC2FC632551,
+                0xFFFFFFFF00000000FFFFFFFFFFFFFFFF,
+            ),
+        ),
+    ] {
+        // Includes doubling, adding a point to its negation and adding the point at infinity.
+        let add_entry_point = format!("{curve}_add_xy");
+        for (p0, p1) in [
+            (points[0], points[1]),
+            (points[0], points[0]),
+            (points[0], points[2]),
+            (points[0], INFINITY),
+            (INFINITY, points[1]),
+            (INFINITY, INFINITY),
+        ] {
+            compare_u256_entry_point_with_vm(&add_entry_point, &[p0.0, p0.1, p1.0, p1.1]);
+        }
+
+        // Includes the zero scalar, the order of the group (and its predecessor), scalars above
+        // the order and multiplying the point at infinity.
+        let mul_entry_point = format!("{curve}_mul_xy");
+        for (p, scalar) in [
+            (points[0], (0, 0)),
+            (points[0], (1, 0)),
+            (points[0], (2, 0)),
+            (points[1], (0x1234567890ABCDEF, 0xFEDCBA0987654321)),
+            (points[0], (order.0 - 1, order.1)),
+            (points[0], order),
+            (points[0], (u128::MAX, u128::MAX)),
+            (INFINITY, (0, 0)),
+            (INFINITY, (3, 0)),
+        ] {
+            compare_u256_entry_point_with_vm(&mul_entry_point, &[p.0, p.1, scalar]);
+        }
+    }
+}
@@ -0,0 +1,483 @@
+use cairo_lang_compiler::CompilerConfig;
+use cairo_lang_starknet::{
+    casm_contract_class::CasmContractClass,
+    contract::starknet_keccak,
+    contract_class::{compile_path, ContractClass},
+};
+#[cfg(feature = "with-serde")]
+use cairo_native::starknet::{AbiValue, ContractAbi};
+use cairo_native::{
+    cache::JitProgramCache,
+    context::NativeContext,
+    executor::JitNativeExecutor,
+    sierra_version::SierraVersionCheck,
+    starknet::{
+        calculate_class_hash, calculate_compiled_class_hash, calculate_contract_address,
+        InMemoryStarknetState, StarknetSyscallHandler, StarknetTestingHandler,
+    },
+    OptLevel,
+};
+use lazy_static::lazy_static;
+use starknet_types_core::{
+    felt::Felt,
+    hash::{Poseidon, StarkHash},
+};
+use std::path::Path;
+
+lazy_static! {
+    static ref COUNTER_CONTRACT: ContractClass =
+        compile_contract("tests/tests/starknet/contracts/test_counter.cairo");
+    static ref COUNTER_FACTORY_CONTRACT: ContractClass =
+        compile_contract("tests/tests/starknet/contracts/test_counter_factory.cairo");
+}
+
+fn compile_contract(path: &str) -> ContractClass {
+    compile_path(
+        Path::new(path),
+        None,
+        CompilerConfig {
+            replace_ids: true,
+            ..Default::default()
+        },
+    )
+    .unwrap()
+}
+
+fn selector(name: &str) -> Felt {
+    Felt::from_bytes_be_slice(&starknet_keccak(name.as_bytes()).to_bytes_be())
+}
+
+fn short_string(value: &str) -> Felt {
+    Felt::from_bytes_be_slice(value.as_bytes())
+}
+
+/// Create a state with the counter (class hash 1) and counter factory (class hash 2) classes.
+fn declare_counter_classes(context: &NativeContext) -> InMemoryStarknetState<'_> {
+    let mut state = InMemoryStarknetState::new(JitProgramCache::new(context).into());
+    state
+        .declare(Felt::from(1), &COUNTER_CONTRACT, OptLevel::default())
+        .unwrap();
+    state
+        .declare(
+            Felt::from(2),
+            &COUNTER_FACTORY_CONTRACT,
+            OptLevel::default(),
+        )
+        .unwrap();
+    state
+}
+
+#[test]
+fn deploy_and_invoke() {
+    let context = NativeContext::new();
+    let mut state = declare_counter_classes(&context);
+    let mut gas = u128::MAX;
+
+    let counter_hash = Felt::from(1);
+    let (counter, return_values) = state
+        .deploy_contract(counter_hash, Felt::ZERO, &[10.into()], Felt::ZERO, &mut gas)
+        .unwrap();
+    assert!(return_values.is_empty());
+    assert_eq!(
+        counter,
+        calculate_contract_address(Felt::ZERO, counter_hash, &[10.into()], Felt::ZERO)
+    );
+    assert_eq!(state.class_hash_at(counter), Some(counter_hash));
+    assert_eq!(
+        state.deploy_contract(counter_hash, Felt::ZERO, &[10.into()], Felt::ZERO, &mut gas),
+        Err(vec![short_string("CONTRACT_ADDRESS_UNAVAILABLE")])
+    );
+
+    assert_eq!(
+        state.invoke_contract(counter, selector("increase"), &[5.into()], &mut gas),
+        Ok(vec![15.into()])
+    );
+    assert_eq!(
+        state.invoke_contract(counter, selector("get"), &[], &mut gas),
+        Ok(vec![15.into()])
+    );
+    assert_eq!(state.storage_at(counter, selector("value")), 15.into());
+    assert_eq!(
+        state
+            .events(counter)
+            .map(|event| event.data.clone())
+            .collect::<Vec<_>>(),
+        [vec![Felt::from(5)]]
+    );
+
+    assert_eq!(
+        state.invoke_contract(counter, selector("increase"), &[Felt::ZERO], &mut gas),
+        Err(vec![
+            short_string("zero amount"),
+            short_string("ENTRYPOINT_FAILED")
+        ])
+    );
+    assert_eq!(
+        state.invoke_contract(counter, selector("decrease"), &[], &mut gas),
+        Err(vec![short_string("ENTRYPOINT_NOT_FOUND")])
+    );
+}
+
+#[test]
+fn nested_calls() {
+    let context = NativeContext::new();
+    let mut state = declare_counter_classes(&context);
+    let mut gas = u128::MAX;
+
+    let (counter_hash, factory_hash) = (Felt::from(1), Felt::from(2));
+    let (factory, _) = state
+        .deploy_contract(factory_hash, Felt::ZERO, &[], Felt::ZERO, &mut gas)
+        .unwrap();
+
+    let counter = state
+        .invoke_contract(
+            factory,
+            selector("deploy_counter"),
+            &[counter_hash, 3.into()],
+            &mut gas,
+        )
+        .unwrap()[0];
+    assert_eq!(
+        counter,
+        calculate_contract_address(Felt::ZERO, counter_hash, &[3.into()], factory)
+    );
+
+    assert_eq!(
+        state.invoke_contract(
+            factory,
+            selector("increase_counter"),
+            &[counter, 4.into()],
+            &mut gas
+        ),
+        Ok(vec![7.into()])
+    );
+    assert_eq!(state.storage_at(counter, selector("value")), 7.into());
+
+    // Library calls run in the context of the caller.
+    assert_eq!(
+        state.invoke_contract(
+            factory,
+            selector("increase_with_library"),
+            &[counter_hash, 2.into()],
+            &mut gas
+        ),
+        Ok(vec![2.into()])
+    );
+    assert_eq!(state.storage_at(factory, selector("value")), 2.into());
+    assert_eq!(state.storage_at(counter, selector("value")), 7.into());
+    assert_eq!(state.events(factory).count(), 1);
+}
+
+#[test]
+fn failed_calls_are_reverted() {
+    let context = NativeContext::new();
+    let mut state = declare_counter_classes(&context);
+    let mut gas = u128::MAX;
+
+    let (counter_hash, factory_hash) = (Felt::from(1), Felt::from(2));
+    let (factory, _) = state
+        .deploy_contract(factory_hash, Felt::ZERO, &[], Felt::ZERO, &mut gas)
+        .unwrap();
+    let (counter, _) = state
+        .deploy_contract(counter_hash, Felt::ZERO, &[1.into()], factory, &mut gas)
+        .unwrap();
+
+    // A failed top-level call.
+    assert_eq!(
+        state.invoke_contract(
+            counter,
+            selector("increase_and_fail"),
+            &[2.into()],
+            &mut gas
+        ),
+        Err(vec![
+            short_string("increase failed"),
+            short_string("ENTRYPOINT_FAILED")
+        ])
+    );
+    assert_eq!(state.storage_at(counter, selector("value")), 1.into());
+    assert_eq!(state.events(counter).count(), 0);
+
+    // A failed nested call, whose caller succeeds.
+    assert_eq!(
+        state.invoke_contract(
+            factory,
+            selector("try_increase_and_fail"),
+            &[counter, 2.into()],
+            &mut gas
+        ),
+        Ok(vec![Felt::ZERO])
+    );
+    assert_eq!(state.storage_at(counter, selector("value")), 1.into());
+    assert_eq!(state.events(counter).count(), 0);
+
+    // A failed constructor.
+    assert_eq!(
+        state.deploy_contract(counter_hash, Felt::ONE, &[], Felt::ZERO, &mut gas),
+        Err(vec![
+            short_string("Failed to deserialize param #1"),
+            short_string("CONSTRUCTOR_FAILED")
+        ])
+    );
+    assert_eq!(
+        state.class_hash_at(calculate_contract_address(
+            Felt::ONE,
+            counter_hash,
+            &[],
+            Felt::ZERO
+        )),
+        None
+    );
+}
+
+#[test]
+fn cheatcodes() {
+    let context = NativeContext::new();
+    let mut state = declare_counter_classes(&context);
+    let mut gas = u128::MAX;
+
+    let (counter, _) = state
+        .deploy_contract(Felt::from(1), Felt::ZERO, &[1.into()], Felt::ZERO, &mut gas)
+        .unwrap();
+    state
+        .invoke_contract(counter, selector("increase"), &[2.into()], &mut gas)
+        .unwrap();
+
+    // The event is popped as `[keys.len, keys.., data.len, data..]`.
+    assert_eq!(
+        state.cheatcode(short_string("pop_log"), &[counter]),
+        Some(vec![1.into(), selector("Increased"), 1.into(), 2.into()])
+    );
+    assert_eq!(
+        state.cheatcode(short_string("pop_log"), &[counter]),
+        Some(Vec::new())
+    );
+    assert_eq!(state.events(counter).count(), 0);
+
+    assert_eq!(
+        state.cheatcode(short_string("set_block_number"), &[7.into()]),
+        Some(Vec::new())
+    );
+    assert_eq!(
+        state
+            .get_execution_info_v2(&mut gas)
+            .unwrap()
+            .block_info
+            .block_number,
+        7
+    );
+
+    assert_eq!(state.cheatcode(short_string("set_block_number"), &[]), None);
+    assert_eq!(state.cheatcode(short_string("set_nothing"), &[]), None);
+}
+
+#[test]
+fn get_class_hash_at_and_meta_tx_v0() {
+    let context = NativeContext::new();
+    let mut state = declare_counter_classes(&context);
+    let mut gas = u128::MAX;
+
+    let counter_hash = Felt::from(1);
+    let (counter, _) = state
+        .deploy_contract(counter_hash, Felt::ZERO, &[1.into()], Felt::ZERO, &mut gas)
+        .unwrap();
+
+    assert_eq!(state.get_class_hash_at(counter, &mut gas), Ok(counter_hash));
+    assert_eq!(state.get_class_hash_at(Felt::ONE, &mut gas), Ok(Felt::ZERO));
+
+    assert_eq!(
+        state.meta_tx_v0(
+            counter,
+            selector("increase"),
+            &[2.into()],
+            &[Felt::from(7)],
+            &mut gas
+        ),
+        Ok(vec![3.into()])
+    );
+    assert_eq!(
+        state.meta_tx_v0(counter, selector("decrease"), &[], &[], &mut gas),
+        Err(vec![short_string("ENTRYPOINT_NOT_FOUND")])
+    );
+
+    // The transaction info is restored after the call.
+    let exec_info = state.get_execution_info_v2(&mut gas).unwrap();
+    assert!(exec_info.tx_info.signature.is_empty());
+}
+
+#[test]
+fn failed_meta_tx_v0_is_reverted() {
+    let context = NativeContext::new();
+    let mut state = declare_counter_classes(&context);
+    let mut gas = u128::MAX;
+
+    let (counter, _) = state
+        .deploy_contract(Felt::from(1), Felt::ZERO, &[1.into()], Felt::ZERO, &mut gas)
+        .unwrap();
+
+    assert_eq!(
+        state.meta_tx_v0(
+            counter,
+            selector("increase_and_fail"),
+            &[2.into()],
+            &[Felt::from(7)],
+            &mut gas
+        ),
+        Err(vec![
+            short_string("increase failed"),
+            short_string("ENTRYPOINT_FAILED")
+        ])
+    );
+    assert_eq!(state.storage_at(counter, selector("value")), 1.into());
+    assert_eq!(state.events(counter).count(), 0);
+
+    let exec_info = state.get_execution_info_v2(&mut gas).unwrap();
+    assert!(exec_info.tx_info.signature.is_empty());
+}
+
+#[test]
+fn compile_contract_class() {
+    let context = NativeContext::new();
+    let class = context
+        .compile_contract_class(&COUNTER_CONTRACT, &SierraVersionCheck::default())
+        .unwrap();
+
+    assert!(class.entry_points.constructor.is_some());
+    assert_eq!(class.entry_points.external.len(), 3);
+    assert!(class.entry_points.l1_handler.is_empty());
+
+    // The debug info of the class names the program's functions.
+    let function_id = class
+        .entry_points
+        .external(selector("get"))
+        .unwrap()
+        .clone();
+    assert!(function_id.debug_name.is_some());
+    assert_eq!(class.entry_points.external(selector("decrease")), None);
+
+    let mut state = InMemoryStarknetState::new(JitProgramCache::new(&context).into());
+    let executor = JitNativeExecutor::from_native_module(class.module, OptLevel::default());
+    let result = executor
+        .invoke_contract_dynamic(&function_id, &[], Some(u128::MAX), &mut state)
+        .unwrap();
+    assert!(!result.failure_flag);
+    assert_eq!(result.return_values, [Felt::ZERO]);
+}
+
+#[cfg(feature = "with-serde")]
+#[test]
+fn abi_calldata() {
+    let context = NativeContext::new();
+    let mut state = declare_counter_classes(&context);
+    let mut gas = u128::MAX;
+
+    let abi = ContractAbi::from_contract_class(&COUNTER_CONTRACT)
+        .unwrap()
+        .unwrap();
+
+    let calldata = abi
+        .encode_calldata("constructor", &[AbiValue::Felt(10.into())])
+        .unwrap();
+    assert_eq!(
+        abi.decode_calldata("constructor", &calldata).unwrap(),
+        [("initial_value".to_string(), AbiValue::Felt(10.into()))]
+    );
+    let (counter, _) = state
+        .deploy_contract(Felt::from(1), Felt::ZERO, &calldata, Felt::ZERO, &mut gas)
+        .unwrap();
+
+    let calldata = abi
+        .encode_calldata("increase", &[AbiValue::Felt(5.into())])
+        .unwrap();
+    let output = state
+        .invoke_contract(counter, selector("increase"), &calldata, &mut gas)
+        .unwrap();
+    assert_eq!(
+        abi.decode_output("increase", &output).unwrap(),
+        [AbiValue::Felt(15.into())]
+    );
+}
+
+#[cfg(feature = "with-serde")]
+#[test]
+fn decoded_events() {
+    let context = NativeContext::new();
+    let mut state = declare_counter_classes(&context);
+    let mut gas = u128::MAX;
+
+    let (counter, _) = state
+        .deploy_contract(Felt::from(1), Felt::ZERO, &[1.into()], Felt::ZERO, &mut gas)
+        .unwrap();
+    state
+        .invoke_contract(counter, selector("increase"), &[5.into()], &mut gas)
+        .unwrap();
+
+    assert_eq!(
+        state.decoded_events(counter).unwrap(),
+        [AbiValue::Enum {
+            variant: "Increased".to_string(),
+            value: Box::new(AbiValue::Struct(vec![(
+                "amount".to_string(),
+                AbiValue::Felt(5.into())
+            )])),
+        }]
+    );
+}
+
+#[test]
+fn class_hashes() {
+    let abi = r#"[{"type": "function", "name": "increase"}]"#;
+
+    let counter_hash = calculate_class_hash(&COUNTER_CONTRACT, abi);
+    assert_eq!(calculate_class_hash(&COUNTER_CONTRACT, abi), counter_hash);
+    assert_ne!(
+        calculate_class_hash(&COUNTER_FACTORY_CONTRACT, abi),
+        counter_hash
+    );
+
+    // The declared ABI is part of the class hash, exactly as serialized.
+    assert_ne!(calculate_class_hash(&COUNTER_CONTRACT, ""), counter_hash);
+    assert_ne!(
+        calculate_class_hash(
+            &COUNTER_CONTRACT,
+            r#"[{"type":"function","name":"increase"}]"#
+        ),
+        counter_hash
+    );
+}
+
+#[test]
+fn compiled_class_hash() {
+    let casm_contract_class: CasmContractClass = serde_json::from_str(
+        r#"{
+            "prime": "0x800000000000011000000000000000000000000000000000000000000000001",
+            "compiler_version": "2.5.4",
+            "bytecode": ["0x1", "0x2"],
+            "hints": [],
+            "entry_points_by_type": {
+                "EXTERNAL": [{ "selector": "0x3", "offset": 1, "builtins": ["range_check"] }],
+                "L1_HANDLER": [],
+                "CONSTRUCTOR": []
+            }
+        }"#,
+    )
+    .unwrap();
+
+    // The selector, offset and builtins of each entry point, then the whole bytecode, are hashed
+    // with poseidon under the `'COMPILED_CLASS_V1'` prefix.
+    let external = Poseidon::hash_array(&[
+        Felt::from(3),
+        Felt::from(1),
+        Poseidon::hash_array(&[short_string("range_check")]),
+    ]);
+    assert_eq!(
+        calculate_compiled_class_hash(&casm_contract_class),
+        Poseidon::hash_array(&[
+            short_string("COMPILED_CLASS_V1"),
+            external,
+            Poseidon::hash_array(&[]),
+            Poseidon::hash_array(&[]),
+            Poseidon::hash_array(&[Felt::from(1), Felt::from(2)]),
+        ])
+    );
+}
@@ -2,9 +2,12 @@ use crate::common::{load_cairo_path, run_native_program};
 use cairo_lang_runner::SierraCasmRunner;
 use cairo_lang_sierra::program::Program;
 use cairo_native::{
+    cache::JitProgramCache,
+    context::NativeContext,
     starknet::{
-        BlockInfo, ExecutionInfo, ExecutionInfoV2, Secp256k1Point, Secp256r1Point,
-        StarknetSyscallHandler, SyscallResult, TxInfo, TxV2Info, U256,
+        BlockInfo, ExecutionInfo, ExecutionInfoV2, InMemoryStarknetState, Secp256k1Point,
+        Secp256r1Point, StarknetSyscallHandler, SyscallGasCosts, SyscallResult, TxInfo, TxV2Info,
+        U256,
     },
     values::JitValue,
 };
@@ -242,8 +245,8 @@ impl StarknetSyscallHandler for SyscallHandler {
 
     fn keccak(&mut self, _input: &[u64], _remaining_gas: &mut u128) -> SyscallResult<U256> {
         Ok(U256 {
-            hi: 330939983442938156232262046592599923289,
-            lo: 288102973244655531496349286021939642254,
+            lo: 330939983442938156232262046592599923289,
+            hi: 288102973244655531496349286021939642254,
         })
     }
 
@@ -699,6 +702,35 @@ fn storage_read() {
     );
 }
 
+#[test]
+fn storage_read_boxed_handler() {
+    let syscall_handler: Box<dyn StarknetSyscallHandler> = Box::new(SyscallHandler);
+    let result = run_native_program(
+        &SYSCALLS_PROGRAM,
+        "storage_read",
+        &[],
+        Some(u128::MAX),
+        Some(syscall_handler),
+    );
+
+    assert_eq_sorted!(
+        result.return_value,
+        JitValue::Enum {
+            tag: 0,
+            value: Box::new(JitValue::Struct {
+                fields: vec![
+                    JitValue::Felt252(Felt::from_dec_str(
+                        "1013181629378419652272218169322268188846114273878719855200100663863924329981",
+                    )
+                    .unwrap()),
+                ],
+                debug_name: None,
+            }),
+            debug_name: None,
+        },
+    );
+}
+
 #[test]
 fn storage_write() {
     let result = run_native_program(
@@ -748,6 +780,44 @@ fn emit_event() {
     );
 }
 
+#[test]
+fn emit_event_base_gas() {
+    let result = run_native_program(
+        &SYSCALLS_PROGRAM,
+        "emit_event",
+        &[],
+        Some(u128::MAX),
+        Some(SyscallHandler),
+    );
+
+    assert_eq!(
+        result.remaining_gas,
+        Some(u128::MAX - SyscallGasCosts::default().emit_event),
+    );
+}
+
+#[test]
+fn emit_event_out_of_gas() {
+    let result = run_native_program(
+        &SYSCALLS_PROGRAM,
+        "emit_event",
+        &[],
+        Some(SyscallGasCosts::default().emit_event - 1),
+        Some(SyscallHandler),
+    );
+
+    assert_eq_sorted!(
+        result.return_value,
+        JitValue::Enum {
+            tag: 1,
+            value: Box::new(JitValue::Array(vec![JitValue::Felt252(
+                Felt::from_bytes_be_slice(b"Syscall out of gas")
+            )])),
+            debug_name: None,
+        },
+    );
+}
+
 #[test]
 fn send_message_to_l1() {
     let result = run_native_program(
@@ -796,3 +866,53 @@ fn keccak() {
         },
     );
 }
+
+#[test]
+fn set_block_number_cheatcode() {
+    let context = NativeContext::new();
+    let state = InMemoryStarknetState::new(JitProgramCache::new(&context).into());
+    let result = run_native_program(
+        &SYSCALLS_PROGRAM,
+        "set_block_number",
+        &[],
+        Some(u128::MAX),
+        Some(state),
+    );
+
+    assert_eq_sorted!(
+        result.return_value,
+        JitValue::Enum {
+            tag: 0,
+            value: Box::new(JitValue::Struct {
+                fields: vec![JitValue::Uint64(7)],
+                debug_name: None,
+            }),
+            debug_name: None,
+        },
+    );
+}
+
+#[test]
+fn sha256_process_block_default() {
+    // The padded block of `"abc"`.
+    let mut block = [0; 16];
+    block[0] = 0x61626380;
+    block[15] = 24;
+
+    let mut state = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+    let mut gas = u128::MAX;
+    SyscallHandler
+        .sha256_process_block(&mut state, &block, &mut gas)
+        .unwrap();
+
+    assert_eq!(
+        state,
+        [
+            0xba7816bf, 0x8f01cfea, 0x414140de, 0x5dae2223, 0xb00361a3, 0x96177a9c, 0xb410ff61,
+            0xf20015ad,
+        ]
+    );
+}